@@ -0,0 +1,9 @@
+fn main() -> std::io::Result<()> {
+    std::env::set_var(
+        "PROTOC",
+        protoc_bin_vendored::protoc_bin_path().map_err(std::io::Error::other)?,
+    );
+    fusen_build::configure()
+        .compile_well_known_types()
+        .compile_protos(&["proto/greeter.proto"], &["proto"])
+}
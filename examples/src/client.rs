@@ -61,6 +61,14 @@ async fn main() {
         .sayHelloV2(ReqDto::default().str("world".to_string()))
         .await;
     info!("rev host msg : {:?}", res);
+    let res = client
+        .updateUser(
+            1,
+            "tenant-a".to_owned(),
+            ReqDto::default().str("fusen".to_owned()),
+        )
+        .await;
+    info!("rev host msg : {:?}", res);
     //通过Fusen进行服务注册与发现，并且进行HTTP2+JSON进行调用
     let client = DemoServiceClient::new(Arc::new(context.client(Type::Fusen)));
     let res = client
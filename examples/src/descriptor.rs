@@ -0,0 +1,10 @@
+use examples::DemoServiceClient;
+use fusen_rs::fusen_common::openapi::write_descriptor;
+
+//导出接口描述文件: cargo run --bin descriptor -- api/descriptor.json
+fn main() -> std::io::Result<()> {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| concat!(env!("CARGO_MANIFEST_DIR"), "/api/descriptor.json").to_owned());
+    write_descriptor(path, &[DemoServiceClient::get_openapi])
+}
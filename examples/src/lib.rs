@@ -7,21 +7,26 @@ use fusen_rs::{
 use opentelemetry::propagation::text_map_propagator::TextMapPropagator;
 use opentelemetry::{trace::TraceContextExt, Context};
 use opentelemetry_sdk::propagation::TraceContextPropagator;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tracing::{debug_span, error, error_span, info, info_span, warn_span, Instrument, Span};
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 
-#[derive(Serialize, Deserialize, Default, Debug, Data)]
+#[derive(Serialize, Deserialize, Default, Debug, Data, JsonSchema)]
 pub struct ReqDto {
     str: String,
 }
 
-#[derive(Serialize, Deserialize, Default, Debug, Data)]
+#[derive(Serialize, Deserialize, Default, Debug, Data, JsonSchema)]
 pub struct ResDto {
     str: String,
 }
 
-#[fusen_trait(id = "org.apache.dubbo.springboot.demo.DemoService")]
+#[fusen_trait(
+    id = "org.apache.dubbo.springboot.demo.DemoService",
+    openapi = true,
+    descriptor = "api/descriptor.json"
+)]
 pub trait DemoService {
     async fn sayHello(&self, name: String) -> String;
 
@@ -30,6 +35,14 @@ pub trait DemoService {
 
     #[asset(path = "/divide", method = GET)]
     async fn divideV2(&self, a: i32, b: i32) -> String;
+
+    #[asset(path = "/user/{id}", method = PATCH)]
+    async fn updateUser(
+        &self,
+        #[path] id: i64,
+        #[header("x-tenant-id")] tenant: String,
+        #[body] user: ReqDto,
+    ) -> ResDto;
 }
 
 #[allow(dead_code)]
@@ -148,3 +161,15 @@ impl Aspect for LogAspect {
         context
     }
 }
+
+//由build.rs根据proto/greeter.proto生成
+pub mod proto {
+    pub mod greeter {
+        include!(concat!(env!("OUT_DIR"), "/greeter.rs"));
+    }
+    pub mod google {
+        pub mod protobuf {
+            include!(concat!(env!("OUT_DIR"), "/google.protobuf.rs"));
+        }
+    }
+}
@@ -1,8 +1,9 @@
-use examples::{DemoService, LogAspect, ReqDto, ResDto};
+use examples::{DemoService, DemoServiceClient, LogAspect, ReqDto, ResDto};
+use fusen_rs::context::RequestContext;
 use fusen_rs::fusen_common::config::get_config_by_file;
 use fusen_rs::fusen_common::date_util::get_now_date_time_as_millis;
 use fusen_rs::fusen_common::logs::LogConfig;
-use fusen_rs::fusen_procedural_macro::{asset, handler};
+use fusen_rs::fusen_procedural_macro::handler;
 use fusen_rs::handler::aspect::Aspect;
 use fusen_rs::handler::HandlerLoad;
 use fusen_rs::{fusen_common, FusenApplicationContext};
@@ -37,11 +38,11 @@ struct DemoServiceImpl {
 
 #[fusen_server(id = "org.apache.dubbo.springboot.demo.DemoService")]
 impl DemoService for DemoServiceImpl {
-    async fn sayHello(&self, req: String) -> FusenResult<String> {
-        info!("res : {:?}", req);
+    async fn sayHello(&self, req: String, ctx: &RequestContext) -> FusenResult<String> {
+        info!("res : {:?}, caller : {:?}", req, ctx.get_caller());
+        ctx.set_response_header("x-request-id", ctx.get_unique_identifier().clone());
         Ok("Hello ".to_owned() + &req)
     }
-    #[asset(path="/sayHelloV2-http",method = POST)]
     async fn sayHelloV2(&self, req: ReqDto) -> FusenResult<ResDto> {
         let _span = info_span!("sayHelloV2-http").entered();
         info!("开始处理 sayHelloV2-http");
@@ -51,11 +52,14 @@ impl DemoService for DemoServiceImpl {
         drop(_span2);
         Ok(ResDto::default().str("Hello ".to_owned() + req.get_str() + " V2"))
     }
-    #[asset(path="/divide",method = GET)]
     async fn divideV2(&self, a: i32, b: i32) -> FusenResult<String> {
         info!("res : a={:?},b={:?}", a, b);
         Ok((a + b).to_string())
     }
+    async fn updateUser(&self, id: i64, tenant: String, user: ReqDto) -> FusenResult<ResDto> {
+        info!("update user : id={},tenant={},user={:?}", id, tenant, user);
+        Ok(ResDto::default().str(format!("{}:{}:{}", tenant, id, user.get_str())))
+    }
 }
 
 #[tokio::main(flavor = "multi_thread", worker_threads = 16)]
@@ -89,6 +93,7 @@ async fn main() {
         //     vec!["ServerLogAspect".to_owned()],
         // ))
         .add_fusen_server(Box::new(server))
+        .add_openapi(DemoServiceClient::get_openapi)
         .add_handler(ServerLogAspect.load())
         .add_handler(LogAspect::new("debug").load())
         .build()
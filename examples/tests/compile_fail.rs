@@ -0,0 +1,22 @@
+//宏在编译期的检查,期望的错误信息位于tests/ui/*.stderr
+#[test]
+fn test_route_check() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/ui/route_method_asset.rs");
+    t.compile_fail("tests/ui/route_method_mismatch.rs");
+    t.compile_fail("tests/ui/route_parent_mismatch.rs");
+    t.compile_fail("tests/ui/binding_mismatch.rs");
+}
+
+//无法远程调用的方法签名
+#[test]
+fn test_method_check() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/method_generic.rs");
+    t.compile_fail("tests/ui/method_impl_trait.rs");
+    t.compile_fail("tests/ui/method_mut_arg.rs");
+    t.compile_fail("tests/ui/method_reference_return.rs");
+    t.compile_fail("tests/ui/method_nested_option.rs");
+    t.compile_fail("tests/ui/method_nested_vec.rs");
+    t.compile_fail("tests/ui/method_nested_return.rs");
+}
@@ -0,0 +1,80 @@
+use examples::{DemoService, ReqDto, ResDto};
+use fusen_rs::{
+    fusen_common::{register::Type, FusenResult},
+    fusen_procedural_macro::fusen_server,
+    FusenApplicationContext,
+};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+struct DemoServiceImpl;
+
+#[fusen_server(id = "org.apache.dubbo.springboot.demo.DemoService")]
+impl DemoService for DemoServiceImpl {
+    async fn sayHello(&self, req: String) -> FusenResult<String> {
+        Ok("Hello ".to_owned() + &req)
+    }
+    async fn sayHelloV2(&self, req: ReqDto) -> FusenResult<ResDto> {
+        Ok(ResDto::default().str("Hello ".to_owned() + req.get_str() + " V2"))
+    }
+    async fn divideV2(&self, a: i32, b: i32) -> FusenResult<String> {
+        Ok((a + b).to_string())
+    }
+    async fn updateUser(&self, id: i64, tenant: String, user: ReqDto) -> FusenResult<ResDto> {
+        Ok(ResDto::default().str(format!("{}:{}:{}", tenant, id, user.get_str())))
+    }
+}
+
+//泛化调用声明了自定义路径、GET与PATCH请求方法的接口
+#[tokio::test(flavor = "multi_thread")]
+async fn test_invoke_generic() {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let context = FusenApplicationContext::builder()
+        .application_name("generic-test")
+        .port(Some(port))
+        .add_fusen_server(Box::new(DemoServiceImpl))
+        .build();
+    let client = context.client(Type::Host(format!("127.0.0.1:{}", port)));
+    tokio::spawn(context.run());
+    let service = "org.apache.dubbo.springboot.demo.DemoService";
+    let invoke = |method: &'static str, args: Vec<Value>| {
+        let client = &client;
+        async move {
+            client
+                .invoke_generic(service, None, None, method, args)
+                .await
+        }
+    };
+    let mut res = invoke("sayHello", vec![json!("world")]).await;
+    for _ in 0..50 {
+        if res.is_ok() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        res = invoke("sayHello", vec![json!("world")]).await;
+    }
+    assert_eq!(res.unwrap(), json!("Hello world"));
+    assert_eq!(
+        invoke("sayHelloV2", vec![json!({"str": "world"})])
+            .await
+            .unwrap(),
+        json!({"str": "Hello world V2"})
+    );
+    assert_eq!(
+        invoke("divideV2", vec![json!(1), json!(2)]).await.unwrap(),
+        json!("3")
+    );
+    assert_eq!(
+        invoke(
+            "updateUser",
+            vec![json!(7), json!("t"), json!({"str": "u"})]
+        )
+        .await
+        .unwrap(),
+        json!({"str": "t:7:u"})
+    );
+}
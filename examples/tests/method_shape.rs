@@ -0,0 +1,86 @@
+use examples::{ReqDto, ResDto};
+use fusen_rs::{
+    fusen_common::{register::Type, FusenResult},
+    fusen_procedural_macro::{fusen_server, fusen_trait},
+    FusenApplicationContext,
+};
+use std::{sync::Arc, time::Duration};
+
+#[fusen_trait(id = "ShapeService")]
+pub trait ShapeService {
+    async fn greet(&self, name: &str) -> String;
+
+    async fn sum(&self, values: &[i32]) -> i32;
+
+    async fn echo(&self, req: &ReqDto) -> ResDto;
+
+    async fn find(&self, name: String) -> Option<String>;
+
+    async fn ping(&self);
+
+    //有默认实现的方法只在本地使用
+    fn prefix(&self) -> String {
+        "Hello ".to_owned()
+    }
+}
+
+struct ShapeServiceImpl;
+
+#[fusen_server(id = "ShapeService")]
+impl ShapeService for ShapeServiceImpl {
+    async fn greet(&self, name: &str) -> FusenResult<String> {
+        Ok(self.prefix() + name)
+    }
+    async fn sum(&self, values: &[i32]) -> FusenResult<i32> {
+        Ok(values.iter().sum())
+    }
+    async fn echo(&self, req: &ReqDto) -> FusenResult<ResDto> {
+        Ok(ResDto::default().str(req.get_str().to_owned()))
+    }
+    async fn find(&self, name: String) -> FusenResult<Option<String>> {
+        Ok((name == "fusen").then_some(name))
+    }
+    async fn ping(&self) -> FusenResult<()> {
+        Ok(())
+    }
+    fn prefix(&self) -> String {
+        "Hi ".to_owned()
+    }
+}
+
+//引用参数、Option与()返回值以及本地默认方法
+#[tokio::test(flavor = "multi_thread")]
+async fn test_method_shape() {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let context = FusenApplicationContext::builder()
+        .application_name("shape-test")
+        .port(Some(port))
+        .add_fusen_server(Box::new(ShapeServiceImpl))
+        .build();
+    let client = ShapeServiceClient::new(Arc::new(
+        context.client(Type::Host(format!("127.0.0.1:{}", port))),
+    ));
+    tokio::spawn(context.run());
+    let mut res = client.greet("world").await;
+    for _ in 0..50 {
+        if res.is_ok() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        res = client.greet("world").await;
+    }
+    assert_eq!(res.unwrap(), "Hi world");
+    assert_eq!(client.sum(&[1, 2, 3]).await.unwrap(), 6);
+    let req = ReqDto::default().str("dto".to_owned());
+    assert_eq!(client.echo(&req).await.unwrap().get_str(), "dto");
+    assert_eq!(
+        client.find("fusen".to_owned()).await.unwrap(),
+        Some("fusen".to_owned())
+    );
+    assert_eq!(client.find("other".to_owned()).await.unwrap(), None);
+    client.ping().await.unwrap();
+}
@@ -0,0 +1,75 @@
+use examples::proto::{
+    google::protobuf::{Empty, Timestamp},
+    greeter::{Greeter, GreeterClient, HelloReply, HelloRequest},
+};
+use fusen_rs::{
+    fusen_common::{register::Type, FusenResult},
+    fusen_procedural_macro::fusen_server,
+    FusenApplicationContext,
+};
+use std::{sync::Arc, time::Duration};
+
+struct GreeterImpl;
+
+#[fusen_server(id = "greeter.Greeter")]
+impl Greeter for GreeterImpl {
+    async fn sayHello(&self, request: HelloRequest) -> FusenResult<HelloReply> {
+        Ok(HelloReply {
+            message: format!("Hello {}", request.name),
+            time: Some(Timestamp {
+                seconds: 1,
+                nanos: 2,
+            }),
+        })
+    }
+    async fn r#type(&self, _request: Empty) -> FusenResult<HelloReply> {
+        Ok(HelloReply {
+            message: "type".to_owned(),
+            time: None,
+        })
+    }
+    async fn r#match(&self, _request: HelloRequest) -> FusenResult<Empty> {
+        Ok(Empty {})
+    }
+}
+
+//build.rs生成的接口包含关键字方法与google.protobuf类型
+#[tokio::test(flavor = "multi_thread")]
+async fn test_proto_service() {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let context = FusenApplicationContext::builder()
+        .application_name("proto-test")
+        .port(Some(port))
+        .add_fusen_server(Box::new(GreeterImpl))
+        .build();
+    let client = GreeterClient::new(Arc::new(
+        context.client(Type::Host(format!("127.0.0.1:{}", port))),
+    ));
+    tokio::spawn(context.run());
+    let request = || HelloRequest {
+        name: "world".to_owned(),
+    };
+    let mut res = client.sayHello(request()).await;
+    for _ in 0..50 {
+        if res.is_ok() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        res = client.sayHello(request()).await;
+    }
+    let res = res.unwrap();
+    assert_eq!(res.message, "Hello world");
+    assert_eq!(
+        res.time,
+        Some(Timestamp {
+            seconds: 1,
+            nanos: 2
+        })
+    );
+    assert_eq!(client.r#type(Empty {}).await.unwrap().message, "type");
+    assert_eq!(client.r#match(request()).await.unwrap(), Empty {});
+}
@@ -0,0 +1,126 @@
+use fusen_rs::{
+    fusen_common::FusenResult,
+    fusen_procedural_macro::{asset, fusen_server, fusen_trait},
+    FusenApplicationContext,
+};
+use std::time::Duration;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+#[fusen_trait(id = "UserService")]
+#[asset(path = "/api")]
+pub trait UserService {
+    #[asset(path = "/users/{id}", method = GET)]
+    async fn get_user(&self, id: i64) -> String;
+}
+
+#[fusen_trait(id = "AccountService")]
+#[asset(path = "/api")]
+pub trait AccountService {
+    #[asset(path = "/users/{name}", method = GET)]
+    async fn get_account(&self, name: String) -> String;
+}
+
+struct UserServiceImpl;
+
+#[fusen_server(id = "UserService")]
+impl UserService for UserServiceImpl {
+    async fn get_user(&self, id: i64) -> FusenResult<String> {
+        Ok(id.to_string())
+    }
+}
+
+struct VersionUserServiceImpl;
+
+#[fusen_server(id = "UserService", version = "1.0.0")]
+impl UserService for VersionUserServiceImpl {
+    async fn get_user(&self, id: i64) -> FusenResult<String> {
+        Ok(id.to_string())
+    }
+}
+
+struct AccountServiceImpl;
+
+#[fusen_server(id = "AccountService")]
+impl AccountService for AccountServiceImpl {
+    async fn get_account(&self, name: String) -> FusenResult<String> {
+        Ok(name)
+    }
+}
+
+//路由冲突在构建时返回错误
+#[tokio::test]
+async fn test_route_conflict() {
+    let context = FusenApplicationContext::builder()
+        .port(Some(8081))
+        .add_fusen_server(Box::new(UserServiceImpl))
+        .try_build();
+    assert!(context.is_ok());
+    let context = FusenApplicationContext::builder()
+        .port(Some(8081))
+        .add_fusen_server(Box::new(UserServiceImpl))
+        .add_fusen_server(Box::new(AccountServiceImpl))
+        .try_build();
+    assert!(context.is_err());
+}
+
+fn get_free_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+//发送HTTP1请求,返回响应的状态行与响应头
+async fn send(port: u16, method: &str, path: &str) -> String {
+    let mut stream = None;
+    for _ in 0..50 {
+        if let Ok(tcp_stream) = TcpStream::connect(("127.0.0.1", port)).await {
+            let _ = stream.insert(tcp_stream);
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let mut stream = stream.unwrap();
+    let request = format!(
+        "{} {} HTTP/1.1\r\nhost: 127.0.0.1\r\nconnection: close\r\ncontent-length: 0\r\n\r\n",
+        method, path
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = vec![];
+    stream.read_to_end(&mut response).await.unwrap();
+    let response = String::from_utf8_lossy(&response).to_lowercase();
+    response.split("\r\n\r\n").next().unwrap().to_owned()
+}
+
+//只有请求方法不被支持时返回405,服务或版本不存在时返回404
+#[tokio::test(flavor = "multi_thread")]
+async fn test_method_not_allowed() {
+    let port = get_free_port();
+    let context = FusenApplicationContext::builder()
+        .port(Some(port))
+        .add_fusen_server(Box::new(UserServiceImpl))
+        .build();
+    tokio::spawn(context.run());
+    assert!(send(port, "GET", "/api/users/1")
+        .await
+        .starts_with("http/1.1 200"));
+    let response = send(port, "POST", "/api/users/1").await;
+    assert!(response.starts_with("http/1.1 405"));
+    assert!(response.contains("allow: get, options"));
+    assert!(send(port, "GET", "/api/accounts/1")
+        .await
+        .starts_with("http/1.1 404"));
+    let port = get_free_port();
+    let context = FusenApplicationContext::builder()
+        .port(Some(port))
+        .add_fusen_server(Box::new(VersionUserServiceImpl))
+        .build();
+    tokio::spawn(context.run());
+    assert!(send(port, "GET", "/api/users/1")
+        .await
+        .starts_with("http/1.1 404"));
+}
@@ -0,0 +1,21 @@
+use fusen_rs::{
+    fusen_common::FusenResult,
+    fusen_procedural_macro::{asset, fusen_server, fusen_trait},
+};
+
+#[fusen_trait(id = "UserService")]
+pub trait UserService {
+    #[asset(path = "/users/{id}", method = GET)]
+    async fn get_user(&self, #[path] id: i64) -> String;
+}
+
+struct UserServiceImpl;
+
+#[fusen_server(id = "UserService")]
+impl UserService for UserServiceImpl {
+    async fn get_user(&self, #[query] id: i64) -> FusenResult<String> {
+        Ok(id.to_string())
+    }
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use fusen_rs::fusen_procedural_macro::fusen_trait;
+
+#[fusen_trait(id = "ShapeService")]
+pub trait ShapeService {
+    async fn get(&self, value: impl serde::Serialize) -> String;
+}
+
+fn main() {}
@@ -0,0 +1,31 @@
+use fusen_rs::{
+    fusen_common::FusenResult,
+    fusen_procedural_macro::{asset, fusen_server, fusen_trait},
+};
+
+#[fusen_trait(id = "UserService")]
+#[asset(path = "/api", method = GET)]
+pub trait UserService {
+    #[asset(path = "/users/{id}")]
+    async fn get_user(&self, id: i64) -> String;
+
+    async fn get_name(&self, id: i64) -> String;
+}
+
+struct UserServiceImpl;
+
+//实现上只重复方法上的声明,父路径与请求方法以接口为准
+#[fusen_server(id = "UserService")]
+impl UserService for UserServiceImpl {
+    #[asset(path = "/users/{id}")]
+    async fn get_user(&self, id: i64) -> FusenResult<String> {
+        Ok(id.to_string())
+    }
+
+    #[asset(method = GET)]
+    async fn get_name(&self, id: i64) -> FusenResult<String> {
+        Ok(id.to_string())
+    }
+}
+
+fn main() {}
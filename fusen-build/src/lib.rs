@@ -0,0 +1,327 @@
+use prost_build::{Comments, Config, Method, Service, ServiceGenerator};
+use prost_types::{DescriptorProto, FileDescriptorSet};
+use std::{cell::RefCell, io, path::Path, rc::Rc};
+
+//在build.rs中根据.proto生成prost消息类型以及#[fusen_trait]接口
+//生成的文件位于OUT_DIR/{package}.rs,通过include!引入
+pub fn compile_protos(
+    protos: &[impl AsRef<Path>],
+    includes: &[impl AsRef<Path>],
+) -> io::Result<()> {
+    configure().compile_protos(protos, includes)
+}
+
+pub fn configure() -> Builder {
+    Builder::default()
+}
+
+#[derive(Default)]
+pub struct Builder {
+    version: Option<String>,
+    group: Option<String>,
+    well_known_types: bool,
+}
+
+impl Builder {
+    pub fn version(mut self, version: &str) -> Self {
+        self.version = Some(version.to_owned());
+        self
+    }
+
+    pub fn group(mut self, group: &str) -> Self {
+        self.group = Some(group.to_owned());
+        self
+    }
+
+    //google.protobuf下的Timestamp等类型由prost_types提供,未实现serde,
+    //开启后会在OUT_DIR生成google.protobuf.rs,需要以google::protobuf模块引入
+    pub fn compile_well_known_types(mut self) -> Self {
+        self.well_known_types = true;
+        self
+    }
+
+    pub fn compile_protos(
+        self,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+    ) -> io::Result<()> {
+        self.compile_with_config(Config::new(), protos, includes)
+    }
+
+    //可以传入自定义的prost_build::Config,如修改out_dir或添加额外的属性
+    pub fn compile_with_config(
+        self,
+        mut config: Config,
+        protos: &[impl AsRef<Path>],
+        includes: &[impl AsRef<Path>],
+    ) -> io::Result<()> {
+        let errors = Rc::new(RefCell::new(vec![]));
+        //消息类型需要支持serde才能使用json等序列化方式,缺省的字段使用默认值
+        config
+            .message_attribute(
+                ".",
+                "#[derive(serde::Serialize, serde::Deserialize)]\n#[serde(default)]",
+            )
+            .enum_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]")
+            .service_generator(Box::new(FusenServiceGenerator {
+                version: self.version,
+                group: self.group,
+                errors: errors.clone(),
+            }));
+        let fds = config.load_fds(protos, includes)?;
+        if self.well_known_types {
+            //注释中的C++/Java示例代码会被当作doctest执行
+            config
+                .compile_well_known_types()
+                .disable_comments([".google.protobuf"]);
+        } else {
+            check_well_known_types(&fds)?;
+        }
+        config.compile_fds(fds)?;
+        let errors = errors.borrow();
+        if !errors.is_empty() {
+            return Err(io::Error::other(errors.join("\n")));
+        }
+        Ok(())
+    }
+}
+
+//prost_types中映射为基础类型的包装类型可以直接序列化
+const PRIMITIVE_WELL_KNOWN_TYPES: [&str; 10] = [
+    "Empty",
+    "BoolValue",
+    "BytesValue",
+    "DoubleValue",
+    "FloatValue",
+    "Int32Value",
+    "Int64Value",
+    "StringValue",
+    "UInt32Value",
+    "UInt64Value",
+];
+
+fn is_unsupported_type(type_name: &str) -> bool {
+    type_name
+        .strip_prefix(".google.protobuf.")
+        .is_some_and(|name| !PRIMITIVE_WELL_KNOWN_TYPES.contains(&name))
+}
+
+fn check_well_known_types(fds: &FileDescriptorSet) -> io::Result<()> {
+    fn check_message(message: &DescriptorProto, path: &str, errors: &mut Vec<String>) {
+        let path = format!("{}.{}", path, message.name());
+        for field in &message.field {
+            if is_unsupported_type(field.type_name()) {
+                errors.push(format!(
+                    "field {}.{} : {}",
+                    path,
+                    field.name(),
+                    field.type_name()
+                ));
+            }
+        }
+        for nested in &message.nested_type {
+            check_message(nested, &path, errors);
+        }
+    }
+    let mut errors = vec![];
+    for file in &fds.file {
+        if file.package() == "google.protobuf" {
+            continue;
+        }
+        for message in &file.message_type {
+            check_message(message, file.package(), &mut errors);
+        }
+        for service in &file.service {
+            for method in &service.method {
+                for type_name in [method.input_type(), method.output_type()] {
+                    if is_unsupported_type(type_name) {
+                        errors.push(format!(
+                            "method {}.{}.{} : {}",
+                            file.package(),
+                            service.name(),
+                            method.name(),
+                            type_name
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(io::Error::other(format!(
+        "fusen-build : well-known types do not support serde, call compile_well_known_types() and include google.protobuf.rs as google::protobuf\n{}",
+        errors.join("\n")
+    )))
+}
+
+struct FusenServiceGenerator {
+    version: Option<String>,
+    group: Option<String>,
+    errors: Rc<RefCell<Vec<String>>>,
+}
+
+impl ServiceGenerator for FusenServiceGenerator {
+    fn generate(&mut self, service: Service, buf: &mut String) {
+        match generate_service(&service, self.version.as_deref(), self.group.as_deref()) {
+            Ok(code) => buf.push_str(&code),
+            Err(err) => self.errors.borrow_mut().push(err),
+        }
+    }
+}
+
+//接口id为package.Service,http路径为/package.Service/Method,方法名与Java接口一致使用小驼峰
+fn generate_service(
+    service: &Service,
+    version: Option<&str>,
+    group: Option<&str>,
+) -> Result<String, String> {
+    let id = match service.package.is_empty() {
+        true => service.proto_name.clone(),
+        false => format!("{}.{}", service.package, service.proto_name),
+    };
+    let mut attr = format!("id = \"{}\"", id);
+    if let Some(version) = version {
+        attr.push_str(&format!(", version = \"{}\"", version));
+    }
+    if let Some(group) = group {
+        attr.push_str(&format!(", group = \"{}\"", group));
+    }
+    let mut buf = String::new();
+    push_comments(&mut buf, &service.comments, "");
+    buf.push_str(&format!(
+        "#[fusen_rs::fusen_procedural_macro::fusen_trait({})]\n",
+        attr
+    ));
+    buf.push_str(&format!(
+        "#[fusen_rs::fusen_procedural_macro::asset(path = \"/{}\")]\n",
+        id
+    ));
+    buf.push_str(&format!("pub trait {} {{\n", service.name));
+    for method in &service.methods {
+        buf.push_str(&generate_method(&id, method)?);
+    }
+    buf.push_str("}\n");
+    Ok(buf)
+}
+
+fn generate_method(id: &str, method: &Method) -> Result<String, String> {
+    if method.client_streaming || method.server_streaming {
+        return Err(format!(
+            "fusen-build : streaming method {}.{} is not supported",
+            id, method.proto_name
+        ));
+    }
+    let mut buf = String::new();
+    push_comments(&mut buf, &method.comments, "    ");
+    buf.push_str(&format!(
+        "    #[fusen_rs::fusen_procedural_macro::asset(path = \"/{}\")]\n",
+        method.proto_name
+    ));
+    //google.protobuf.Empty对应()
+    let input = match method.input_type.as_str() {
+        "()" => String::new(),
+        input_type => format!(", request: {}", input_type),
+    };
+    let output = match method.output_type.as_str() {
+        "()" => String::new(),
+        output_type => format!(" -> {}", output_type),
+    };
+    buf.push_str(&format!(
+        "    async fn {}(&self{}){};\n",
+        get_method_name(&method.proto_name),
+        input,
+        output
+    ));
+    Ok(buf)
+}
+
+//关键字使用原始标识符,self等不能作为原始标识符的关键字添加后缀
+fn get_method_name(proto_name: &str) -> String {
+    let mut chars = proto_name.chars();
+    let name: String = match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    };
+    match name.as_str() {
+        "self" | "super" | "crate" | "Self" => format!("{}_", name),
+        "as" | "async" | "await" | "break" | "const" | "continue" | "dyn" | "else" | "enum"
+        | "extern" | "false" | "fn" | "for" | "if" | "impl" | "in" | "let" | "loop" | "match"
+        | "mod" | "move" | "mut" | "pub" | "ref" | "return" | "static" | "struct" | "trait"
+        | "true" | "type" | "unsafe" | "use" | "where" | "while" | "abstract" | "become"
+        | "box" | "do" | "final" | "macro" | "override" | "priv" | "typeof" | "unsized"
+        | "virtual" | "yield" | "try" | "gen" => format!("r#{}", name),
+        _ => name,
+    }
+}
+
+fn push_comments(buf: &mut String, comments: &Comments, indent: &str) {
+    for line in &comments.leading {
+        buf.push_str(&format!("{}///{}\n", indent, line));
+    }
+}
+
+#[test]
+fn test() {
+    let method = |name: &str, input_type: &str, output_type: &str| Method {
+        name: name.to_owned(),
+        proto_name: name.to_owned(),
+        comments: Comments::default(),
+        input_type: input_type.to_owned(),
+        output_type: output_type.to_owned(),
+        input_proto_type: String::new(),
+        output_proto_type: String::new(),
+        options: prost_types::MethodOptions::default(),
+        client_streaming: false,
+        server_streaming: false,
+    };
+    let mut service = Service {
+        name: "Greeter".to_owned(),
+        proto_name: "Greeter".to_owned(),
+        package: "helloworld".to_owned(),
+        comments: Comments {
+            leading: vec![" The greeting service".to_owned()],
+            ..Default::default()
+        },
+        methods: vec![
+            method("SayHello", "HelloRequest", "HelloReply"),
+            method("Ping", "()", "()"),
+        ],
+        options: prost_types::ServiceOptions::default(),
+    };
+    let code = generate_service(&service, Some("1.0.0"), None).unwrap();
+    assert!(code.starts_with("/// The greeting service\n"));
+    assert!(code.contains("fusen_trait(id = \"helloworld.Greeter\", version = \"1.0.0\")"));
+    assert!(code.contains("asset(path = \"/helloworld.Greeter\")"));
+    assert!(code.contains("asset(path = \"/SayHello\")"));
+    assert!(code.contains("async fn sayHello(&self, request: HelloRequest) -> HelloReply;"));
+    assert!(code.contains("async fn ping(&self);"));
+    assert_eq!(get_method_name("Type"), "r#type");
+    assert_eq!(get_method_name("Match"), "r#match");
+    assert_eq!(get_method_name("Self"), "self_");
+    assert!(is_unsupported_type(".google.protobuf.Timestamp"));
+    assert!(!is_unsupported_type(".google.protobuf.Empty"));
+    assert!(!is_unsupported_type(".helloworld.HelloRequest"));
+    let mut fds = FileDescriptorSet {
+        file: vec![prost_types::FileDescriptorProto {
+            package: Some("helloworld".to_owned()),
+            message_type: vec![DescriptorProto {
+                name: Some("HelloReply".to_owned()),
+                field: vec![prost_types::FieldDescriptorProto {
+                    name: Some("time".to_owned()),
+                    type_name: Some(".google.protobuf.Timestamp".to_owned()),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }],
+    };
+    let err = check_well_known_types(&fds).unwrap_err().to_string();
+    assert!(err.contains("field helloworld.HelloReply.time : .google.protobuf.Timestamp"));
+    fds.file[0].message_type[0].field[0].type_name = Some(".google.protobuf.Empty".to_owned());
+    assert!(check_well_known_types(&fds).is_ok());
+    service.methods[0].server_streaming = true;
+    assert!(generate_service(&service, None, None).is_err());
+}
@@ -1,5 +1,5 @@
 use bytes::{Buf, Bytes, BytesMut};
-use serde::Serialize;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use crate::error::{BoxError, FusenError};
 
@@ -18,6 +18,143 @@ impl From<&str> for CodecType {
     }
 }
 
+//内置序列化格式的编解码,支持的格式是封闭的集合,即SerializationType的各个枚举值
+//方法带有泛型参数,不能作为trait object使用,动态选择格式时使用SerializationType
+pub trait SerializationFormat {
+    fn content_type(&self) -> &'static str;
+
+    fn serialize<T: Serialize>(&self, obj: &T) -> Result<Bytes, BoxError>;
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, BoxError>;
+}
+
+pub struct JsonSerialization;
+
+pub struct MessagePackSerialization;
+
+pub struct CborSerialization;
+
+pub struct BincodeSerialization;
+
+impl SerializationFormat for JsonSerialization {
+    fn content_type(&self) -> &'static str {
+        "application/json"
+    }
+
+    fn serialize<T: Serialize>(&self, obj: &T) -> Result<Bytes, BoxError> {
+        object_to_bytes(obj)
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, BoxError> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+impl SerializationFormat for MessagePackSerialization {
+    fn content_type(&self) -> &'static str {
+        "application/msgpack"
+    }
+
+    fn serialize<T: Serialize>(&self, obj: &T) -> Result<Bytes, BoxError> {
+        Ok(rmp_serde::to_vec_named(obj)?.into())
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, BoxError> {
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+}
+
+impl SerializationFormat for CborSerialization {
+    fn content_type(&self) -> &'static str {
+        "application/cbor"
+    }
+
+    fn serialize<T: Serialize>(&self, obj: &T) -> Result<Bytes, BoxError> {
+        let mut buf = vec![];
+        ciborium::into_writer(obj, &mut buf)?;
+        Ok(buf.into())
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, BoxError> {
+        Ok(ciborium::from_reader(bytes)?)
+    }
+}
+
+impl SerializationFormat for BincodeSerialization {
+    fn content_type(&self) -> &'static str {
+        "application/x-bincode"
+    }
+
+    fn serialize<T: Serialize>(&self, obj: &T) -> Result<Bytes, BoxError> {
+        Ok(bincode::serialize(obj)?.into())
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, BoxError> {
+        Ok(bincode::deserialize(bytes)?)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SerializationType {
+    #[default]
+    JSON,
+    MessagePack,
+    CBOR,
+    Bincode,
+}
+
+impl SerializationType {
+    fn from_media_type(media_type: &str) -> Option<Self> {
+        let media_type = media_type.split(';').next()?.trim().to_lowercase();
+        match media_type.as_str() {
+            "application/json" => Some(Self::JSON),
+            "application/msgpack" | "application/x-msgpack" => Some(Self::MessagePack),
+            "application/cbor" => Some(Self::CBOR),
+            "application/x-bincode" | "application/bincode" => Some(Self::Bincode),
+            _ => None,
+        }
+    }
+
+    pub fn from_accept(accept: &str) -> Option<Self> {
+        accept.split(',').find_map(Self::from_media_type)
+    }
+}
+
+impl From<&str> for SerializationType {
+    fn from(value: &str) -> Self {
+        Self::from_media_type(value).unwrap_or_default()
+    }
+}
+
+impl SerializationFormat for SerializationType {
+    fn content_type(&self) -> &'static str {
+        match self {
+            SerializationType::JSON => JsonSerialization.content_type(),
+            SerializationType::MessagePack => MessagePackSerialization.content_type(),
+            SerializationType::CBOR => CborSerialization.content_type(),
+            SerializationType::Bincode => BincodeSerialization.content_type(),
+        }
+    }
+
+    fn serialize<T: Serialize>(&self, obj: &T) -> Result<Bytes, BoxError> {
+        match self {
+            SerializationType::JSON => JsonSerialization.serialize(obj),
+            SerializationType::MessagePack => MessagePackSerialization.serialize(obj),
+            SerializationType::CBOR => CborSerialization.serialize(obj),
+            SerializationType::Bincode => BincodeSerialization.serialize(obj),
+        }
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, BoxError> {
+        match self {
+            SerializationType::JSON => JsonSerialization.deserialize(bytes),
+            SerializationType::MessagePack => MessagePackSerialization.deserialize(bytes),
+            SerializationType::CBOR => CborSerialization.deserialize(bytes),
+            SerializationType::Bincode => BincodeSerialization.deserialize(bytes),
+        }
+    }
+}
+
 pub fn json_field_compatible(ty: &str, field: Bytes) -> Result<String, FusenError> {
     let mut field_str =
         String::from_utf8(field.to_vec()).map_err(|e| FusenError::Info(e.to_string()))?;
@@ -46,3 +183,37 @@ pub fn object_to_bytes<T: Serialize>(obj: &T) -> Result<Bytes, BoxError> {
     let bytes = serde_json::to_vec(obj)?;
     Ok(Bytes::copy_from_slice(&bytes))
 }
+
+#[test]
+fn test() {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct ReqDto {
+        id: i64,
+        name: String,
+        tags: Vec<String>,
+    }
+    let req = ReqDto {
+        id: 7,
+        name: "fusen".to_owned(),
+        tags: vec!["rust".to_owned()],
+    };
+    for serialization in [
+        SerializationType::JSON,
+        SerializationType::MessagePack,
+        SerializationType::CBOR,
+        SerializationType::Bincode,
+    ] {
+        let bytes = SerializationFormat::serialize(&serialization, &req).unwrap();
+        let res: ReqDto = serialization.deserialize(&bytes).unwrap();
+        assert_eq!(req, res);
+        assert_eq!(
+            SerializationType::from(serialization.content_type()),
+            serialization
+        );
+    }
+    assert_eq!(
+        SerializationType::from_accept("text/html, application/msgpack;q=0.9"),
+        Some(SerializationType::MessagePack)
+    );
+    assert_eq!(SerializationType::from_accept("*/*"), None);
+}
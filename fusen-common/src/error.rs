@@ -1,19 +1,247 @@
-use serde::{Deserialize, Serialize};
+use fusen_procedural_macro::Data;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::fmt::{self, Display, Formatter};
 pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
 pub type BoxFusenError = Box<FusenError>;
+//业务异常在错误详情中的类型标识
+pub const BUSINESS_ERROR_TYPE_URL: &str = "type.fusen.rs/fusen.BusinessError";
+pub const JAVA_EXCEPTION_TYPE_URL: &str = "type.fusen.rs/fusen.JavaException";
+
+//gRPC标准状态码,序列化为数字与google.rpc.Status保持一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusCode {
+    Ok = 0,
+    Cancelled = 1,
+    Unknown = 2,
+    InvalidArgument = 3,
+    DeadlineExceeded = 4,
+    NotFound = 5,
+    AlreadyExists = 6,
+    PermissionDenied = 7,
+    ResourceExhausted = 8,
+    FailedPrecondition = 9,
+    Aborted = 10,
+    OutOfRange = 11,
+    Unimplemented = 12,
+    Internal = 13,
+    Unavailable = 14,
+    DataLoss = 15,
+    Unauthenticated = 16,
+}
+
+impl StatusCode {
+    pub fn from_i32(code: i32) -> StatusCode {
+        match code {
+            0 => StatusCode::Ok,
+            1 => StatusCode::Cancelled,
+            3 => StatusCode::InvalidArgument,
+            4 => StatusCode::DeadlineExceeded,
+            5 => StatusCode::NotFound,
+            6 => StatusCode::AlreadyExists,
+            7 => StatusCode::PermissionDenied,
+            8 => StatusCode::ResourceExhausted,
+            9 => StatusCode::FailedPrecondition,
+            10 => StatusCode::Aborted,
+            11 => StatusCode::OutOfRange,
+            12 => StatusCode::Unimplemented,
+            13 => StatusCode::Internal,
+            14 => StatusCode::Unavailable,
+            15 => StatusCode::DataLoss,
+            16 => StatusCode::Unauthenticated,
+            _ => StatusCode::Unknown,
+        }
+    }
+
+    pub fn as_i32(&self) -> i32 {
+        *self as i32
+    }
+
+    //参考google.rpc.Code中的HTTP映射
+    pub fn http_status(&self) -> u16 {
+        match self {
+            StatusCode::Ok => 200,
+            StatusCode::Cancelled => 499,
+            StatusCode::Unknown => 500,
+            StatusCode::InvalidArgument => 400,
+            StatusCode::DeadlineExceeded => 504,
+            StatusCode::NotFound => 404,
+            StatusCode::AlreadyExists => 409,
+            StatusCode::PermissionDenied => 403,
+            StatusCode::ResourceExhausted => 429,
+            StatusCode::FailedPrecondition => 400,
+            StatusCode::Aborted => 409,
+            StatusCode::OutOfRange => 400,
+            StatusCode::Unimplemented => 501,
+            StatusCode::Internal => 500,
+            StatusCode::Unavailable => 503,
+            StatusCode::DataLoss => 500,
+            StatusCode::Unauthenticated => 401,
+        }
+    }
+
+    pub fn from_http_status(status: u16) -> StatusCode {
+        match status {
+            200..=299 => StatusCode::Ok,
+            400 => StatusCode::InvalidArgument,
+            401 => StatusCode::Unauthenticated,
+            403 => StatusCode::PermissionDenied,
+            404 => StatusCode::NotFound,
+            409 => StatusCode::Aborted,
+            413 | 429 => StatusCode::ResourceExhausted,
+            499 => StatusCode::Cancelled,
+            501 => StatusCode::Unimplemented,
+            502 | 503 => StatusCode::Unavailable,
+            504 => StatusCode::DeadlineExceeded,
+            500 => StatusCode::Internal,
+            _ => StatusCode::Unknown,
+        }
+    }
+}
+
+impl Serialize for StatusCode {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i32(self.as_i32())
+    }
+}
+
+impl<'de> Deserialize<'de> for StatusCode {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(StatusCode::from_i32(i32::deserialize(deserializer)?))
+    }
+}
+
+//对应google.protobuf.Any
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default, Data)]
+pub struct ErrorDetail {
+    type_url: String,
+    value: Vec<u8>,
+}
+
+impl ErrorDetail {
+    pub fn new(type_url: String, value: Vec<u8>) -> Self {
+        ErrorDetail { type_url, value }
+    }
+}
+
+//对应google.rpc.Status
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Data)]
+pub struct FusenStatus {
+    code: StatusCode,
+    message: String,
+    details: Vec<ErrorDetail>,
+}
+
+impl FusenStatus {
+    pub fn new(code: StatusCode, message: String) -> Self {
+        FusenStatus {
+            code,
+            message,
+            details: vec![],
+        }
+    }
+}
+
+//Java服务端抛出的异常
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default, Data)]
+pub struct JavaException {
+    class_name: String,
+    message: Option<String>,
+    cause: Option<Box<JavaException>>,
+}
+
+impl JavaException {
+    pub fn new(class_name: String, message: Option<String>) -> Self {
+        JavaException {
+            class_name,
+            message,
+            cause: None,
+        }
+    }
+}
+
+impl Display for JavaException {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.message {
+            Some(message) => write!(f, "{}: {}", self.class_name, message),
+            None => write!(f, "{}", self.class_name),
+        }
+    }
+}
+
+impl From<JavaException> for FusenError {
+    fn from(value: JavaException) -> Self {
+        let message = value.to_string();
+        let details = match serde_json::to_vec(&value) {
+            Ok(value) => vec![ErrorDetail::new(JAVA_EXCEPTION_TYPE_URL.to_owned(), value)],
+            Err(_) => vec![],
+        };
+        FusenError::Status(FusenStatus::new(StatusCode::Unknown, message).details(details))
+    }
+}
 
 #[derive(Serialize, Deserialize, Debug)]
 pub enum FusenError {
     Null,
     NotFind,
     Info(String),
+    Status(FusenStatus),
 }
 
 impl FusenError {
     pub fn boxed(self) -> BoxFusenError {
         Box::new(self)
     }
+
+    pub fn status(code: StatusCode, message: impl Into<String>) -> Self {
+        FusenError::Status(FusenStatus::new(code, message.into()))
+    }
+
+    pub fn code(&self) -> StatusCode {
+        match self {
+            FusenError::Null => StatusCode::NotFound,
+            //找不到服务或方法
+            FusenError::NotFind => StatusCode::Unimplemented,
+            FusenError::Info(_) => StatusCode::Unknown,
+            FusenError::Status(status) => status.code,
+        }
+    }
+
+    //业务异常使用JSON序列化后放入错误详情,Java客户端也可以直接读取
+    pub fn business<E: Serialize>(err: &E) -> Self {
+        match serde_json::to_vec(err) {
+            Ok(value) => {
+                let message = String::from_utf8_lossy(&value).to_string();
+                FusenError::Status(FusenStatus::new(StatusCode::Unknown, message).details(vec![
+                    ErrorDetail::new(BUSINESS_ERROR_TYPE_URL.to_owned(), value),
+                ]))
+            }
+            Err(err) => FusenError::Info(err.to_string()),
+        }
+    }
+
+    pub fn java_exception(&self) -> Option<JavaException> {
+        let FusenError::Status(status) = self else {
+            return None;
+        };
+        status
+            .details
+            .iter()
+            .find(|e| e.type_url == JAVA_EXCEPTION_TYPE_URL)
+            .and_then(|e| serde_json::from_slice(&e.value).ok())
+    }
+
+    pub fn into_status(self) -> FusenStatus {
+        match self {
+            FusenError::Status(status) => status,
+            err => FusenStatus::new(err.code(), err.to_string()),
+        }
+    }
+}
+
+impl From<FusenStatus> for FusenError {
+    fn from(value: FusenStatus) -> Self {
+        FusenError::Status(value)
+    }
 }
 
 impl From<&str> for FusenError {
@@ -30,6 +258,10 @@ impl From<String> for FusenError {
 
 impl From<crate::Error> for FusenError {
     fn from(value: crate::Error) -> Self {
+        let value = match value.downcast::<FusenError>() {
+            Ok(err) => return *err,
+            Err(value) => value,
+        };
         let msg = value.to_string();
         match msg.as_str() {
             "404" => FusenError::NotFind,
@@ -53,8 +285,74 @@ impl Display for FusenError {
             FusenError::Null => write!(f, "null value"),
             FusenError::Info(msg) => write!(f, "{}", msg),
             FusenError::NotFind => write!(f, "404",),
+            FusenError::Status(status) => write!(f, "{}", status.message),
         }
     }
 }
 
 impl std::error::Error for FusenError {}
+
+//声明了业务异常的方法在客户端返回的错误类型
+#[derive(Debug)]
+pub enum CallError<E> {
+    Business(E),
+    Fusen(FusenError),
+}
+
+impl<E: DeserializeOwned> From<FusenError> for CallError<E> {
+    fn from(value: FusenError) -> Self {
+        if let FusenError::Status(status) = &value {
+            if let Some(detail) = status
+                .details
+                .iter()
+                .find(|e| e.type_url == BUSINESS_ERROR_TYPE_URL)
+            {
+                return match serde_json::from_slice(&detail.value) {
+                    Ok(err) => CallError::Business(err),
+                    Err(err) => CallError::Fusen(FusenError::Info(err.to_string())),
+                };
+            }
+        }
+        CallError::Fusen(value)
+    }
+}
+
+impl<E: Display> Display for CallError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CallError::Business(err) => write!(f, "{}", err),
+            CallError::Fusen(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl<E: Display + fmt::Debug> std::error::Error for CallError<E> {}
+
+#[test]
+fn test() {
+    for code in 0..=16 {
+        let status_code = StatusCode::from_i32(code);
+        assert_eq!(status_code.as_i32(), code);
+        if status_code != StatusCode::Ok {
+            assert!(status_code.http_status() >= 400);
+        }
+    }
+    assert_eq!(StatusCode::from_i32(99), StatusCode::Unknown);
+    assert_eq!(
+        StatusCode::from_http_status(StatusCode::Unauthenticated.http_status()),
+        StatusCode::Unauthenticated
+    );
+    let err = FusenError::status(StatusCode::PermissionDenied, "denied");
+    assert_eq!(err.code(), StatusCode::PermissionDenied);
+    assert_eq!(err.to_string(), "denied");
+    assert_eq!(
+        serde_json::to_string(&FusenStatus::new(StatusCode::NotFound, "none".to_owned())).unwrap(),
+        r#"{"code":5,"message":"none","details":[]}"#
+    );
+    let err: crate::Error = Box::new(err);
+    assert_eq!(FusenError::from(err).code(), StatusCode::PermissionDenied);
+    let err: CallError<(i32, String)> = FusenError::business(&(1, "exist".to_owned())).into();
+    assert!(matches!(err, CallError::Business((1, ref msg)) if msg == "exist"));
+    let err: CallError<(i32, String)> = FusenError::NotFind.into();
+    assert!(matches!(err, CallError::Fusen(FusenError::NotFind)));
+}
@@ -1,11 +1,16 @@
 use bytes::{Bytes, BytesMut};
-use codec::CodecType;
-use error::FusenError;
+use codec::{CodecType, SerializationFormat, SerializationType};
+use error::{FusenError, StatusCode};
 use fusen_procedural_macro::Data;
 use http::{HeaderMap, HeaderValue};
 use register::Type;
-use serde::{Deserialize, Serialize};
-use std::collections::{hash_map::Iter, HashMap};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    collections::{hash_map::Iter, HashMap},
+    fmt::Display,
+    net::SocketAddr,
+    time::Instant,
+};
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 pub type Result<T> = std::result::Result<T, Error>;
 pub type Response<T> = std::result::Result<T, String>;
@@ -18,6 +23,8 @@ pub mod error;
 pub mod logs;
 pub mod r#macro;
 pub mod net;
+pub mod openapi;
+pub mod param;
 pub mod register;
 pub mod server;
 pub mod trie;
@@ -38,6 +45,17 @@ impl MetaData {
         }
         CodecType::JSON
     }
+    pub fn get_serialization(&self) -> SerializationType {
+        self.get_value("content-type")
+            .map_or(SerializationType::JSON, |e| {
+                SerializationType::from(e.as_str())
+            })
+    }
+    pub fn get_accept_serialization(&self) -> SerializationType {
+        self.get_value("accept")
+            .and_then(|e| SerializationType::from_accept(e))
+            .unwrap_or_else(|| self.get_serialization())
+    }
     pub fn into_inner(self) -> HashMap<String, String> {
         self.inner
     }
@@ -45,7 +63,7 @@ impl MetaData {
         self.inner.get(key)
     }
 
-    pub fn get_iter(&self) -> Iter<String, String> {
+    pub fn get_iter(&self) -> Iter<'_, String, String> {
         self.inner.iter()
     }
     pub fn clone_map(&self) -> HashMap<String, String> {
@@ -127,65 +145,175 @@ impl ContextInfo {
 #[derive(Debug, Data)]
 pub struct FusenRequest {
     headers: HashMap<String, String>,
+    path_fields: Option<Vec<(String, String)>>,
     query_fields: Option<Vec<(String, String)>>,
     body: Bytes,
+    serialization: SerializationType,
+    //客户端声明的参数绑定,HTTP编码时按照绑定拆分参数
+    bindings: Option<Vec<(FieldBinding, String)>>,
+    //服务端收到的HTTP请求,参数按照绑定方式分布在path/query/header/body中
+    http_binding: bool,
+    //服务端收到的原始请求路径,包含query
+    uri: Option<String>,
+}
+
+//参数绑定方式,对应方法参数上声明的#[path] #[query] #[header] #[body]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldBinding {
+    Path,
+    Query,
+    Header,
+    Body,
 }
 
 impl FusenRequest {
-    pub fn new_for_client(method: &str, fields_ty: Vec<String>, bodys: Vec<String>) -> Self {
+    pub fn new_for_client(
+        method: &str,
+        fields_ty: Vec<String>,
+        bodys: Vec<Bytes>,
+        serialization: SerializationType,
+    ) -> Self {
         let mut query_fields = None;
         let mut bytes = BytesMut::new();
+        let mut serialization = serialization;
         if method.to_lowercase().as_str() != "post" {
             let mut vec = vec![];
             for (idx, body) in bodys.into_iter().enumerate() {
-                vec.push((fields_ty[idx].to_owned(), body));
+                vec.push((
+                    fields_ty[idx].to_owned(),
+                    String::from_utf8_lossy(&body).to_string(),
+                ));
             }
             let _ = query_fields.insert(vec);
-        } else {
+            serialization = SerializationType::JSON;
+        } else if let SerializationType::JSON = serialization {
+            let bodys: Vec<String> = bodys
+                .iter()
+                .map(|e| String::from_utf8_lossy(e).to_string())
+                .collect();
             bytes.extend_from_slice(serde_json::to_string(&bodys).unwrap().as_bytes());
+        } else {
+            let bodys: Vec<&serde_bytes::Bytes> =
+                bodys.iter().map(|e| serde_bytes::Bytes::new(e)).collect();
+            bytes.extend_from_slice(
+                &SerializationFormat::serialize(&serialization, &bodys).unwrap(),
+            );
         }
         FusenRequest {
             headers: Default::default(),
+            path_fields: None,
             query_fields,
             body: bytes.into(),
+            serialization,
+            bindings: None,
+            http_binding: false,
+            uri: None,
         }
     }
+    //参数仍按顺序放入body,Dubbo与gRPC按顺序传递,HTTP编码时再按绑定拆分
+    pub fn new_for_client_binding(
+        bindings: Vec<(FieldBinding, String)>,
+        bodys: Vec<Bytes>,
+        serialization: SerializationType,
+    ) -> Self {
+        let mut request = Self::new_for_client("POST", vec![], bodys, serialization);
+        request.bindings = Some(bindings);
+        request
+    }
     pub fn new(query_fields: Option<Vec<(String, String)>>, body: Bytes) -> Self {
         FusenRequest {
             headers: Default::default(),
+            path_fields: None,
             query_fields,
             body,
+            serialization: Default::default(),
+            bindings: None,
+            http_binding: false,
+            uri: None,
         }
     }
-    pub fn get_fields(
-        &mut self,
-        temp_fields_name: Vec<&str>,
-        temp_fields_ty: Vec<&str>,
-    ) -> Result<Vec<String>> {
-        let mut new_fields = vec![];
-        if let Some(fields_name) = &self.query_fields {
-            let mut hash_map = HashMap::with_capacity(8);
-            for item in fields_name.iter() {
-                let _ = hash_map.insert(item.0.clone(), item.1.clone());
-            }
-            for item in temp_fields_name.iter().enumerate() {
-                let fields = hash_map.get(*item.1).ok_or("fields handler error")?;
-                let mut temp = String::new();
-                if "String" == temp_fields_ty[item.0] {
-                    temp.push('\"');
-                    temp.push_str(fields);
-                    temp.push('\"');
-                } else {
-                    temp.push_str(fields);
-                }
-                new_fields.push(temp);
+    pub fn get_fields(&self, fields_name: Vec<&str>) -> Result<Vec<FieldValue>> {
+        if fields_name.is_empty() {
+            return Ok(vec![]);
+        }
+        if self.body.is_empty() && (self.path_fields.is_some() || self.query_fields.is_some()) {
+            let params: Vec<(String, String)> = self
+                .path_fields
+                .iter()
+                .chain(self.query_fields.iter())
+                .flatten()
+                .cloned()
+                .collect();
+            return Ok(fields_name
+                .into_iter()
+                .map(|name| {
+                    FieldValue::Params(name.to_owned(), get_values(&params, name), params.clone())
+                })
+                .collect());
+        }
+        let fields = self.get_body_fields()?;
+        if fields.len() < fields_name.len() {
+            return Err(invalid_param(fields_name[fields.len()], "missing value").into());
+        }
+        Ok(fields_name
+            .into_iter()
+            .zip(fields)
+            .map(|(name, bytes)| FieldValue::Bytes(name.to_owned(), bytes, self.serialization))
+            .collect())
+    }
+    //按顺序排列在body中的参数
+    pub fn get_body_fields(&self) -> Result<Vec<Bytes>> {
+        if let SerializationType::JSON = self.serialization {
+            if self.body.starts_with(b"[") {
+                let fields: Vec<String> = serde_json::from_slice(&self.body)?;
+                Ok(fields.into_iter().map(Bytes::from).collect())
+            } else {
+                Ok(vec![self.body.clone()])
             }
-        } else if self.body.starts_with(b"[") {
-            new_fields = serde_json::from_slice(&self.body)?;
         } else {
-            new_fields.push(String::from_utf8(self.body.to_vec())?);
+            let fields: Vec<serde_bytes::ByteBuf> = self.serialization.deserialize(&self.body)?;
+            Ok(fields
+                .into_iter()
+                .map(|e| Bytes::from(e.into_vec()))
+                .collect())
+        }
+    }
+}
+
+fn get_values(params: &[(String, String)], name: &str) -> Vec<String> {
+    params
+        .iter()
+        .filter(|e| e.0 == name)
+        .map(|e| e.1.clone())
+        .collect()
+}
+
+fn invalid_param(name: &str, err: impl Display) -> FusenError {
+    FusenError::status(
+        StatusCode::InvalidArgument,
+        format!("invalid parameter {} : {}", name, err),
+    )
+}
+
+//服务端方法参数的原始值,按照方法声明的类型进行转换
+#[derive(Debug)]
+pub enum FieldValue {
+    //按照请求的序列化方式解析
+    Bytes(String, Bytes, SerializationType),
+    //path/query/header中的字符串参数,最后一项为同一来源的全部参数,用于展开结构体
+    Params(String, Vec<String>, Vec<(String, String)>),
+}
+
+impl FieldValue {
+    pub fn deserialize<T: DeserializeOwned>(&self) -> FusenResult<T> {
+        match self {
+            FieldValue::Bytes(name, bytes, serialization) => serialization
+                .deserialize(bytes)
+                .map_err(|err| invalid_param(name, err)),
+            FieldValue::Params(name, values, params) => {
+                param::from_params(values, params).map_err(|err| invalid_param(name, err))
+            }
         }
-        Ok(new_fields)
     }
 }
 
@@ -223,6 +351,37 @@ pub struct FusenContext {
     context_info: ContextInfo,
     request: FusenRequest,
     response: FusenResponse,
+    //请求的截止时间,由grpc-timeout等请求头计算得出
+    deadline: Option<Instant>,
+    connection: ConnectionInfo,
+    //鉴权通过后的调用方身份
+    principal: Option<String>,
+    direction: Direction,
+}
+
+//请求的方向,由发起请求的客户端与解码请求的服务端分别标记
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Direction {
+    #[default]
+    Unknown,
+    Client,
+    Server,
+}
+
+//请求所在连接的信息,客户端发起的请求中为空
+#[derive(Debug, Clone, Default, Data)]
+pub struct ConnectionInfo {
+    remote_addr: Option<SocketAddr>,
+    local_addr: Option<SocketAddr>,
+    //HTTP/1.1, HTTP/2.0, HTTP/3.0, dubbo
+    protocol: String,
+    tls: Option<TlsInfo>,
+}
+
+#[derive(Debug, Clone, Default, Data)]
+pub struct TlsInfo {
+    alpn: Option<String>,
+    server_name: Option<String>,
 }
 
 impl FusenContext {
@@ -239,6 +398,10 @@ impl FusenContext {
             meta_data,
             request,
             response: Default::default(),
+            deadline: None,
+            connection: Default::default(),
+            principal: None,
+            direction: Direction::Unknown,
         }
     }
     pub fn insert_server_type(&mut self, server_tyep: Type) {
@@ -250,6 +413,40 @@ impl FusenContext {
     pub fn get_return_ty(&self) -> Option<&'static str> {
         self.response.response_ty
     }
+    //按照参数绑定获取参数,path/query/header中的参数按照声明的类型转换
+    pub fn get_binding_fields(&self, fields: Vec<(FieldBinding, &str)>) -> Result<Vec<FieldValue>> {
+        if !self.request.http_binding {
+            return self
+                .request
+                .get_fields(fields.into_iter().map(|e| e.1).collect());
+        }
+        let mut new_fields = vec![];
+        for (binding, name) in fields {
+            let params = match binding {
+                FieldBinding::Path => self.request.path_fields.clone().unwrap_or_default(),
+                FieldBinding::Query => self.request.query_fields.clone().unwrap_or_default(),
+                FieldBinding::Header => self
+                    .meta_data
+                    .get_value(name)
+                    .map(|e| vec![(name.to_owned(), e.clone())])
+                    .unwrap_or_default(),
+                FieldBinding::Body => {
+                    new_fields.push(FieldValue::Bytes(
+                        name.to_owned(),
+                        self.request.body.clone(),
+                        self.request.serialization,
+                    ));
+                    continue;
+                }
+            };
+            new_fields.push(FieldValue::Params(
+                name.to_owned(),
+                get_values(&params, name),
+                params,
+            ));
+        }
+        Ok(new_fields)
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -265,6 +462,9 @@ pub enum Path {
     PUT(String),
     DELETE(String),
     POST(String),
+    PATCH(String),
+    HEAD(String),
+    OPTIONS(String),
 }
 
 impl Default for Path {
@@ -275,45 +475,50 @@ impl Default for Path {
 
 impl Path {
     pub fn get_key(&self) -> String {
-        let mut key = String::new();
-        match self {
-            Path::GET(path) => {
-                key.push_str("get:");
-                key.push_str(path);
-            }
-            Path::POST(path) => {
-                key.push_str("post:");
-                key.push_str(path);
-            }
-            Path::PUT(path) => {
-                key.push_str("put:");
-                key.push_str(path);
-            }
-            Path::DELETE(path) => {
-                key.push_str("delete:");
-                key.push_str(path);
-            }
-        };
+        let mut key = self.get_method().to_lowercase();
+        key.push(':');
+        key.push_str(self.get_path_ref());
         key
     }
 
     pub fn update_path(&mut self, new_path: String) {
         match self {
-            Path::GET(path) => *path = new_path,
-            Path::POST(path) => *path = new_path,
-            Path::PUT(path) => *path = new_path,
-            Path::DELETE(path) => *path = new_path,
+            Path::GET(path)
+            | Path::POST(path)
+            | Path::PUT(path)
+            | Path::DELETE(path)
+            | Path::PATCH(path)
+            | Path::HEAD(path)
+            | Path::OPTIONS(path) => *path = new_path,
         }
     }
 
     pub fn get_path(&self) -> String {
+        self.get_path_ref().clone()
+    }
+
+    fn get_path_ref(&self) -> &String {
+        match self {
+            Path::GET(path)
+            | Path::POST(path)
+            | Path::PUT(path)
+            | Path::DELETE(path)
+            | Path::PATCH(path)
+            | Path::HEAD(path)
+            | Path::OPTIONS(path) => path,
+        }
+    }
+
+    pub fn get_method(&self) -> &'static str {
         match self {
-            Path::GET(path) => path,
-            Path::POST(path) => path,
-            Path::PUT(path) => path,
-            Path::DELETE(path) => path,
+            Path::GET(_) => "GET",
+            Path::POST(_) => "POST",
+            Path::PUT(_) => "PUT",
+            Path::DELETE(_) => "DELETE",
+            Path::PATCH(_) => "PATCH",
+            Path::HEAD(_) => "HEAD",
+            Path::OPTIONS(_) => "OPTIONS",
         }
-        .clone()
     }
 
     pub fn new(method: &str, path: String) -> Self {
@@ -321,6 +526,9 @@ impl Path {
             "get" => Self::GET(path),
             "put" => Self::PUT(path),
             "delete" => Self::DELETE(path),
+            "patch" => Self::PATCH(path),
+            "head" => Self::HEAD(path),
+            "options" => Self::OPTIONS(path),
             _ => Self::POST(path),
         }
     }
@@ -0,0 +1,431 @@
+use crate::{
+    codec::SerializationFormat,
+    error::FusenError,
+    server::{RpcServer, ServerInfo},
+    FieldBinding, FusenContext, FusenFuture, MethodResource,
+};
+use fusen_procedural_macro::Data;
+pub use schemars;
+pub use schemars::gen::SchemaGenerator;
+use schemars::{gen::SchemaSettings, schema::Schema};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::path::Path;
+
+//OpenAPI文档的访问路径及文档信息
+#[derive(Serialize, Deserialize, Default, Clone, Debug, Data)]
+pub struct OpenApiConfig {
+    path: Option<String>,
+    title: Option<String>,
+    version: Option<String>,
+}
+
+//#[fusen_trait(openapi = true)]生成的get_openapi
+pub type OpenApiFn = fn(&mut SchemaGenerator) -> ServiceDoc;
+
+#[derive(Debug, Data)]
+pub struct ServiceDoc {
+    id: String,
+    version: Option<String>,
+    group: Option<String>,
+    methods: Vec<MethodDoc>,
+}
+
+impl ServiceDoc {
+    pub fn new(
+        id: &str,
+        version: Option<&str>,
+        group: Option<&str>,
+        methods: Vec<MethodDoc>,
+    ) -> Self {
+        ServiceDoc {
+            id: id.to_owned(),
+            version: version.map(|e| e.to_owned()),
+            group: group.map(|e| e.to_owned()),
+            methods,
+        }
+    }
+
+    fn get_descriptor(&self) -> Value {
+        let methods: Vec<Value> = self
+            .methods
+            .iter()
+            .map(|method| {
+                let params: Vec<Value> = method
+                    .params
+                    .iter()
+                    .map(|param| {
+                        json!({
+                            "name": param.name,
+                            "in": get_location(&method.get_binding(param)),
+                            "required": param.required,
+                            "schema": param.schema,
+                        })
+                    })
+                    .collect();
+                json!({
+                    "name": method.name,
+                    "path": method.path,
+                    "method": method.method,
+                    "params": params,
+                    "response": method.response,
+                })
+            })
+            .collect();
+        json!({
+            "id": self.id,
+            "version": self.version,
+            "group": self.group,
+            "methods": methods,
+        })
+    }
+}
+
+#[derive(Debug, Data)]
+pub struct MethodDoc {
+    name: String,
+    path: String,
+    method: String,
+    params: Vec<ParamDoc>,
+    response: Schema,
+}
+
+impl MethodDoc {
+    pub fn new(
+        name: &str,
+        path: &str,
+        method: &str,
+        params: Vec<ParamDoc>,
+        response: Schema,
+    ) -> Self {
+        MethodDoc {
+            name: name.to_owned(),
+            path: path.to_owned(),
+            method: method.to_uppercase(),
+            params,
+            response,
+        }
+    }
+
+    //未声明参数绑定时,POST请求的参数在请求体中,其余请求方法作为path或query
+    fn get_binding(&self, param: &ParamDoc) -> FieldBinding {
+        match param.binding {
+            Some(binding) => binding,
+            None if self.method == "POST" => FieldBinding::Body,
+            None if get_openapi_path(&self.path).contains(&format!("{{{}}}", param.name)) => {
+                FieldBinding::Path
+            }
+            None => FieldBinding::Query,
+        }
+    }
+
+    fn get_operation(&self, id: &str) -> Value {
+        let mut parameters = vec![];
+        let mut bodys = vec![];
+        for param in &self.params {
+            let binding = self.get_binding(param);
+            if let FieldBinding::Body = binding {
+                bodys.push(param);
+                continue;
+            }
+            parameters.push(json!({
+                "name": param.name,
+                "in": get_location(&binding),
+                "required": param.required || matches!(binding, FieldBinding::Path),
+                "schema": param.schema,
+            }));
+        }
+        let mut operation = json!({
+            "operationId": format!("{}.{}", id, self.name),
+            "tags": [id],
+            "responses": {
+                "200": {
+                    "description": "OK",
+                    "content": {"application/json": {"schema": self.response}},
+                }
+            },
+        });
+        if !parameters.is_empty() {
+            operation["parameters"] = Value::Array(parameters);
+        }
+        //多个参数时请求体为按顺序排列的参数,每个参数单独序列化为JSON字符串
+        let body = match bodys[..] {
+            [] => None,
+            [param] => Some(json!(param.schema)),
+            _ => Some(json!({
+                "type": "array",
+                "items": {"type": "string"},
+                "description": format!(
+                    "JSON encoded arguments in order : {}",
+                    bodys.iter().map(|e| e.name.as_str()).collect::<Vec<_>>().join(", ")
+                ),
+            })),
+        };
+        if let Some(schema) = body {
+            operation["requestBody"] = json!({
+                "required": true,
+                "content": {"application/json": {"schema": schema}},
+            });
+        }
+        operation
+    }
+}
+
+#[derive(Debug, Data)]
+pub struct ParamDoc {
+    name: String,
+    binding: Option<FieldBinding>,
+    required: bool,
+    schema: Schema,
+}
+
+impl ParamDoc {
+    pub fn new(name: &str, binding: Option<FieldBinding>, required: bool, schema: Schema) -> Self {
+        ParamDoc {
+            name: name.to_owned(),
+            binding,
+            required,
+            schema,
+        }
+    }
+}
+
+fn get_location(binding: &FieldBinding) -> &'static str {
+    match binding {
+        FieldBinding::Path => "path",
+        FieldBinding::Query => "query",
+        FieldBinding::Header => "header",
+        FieldBinding::Body => "body",
+    }
+}
+
+//{id:[0-9]+}与{*rest}在OpenAPI中均为{name}
+fn get_openapi_path(path: &str) -> String {
+    path.split('/')
+        .map(
+            |segment| match segment.strip_prefix('{').and_then(|e| e.strip_suffix('}')) {
+                Some(inner) => {
+                    let name = inner.trim_start_matches('*');
+                    format!("{{{}}}", name.split(':').next().unwrap_or(name))
+                }
+                None => segment.to_owned(),
+            },
+        )
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+pub fn get_openapi(config: &OpenApiConfig, docs: &[OpenApiFn]) -> Value {
+    let mut generator = SchemaSettings::openapi3().into_generator();
+    let mut paths = Map::new();
+    for doc in docs {
+        let doc = doc(&mut generator);
+        for method in &doc.methods {
+            let item = paths
+                .entry(get_openapi_path(&method.path))
+                .or_insert_with(|| json!({}));
+            item[method.method.to_lowercase()] = method.get_operation(&doc.id);
+        }
+    }
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": config.title.as_deref().unwrap_or("fusen"),
+            "version": config.version.as_deref().unwrap_or("1.0.0"),
+        },
+        "paths": paths,
+        "components": {"schemas": generator.take_definitions()},
+    })
+}
+
+//接口描述文件,包含服务id、版本、分组、方法路径以及参数和返回值的JSON Schema
+//可供其他语言生成对应的接口,也可以在CI中比较是否有不兼容的改动
+pub fn get_descriptor(docs: &[OpenApiFn]) -> Value {
+    let mut generator = SchemaSettings::draft07().into_generator();
+    let services: Vec<Value> = docs
+        .iter()
+        .map(|doc| doc(&mut generator).get_descriptor())
+        .collect();
+    json!({
+        "services": services,
+        "definitions": generator.take_definitions(),
+    })
+}
+
+//设置该环境变量为overwrite时check_descriptor重新生成描述文件
+pub const DESCRIPTOR_ENV: &str = "FUSEN_DESCRIPTOR";
+
+fn get_descriptor_string(docs: &[OpenApiFn]) -> std::io::Result<String> {
+    Ok(serde_json::to_string_pretty(&get_descriptor(docs))? + "\n")
+}
+
+//内容没有变化时不重写文件,可以在build.rs或单独的bin中调用
+pub fn write_descriptor(path: impl AsRef<Path>, docs: &[OpenApiFn]) -> std::io::Result<()> {
+    let descriptor = get_descriptor_string(docs)?;
+    if std::fs::read_to_string(&path).is_ok_and(|e| e == descriptor) {
+        return Ok(());
+    }
+    if let Some(parent) = path.as_ref().parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, descriptor)
+}
+
+//与已提交的描述文件比较,接口有改动时返回错误,供CI发现不兼容的改动
+pub fn check_descriptor(path: impl AsRef<Path>, docs: &[OpenApiFn]) -> std::io::Result<()> {
+    if std::env::var(DESCRIPTOR_ENV).is_ok_and(|e| e == "overwrite") {
+        return write_descriptor(path, docs);
+    }
+    let descriptor = get_descriptor_string(docs)?;
+    match std::fs::read_to_string(&path) {
+        Ok(committed) if committed == descriptor => Ok(()),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!(
+                "descriptor {} is out of date, review the api change and rerun with {}=overwrite",
+                path.as_ref().display(),
+                DESCRIPTOR_ENV
+            ),
+        )),
+    }
+}
+
+//在配置的路径上以GET方式提供OpenAPI文档
+pub struct OpenApiServer {
+    info: ServerInfo,
+    document: Value,
+}
+
+impl OpenApiServer {
+    pub fn new(config: &OpenApiConfig, docs: &[OpenApiFn]) -> Self {
+        let path = config
+            .path
+            .clone()
+            .unwrap_or_else(|| "/openapi.json".to_owned());
+        let method = MethodResource::new("openapi".to_owned(), path, "GET".to_owned());
+        OpenApiServer {
+            info: ServerInfo::new("OpenApi", None, None, vec![method]),
+            document: get_openapi(config, docs),
+        }
+    }
+}
+
+impl RpcServer for OpenApiServer {
+    fn invoke(&'static self, mut context: FusenContext) -> FusenFuture<FusenContext> {
+        Box::pin(async move {
+            let serialization = context.get_meta_data().get_accept_serialization();
+            let response = SerializationFormat::serialize(&serialization, &self.document)
+                .map_err(|err| FusenError::from(err.to_string()));
+            context.get_mut_response().set_response(response);
+            context
+        })
+    }
+
+    fn get_info(&self) -> ServerInfo {
+        self.info.clone()
+    }
+}
+
+#[test]
+fn test() {
+    fn get_user_doc(generator: &mut SchemaGenerator) -> ServiceDoc {
+        #[allow(dead_code)]
+        #[derive(schemars::JsonSchema)]
+        struct User {
+            name: String,
+            age: Option<u8>,
+        }
+        ServiceDoc::new(
+            "UserService",
+            None,
+            Some("test"),
+            vec![
+                MethodDoc::new(
+                    "update",
+                    "/user/{id:[0-9]+}",
+                    "PATCH",
+                    vec![
+                        ParamDoc::new(
+                            "id",
+                            Some(FieldBinding::Path),
+                            true,
+                            generator.subschema_for::<i64>(),
+                        ),
+                        ParamDoc::new(
+                            "x-tenant-id",
+                            Some(FieldBinding::Header),
+                            false,
+                            generator.subschema_for::<Option<String>>(),
+                        ),
+                        ParamDoc::new(
+                            "user",
+                            Some(FieldBinding::Body),
+                            true,
+                            generator.subschema_for::<User>(),
+                        ),
+                    ],
+                    generator.subschema_for::<User>(),
+                ),
+                MethodDoc::new(
+                    "get",
+                    "/user/{id}",
+                    "GET",
+                    vec![ParamDoc::new(
+                        "id",
+                        None,
+                        true,
+                        generator.subschema_for::<i64>(),
+                    )],
+                    generator.subschema_for::<User>(),
+                ),
+                MethodDoc::new(
+                    "add",
+                    "/add",
+                    "post",
+                    vec![
+                        ParamDoc::new("a", None, true, generator.subschema_for::<i32>()),
+                        ParamDoc::new("b", None, true, generator.subschema_for::<i32>()),
+                    ],
+                    generator.subschema_for::<i32>(),
+                ),
+            ],
+        )
+    }
+    let document = get_openapi(&OpenApiConfig::default(), &[get_user_doc]);
+    let operation = &document["paths"]["/user/{id}"]["patch"];
+    assert_eq!(operation["operationId"], "UserService.update");
+    assert_eq!(operation["parameters"][0]["in"], "path");
+    assert_eq!(operation["parameters"][1]["in"], "header");
+    assert_eq!(operation["parameters"][1]["required"], false);
+    assert_eq!(
+        operation["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+        "#/components/schemas/User"
+    );
+    assert!(document["components"]["schemas"]["User"]["properties"]["age"].is_object());
+    assert_eq!(
+        document["paths"]["/user/{id}"]["get"]["parameters"][0]["in"],
+        "path"
+    );
+    let operation = &document["paths"]["/add"]["post"];
+    assert_eq!(
+        operation["requestBody"]["content"]["application/json"]["schema"]["type"],
+        "array"
+    );
+    let descriptor = get_descriptor(&[get_user_doc]);
+    let service = &descriptor["services"][0];
+    assert_eq!(service["group"], "test");
+    assert_eq!(service["methods"][0]["params"][2]["in"], "body");
+    assert_eq!(service["methods"][1]["params"][0]["in"], "path");
+    assert_eq!(
+        service["methods"][0]["response"]["$ref"],
+        "#/definitions/User"
+    );
+    assert!(descriptor["definitions"]["User"].is_object());
+    let path = std::env::temp_dir().join(format!("fusen-descriptor-{}.json", std::process::id()));
+    assert!(check_descriptor(&path, &[get_user_doc]).is_err());
+    write_descriptor(&path, &[get_user_doc]).unwrap();
+    check_descriptor(&path, &[get_user_doc]).unwrap();
+    std::fs::write(&path, "{}").unwrap();
+    assert!(check_descriptor(&path, &[get_user_doc]).is_err());
+    let _ = std::fs::remove_file(path);
+}
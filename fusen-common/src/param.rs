@@ -0,0 +1,400 @@
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess, SeqAccess,
+    Visitor,
+};
+use std::fmt::Display;
+
+//path,query与header中的参数均为字符串,根据方法声明的类型进行转换
+#[derive(Debug)]
+pub struct ParamError(String);
+
+impl Display for ParamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ParamError {}
+
+impl de::Error for ParamError {
+    fn custom<T: Display>(msg: T) -> Self {
+        ParamError(msg.to_string())
+    }
+}
+
+//values为参数名对应的全部值,重复的key对应Vec<T>
+//参数名不存在且类型为结构体时,使用params中的全部参数展开为结构体的字段
+pub fn from_params<T: DeserializeOwned>(
+    values: &[String],
+    params: &[(String, String)],
+) -> Result<T, ParamError> {
+    T::deserialize(ParamsDeserializer { values, params })
+}
+
+struct ParamsDeserializer<'a> {
+    values: &'a [String],
+    params: &'a [(String, String)],
+}
+
+impl<'a> ParamsDeserializer<'a> {
+    fn value(&self) -> Result<ValueDeserializer<'a>, ParamError> {
+        self.values
+            .last()
+            .map(|e| ValueDeserializer(e))
+            .ok_or_else(|| ParamError("missing value".to_owned()))
+    }
+}
+
+macro_rules! forward_to_value {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParamError> {
+                self.value()?.$method(visitor)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for ParamsDeserializer<'_> {
+    type Error = ParamError;
+
+    forward_to_value! {
+        deserialize_any deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32
+        deserialize_i64 deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32
+        deserialize_u64 deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char
+        deserialize_str deserialize_string deserialize_bytes deserialize_byte_buf
+        deserialize_unit deserialize_identifier deserialize_ignored_any
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParamError> {
+        if self.values.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParamError> {
+        visitor.visit_seq(ValuesAccess(self.values.iter().map(|e| e.as_str())))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ParamError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParamError> {
+        if self.values.is_empty() {
+            visitor.visit_map(ParamsAccess::new(self.params))
+        } else {
+            self.value()?.deserialize_map(visitor)
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ParamError> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ParamError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ParamError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ParamError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ParamError> {
+        self.value()?.deserialize_enum(name, variants, visitor)
+    }
+}
+
+struct ValueDeserializer<'a>(&'a str);
+
+impl ValueDeserializer<'_> {
+    fn parse<T: std::str::FromStr>(&self) -> Result<T, ParamError>
+    where
+        T::Err: Display,
+    {
+        self.0
+            .parse()
+            .map_err(|err| ParamError(format!("{:?} {}", self.0, err)))
+    }
+
+    //结构体等复杂类型兼容以JSON文本传递
+    fn json(&self) -> Result<serde_json::Value, ParamError> {
+        serde_json::from_str(self.0).map_err(|err| ParamError(format!("{:?} {}", self.0, err)))
+    }
+}
+
+macro_rules! parse_value {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParamError> {
+                visitor.$visit(self.parse()?)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for ValueDeserializer<'_> {
+    type Error = ParamError;
+
+    parse_value! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParamError> {
+        visitor.visit_str(self.0)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParamError> {
+        visitor.visit_str(self.0)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParamError> {
+        visitor.visit_str(self.0)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParamError> {
+        visitor.visit_str(self.0)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParamError> {
+        visitor.visit_bytes(self.0.as_bytes())
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParamError> {
+        visitor.visit_bytes(self.0.as_bytes())
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParamError> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParamError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ParamError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, ParamError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParamError> {
+        visitor.visit_seq(ValuesAccess(std::iter::once(self.0)))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ParamError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, ParamError> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParamError> {
+        self.json()?
+            .deserialize_map(visitor)
+            .map_err(de::Error::custom)
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ParamError> {
+        self.json()?
+            .deserialize_struct(name, fields, visitor)
+            .map_err(de::Error::custom)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, ParamError> {
+        visitor.visit_enum(IntoDeserializer::<ParamError>::into_deserializer(self.0))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ParamError> {
+        visitor.visit_unit()
+    }
+}
+
+struct ValuesAccess<I>(I);
+
+impl<'de, 'a, I: Iterator<Item = &'a str>> SeqAccess<'de> for ValuesAccess<I> {
+    type Error = ParamError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, ParamError> {
+        match self.0.next() {
+            Some(value) => seed.deserialize(ValueDeserializer(value)).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+//按照参数名分组,保持参数出现的顺序
+struct ParamsAccess {
+    entries: std::vec::IntoIter<(String, Vec<String>)>,
+    values: Vec<String>,
+}
+
+impl ParamsAccess {
+    fn new(params: &[(String, String)]) -> Self {
+        let mut entries: Vec<(String, Vec<String>)> = vec![];
+        for (key, value) in params {
+            match entries.iter_mut().find(|e| &e.0 == key) {
+                Some(entry) => entry.1.push(value.clone()),
+                None => entries.push((key.clone(), vec![value.clone()])),
+            }
+        }
+        ParamsAccess {
+            entries: entries.into_iter(),
+            values: vec![],
+        }
+    }
+}
+
+impl<'de> MapAccess<'de> for ParamsAccess {
+    type Error = ParamError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, ParamError> {
+        match self.entries.next() {
+            Some((key, values)) => {
+                self.values = values;
+                seed.deserialize(ValueDeserializer(&key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, ParamError> {
+        let values = std::mem::take(&mut self.values);
+        seed.deserialize(ParamsDeserializer {
+            values: &values,
+            params: &[],
+        })
+    }
+}
+
+#[test]
+fn test() {
+    use serde::Deserialize;
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Status {
+        Active,
+    }
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Query {
+        name: String,
+        age: Option<u8>,
+        tags: Vec<String>,
+        status: Status,
+    }
+    let values = |values: &[&str]| values.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+    assert_eq!(from_params::<i64>(&values(&["7"]), &[]).unwrap(), 7);
+    assert_eq!(
+        from_params::<String>(&values(&["007"]), &[]).unwrap(),
+        "007"
+    );
+    assert_eq!(from_params::<char>(&values(&["中"]), &[]).unwrap(), '中');
+    assert_eq!(from_params::<Option<String>>(&[], &[]).unwrap(), None);
+    assert_eq!(
+        from_params::<Vec<u8>>(&values(&["1", "2"]), &[]).unwrap(),
+        vec![1, 2]
+    );
+    assert_eq!(
+        from_params::<Status>(&values(&["Active"]), &[]).unwrap(),
+        Status::Active
+    );
+    assert!(from_params::<i32>(&values(&["abc"]), &[]).is_err());
+    assert!(from_params::<i32>(&[], &[]).is_err());
+    let params = [("name", "123"), ("tags", "x"), ("status", "Active")]
+        .map(|(key, value)| (key.to_owned(), value.to_owned()));
+    assert_eq!(
+        from_params::<Query>(&[], &params).unwrap(),
+        Query {
+            name: "123".to_owned(),
+            age: None,
+            tags: vec!["x".to_owned()],
+            status: Status::Active
+        }
+    );
+    let json = values(&[r#"{"name":"a","age":1,"tags":[],"status":"Active"}"#]);
+    assert_eq!(from_params::<Query>(&json, &[]).unwrap().age, Some(1));
+}
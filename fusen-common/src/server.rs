@@ -1,13 +1,21 @@
+use crate::{
+    codec::{SerializationFormat, SerializationType},
+    error::{FusenError, StatusCode},
+    FieldBinding, FusenContext, FusenFuture, FusenRequest, FusenResult, MethodResource,
+};
 use fusen_procedural_macro::Data;
-
-use crate::{FusenContext, FusenFuture, MethodResource};
+use serde_json::Value;
+use std::{collections::HashMap, fmt::Display};
 
 pub trait RpcServer: Send + Sync {
     fn invoke(&'static self, msg: FusenContext) -> FusenFuture<FusenContext>;
     fn get_info(&self) -> ServerInfo;
+    fn is_generic(&self) -> bool {
+        false
+    }
 }
 
-#[derive(Debug, Data)]
+#[derive(Debug, Clone, Data)]
 pub struct ServerInfo {
     pub id: String,
     pub version: Option<String>,
@@ -30,3 +38,326 @@ impl ServerInfo {
         }
     }
 }
+
+//#[fusen_trait]生成的路由信息,#[fusen_server]的路径、请求方法和参数绑定都以此为准
+#[derive(Debug)]
+pub struct TraitResource {
+    pub id: &'static str,
+    pub path: &'static str,
+    pub method: &'static str,
+    pub methods: &'static [TraitMethod],
+}
+
+#[derive(Debug)]
+pub struct TraitMethod {
+    pub name: &'static str,
+    pub path: &'static str,
+    pub method: &'static str,
+    pub fields: &'static [&'static str],
+    pub bindings: Option<&'static [(FieldBinding, &'static str)]>,
+}
+
+impl TraitResource {
+    pub const fn find_method(&self, name: &str) -> Option<&'static TraitMethod> {
+        let mut idx = 0;
+        while idx < self.methods.len() {
+            if str_eq(self.methods[idx].name, name, false) {
+                return Some(&self.methods[idx]);
+            }
+            idx += 1;
+        }
+        None
+    }
+
+    pub const fn get_method(&self, name: &str) -> &'static TraitMethod {
+        match self.find_method(name) {
+            Some(method) => method,
+            None => panic!("method is not declared in fusen_trait"),
+        }
+    }
+
+    //只比较实现上显式声明的部分
+    pub const fn is_route_match(&self, path: Option<&str>, method: Option<&str>) -> bool {
+        if let Some(path) = path {
+            if !str_eq(self.path, path, false) {
+                return false;
+            }
+        }
+        match method {
+            Some(method) => str_eq(self.method, method, true),
+            None => true,
+        }
+    }
+
+    pub fn get_methods(&self) -> Vec<MethodResource> {
+        self.methods
+            .iter()
+            .map(|e| MethodResource::new(e.name.to_owned(), e.path.to_owned(), e.method.to_owned()))
+            .collect()
+    }
+}
+
+impl TraitMethod {
+    //方法路径以接口声明的父路径为前缀,只比较实现上显式声明的部分
+    pub const fn is_route_match(
+        &self,
+        parent: &str,
+        path: Option<&str>,
+        method: Option<&str>,
+    ) -> bool {
+        if let Some(path) = path {
+            let (full, parent, path) = (self.path.as_bytes(), parent.as_bytes(), path.as_bytes());
+            if full.len() != parent.len() + path.len() {
+                return false;
+            }
+            let mut idx = 0;
+            while idx < full.len() {
+                let byte = match idx < parent.len() {
+                    true => parent[idx],
+                    false => path[idx - parent.len()],
+                };
+                if full[idx] != byte {
+                    return false;
+                }
+                idx += 1;
+            }
+        }
+        match method {
+            Some(method) => str_eq(self.method, method, true),
+            None => true,
+        }
+    }
+
+    pub const fn is_binding_match(&self, bindings: &[(FieldBinding, &str)]) -> bool {
+        let Some(declared) = self.bindings else {
+            return false;
+        };
+        if declared.len() != bindings.len() {
+            return false;
+        }
+        let mut idx = 0;
+        while idx < declared.len() {
+            if declared[idx].0 as u8 != bindings[idx].0 as u8
+                || !str_eq(declared[idx].1, bindings[idx].1, false)
+            {
+                return false;
+            }
+            idx += 1;
+        }
+        true
+    }
+}
+
+const fn str_eq(left: &str, right: &str, ignore_case: bool) -> bool {
+    let (left, right) = (left.as_bytes(), right.as_bytes());
+    if left.len() != right.len() {
+        return false;
+    }
+    let mut idx = 0;
+    while idx < left.len() {
+        let (mut l, mut r) = (left[idx], right[idx]);
+        if ignore_case {
+            (l, r) = (l.to_ascii_lowercase(), r.to_ascii_lowercase());
+        }
+        if l != r {
+            return false;
+        }
+        idx += 1;
+    }
+    true
+}
+
+//泛化服务,不需要接口定义即可处理任意方法的调用,参数与返回值统一使用JSON
+pub trait GenericServer: Send + Sync {
+    fn invoke(&'static self, request: GenericRequest) -> FusenFuture<FusenResult<Value>>;
+}
+
+#[derive(Debug, Data)]
+pub struct GenericRequest {
+    method_name: String,
+    args: Vec<Value>,
+    headers: HashMap<String, String>,
+}
+
+pub struct GenericRpcServer {
+    info: ServerInfo,
+    server: &'static dyn GenericServer,
+}
+
+impl GenericRpcServer {
+    pub fn new(
+        id: &str,
+        version: Option<&str>,
+        group: Option<&str>,
+        server: Box<dyn GenericServer>,
+    ) -> Self {
+        GenericRpcServer {
+            info: ServerInfo::new(id, version, group, vec![]),
+            server: Box::leak(server),
+        }
+    }
+}
+
+//fusen与Dubbo客户端按顺序传递参数,每个参数单独序列化
+//其他HTTP请求的参数来自query与body,query中的值保持为字符串,body为JSON数组时展开为多个参数
+fn get_generic_args(request: &FusenRequest) -> FusenResult<Vec<Value>> {
+    let body = request.get_body();
+    let serialization = request.get_serialization();
+    if *request.get_http_binding() {
+        let mut args: Vec<Value> = request
+            .get_query_fields()
+            .iter()
+            .flatten()
+            .map(|(_, value)| Value::String(value.clone()))
+            .collect();
+        if !body.is_empty() {
+            match serialization
+                .deserialize::<Value>(body)
+                .map_err(invalid_args)?
+            {
+                Value::Array(items) => args.extend(items),
+                value => args.push(value),
+            }
+        }
+        return Ok(args);
+    }
+    if body.is_empty() {
+        return Ok(vec![]);
+    }
+    match serialization {
+        SerializationType::JSON => serde_json::from_slice::<Vec<String>>(body)
+            .map_err(invalid_args)?
+            .iter()
+            .map(|field| serde_json::from_str(field).map_err(invalid_args))
+            .collect(),
+        serialization => {
+            let fields: Vec<serde_bytes::ByteBuf> =
+                serialization.deserialize(body).map_err(invalid_args)?;
+            let mut args = vec![];
+            for field in fields {
+                args.push(serialization.deserialize(&field).map_err(invalid_args)?);
+            }
+            Ok(args)
+        }
+    }
+}
+
+fn invalid_args(err: impl Display) -> FusenError {
+    FusenError::status(
+        StatusCode::InvalidArgument,
+        format!("invalid generic args : {}", err),
+    )
+}
+
+impl RpcServer for GenericRpcServer {
+    fn invoke(&'static self, mut context: FusenContext) -> FusenFuture<FusenContext> {
+        Box::pin(async move {
+            let args = match get_generic_args(context.get_request()) {
+                Ok(args) => args,
+                Err(err) => {
+                    context.get_mut_response().set_response(Err(err));
+                    return context;
+                }
+            };
+            let request = GenericRequest {
+                method_name: context.get_context_info().get_method_name().clone(),
+                args,
+                headers: context.get_meta_data().clone_map(),
+            };
+            let serialization = context.get_meta_data().get_accept_serialization();
+            let response = match self.server.invoke(request).await {
+                Ok(res) => serialization
+                    .serialize(&res)
+                    .map_err(|err| FusenError::from(err.to_string())),
+                Err(err) => Err(err),
+            };
+            context.get_mut_response().set_response(response);
+            context
+        })
+    }
+
+    fn get_info(&self) -> ServerInfo {
+        self.info.clone()
+    }
+
+    fn is_generic(&self) -> bool {
+        true
+    }
+}
+
+#[test]
+fn test() {
+    use bytes::Bytes;
+    let request = FusenRequest::new(None, Bytes::from(r#"["1","[2,3]","\"a\""]"#))
+        .serialization(SerializationType::JSON);
+    assert_eq!(
+        get_generic_args(&request).unwrap(),
+        vec![Value::from(1), serde_json::json!([2, 3]), Value::from("a")]
+    );
+    //不是合法JSON的参数返回InvalidArgument,而不是当作字符串
+    let request =
+        FusenRequest::new(None, Bytes::from(r#"["1","a"]"#)).serialization(SerializationType::JSON);
+    assert_eq!(
+        get_generic_args(&request).unwrap_err().code(),
+        StatusCode::InvalidArgument
+    );
+    let request = FusenRequest::new(None, Bytes::from(r#"[1,{"a":1}]"#))
+        .serialization(SerializationType::JSON)
+        .http_binding(true);
+    assert_eq!(
+        get_generic_args(&request).unwrap(),
+        vec![Value::from(1), serde_json::json!({"a": 1})]
+    );
+    //query中的值保持为字符串
+    let request = FusenRequest::new(
+        Some(vec![
+            ("id".to_owned(), "123".to_owned()),
+            ("flag".to_owned(), "true".to_owned()),
+        ]),
+        Bytes::new(),
+    )
+    .http_binding(true);
+    assert_eq!(
+        get_generic_args(&request).unwrap(),
+        vec![Value::from("123"), Value::from("true")]
+    );
+    const RESOURCE: TraitResource = TraitResource {
+        id: "UserService",
+        path: "/UserService",
+        method: "POST",
+        methods: &[TraitMethod {
+            name: "update",
+            path: "/UserService/user/{id}",
+            method: "PATCH",
+            fields: &["id", "user"],
+            bindings: Some(&[(FieldBinding::Path, "id"), (FieldBinding::Body, "user")]),
+        }],
+    };
+    const METHOD: &TraitMethod = RESOURCE.get_method("update");
+    assert!(METHOD.is_route_match("/UserService", Some("/user/{id}"), Some("patch")));
+    assert!(METHOD.is_route_match("/UserService", Some("/user/{id}"), None));
+    assert!(METHOD.is_route_match("/UserService", None, Some("PATCH")));
+    assert!(!METHOD.is_route_match("/UserService", Some("/users/{id}"), Some("PATCH")));
+    assert!(!METHOD.is_route_match("/UserService", None, Some("GET")));
+    assert!(RESOURCE.is_route_match(None, Some("post")));
+    assert!(!RESOURCE.is_route_match(Some("/api"), None));
+    assert!(METHOD.is_binding_match(&[(FieldBinding::Path, "id"), (FieldBinding::Body, "user")]));
+    assert!(!METHOD.is_binding_match(&[(FieldBinding::Query, "id"), (FieldBinding::Body, "user")]));
+    assert_eq!(RESOURCE.get_methods()[0].get_method(), "PATCH");
+    assert!(RESOURCE.find_method("delete").is_none());
+    struct MockServer;
+    impl GenericServer for MockServer {
+        fn invoke(&'static self, _: GenericRequest) -> FusenFuture<FusenResult<Value>> {
+            Box::pin(async { Ok(Value::Null) })
+        }
+    }
+    let server = GenericRpcServer::new(
+        "MockService",
+        Some("1.0.0"),
+        Some("g"),
+        Box::new(MockServer),
+    );
+    let info = server.get_info();
+    assert_eq!(info.group.as_deref(), Some("g"));
+    assert_eq!(info.version.as_deref(), Some("1.0.0"));
+}
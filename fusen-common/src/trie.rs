@@ -1,19 +1,28 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::collections::HashMap;
 
 use fusen_procedural_macro::Data;
+use regex::Regex;
 
+//按照 静态段 > 正则约束的变量段 > 变量段 > 通配段 的优先级进行匹配
 #[derive(Debug, Default)]
 pub struct Trie {
-    root: Rc<RefCell<TreeNode>>,
+    root: TreeNode,
 }
 
-unsafe impl Sync for Trie {}
-unsafe impl Send for Trie {}
-
 #[derive(Debug, Default)]
 struct TreeNode {
-    nodes: HashMap<String, Rc<RefCell<TreeNode>>>,
-    value: Option<String>,
+    nodes: HashMap<String, TreeNode>,
+    //正则约束的变量段排在前面
+    params: Vec<(Option<Regex>, TreeNode)>,
+    //key为请求方法
+    catch_all: HashMap<String, Route>,
+    routes: HashMap<String, Route>,
+}
+
+#[derive(Debug)]
+struct Route {
+    path: String,
+    names: Vec<String>,
 }
 
 #[derive(Debug, Data)]
@@ -22,110 +31,207 @@ pub struct QueryResult {
     pub query_fields: Option<Vec<(String, String)>>,
 }
 
+enum Segment<'a> {
+    Static(&'a str),
+    //{id} 或 {id:[0-9]+}
+    Param(&'a str, Option<Regex>),
+    //{*rest}
+    CatchAll(&'a str),
+}
+
+impl<'a> Segment<'a> {
+    fn parse(segment: &'a str) -> Result<Self, String> {
+        let Some(inner) = segment.strip_prefix('{').and_then(|e| e.strip_suffix('}')) else {
+            return Ok(Segment::Static(segment));
+        };
+        if let Some(name) = inner.strip_prefix('*') {
+            return Ok(Segment::CatchAll(name));
+        }
+        match inner.split_once(':') {
+            Some((name, regex)) => {
+                let regex = Regex::new(&format!("^(?:{})$", regex))
+                    .map_err(|err| format!("invalid path segment {} : {}", segment, err))?;
+                Ok(Segment::Param(name, Some(regex)))
+            }
+            None => Ok(Segment::Param(inner, None)),
+        }
+    }
+}
+
 impl Trie {
-    pub fn insert(&mut self, path: String) {
-        let paths: Vec<&str> = path.split('/').collect();
-        let mut temp = self.root.clone();
-        for item in paths {
-            let res_node = temp.as_ref().borrow().nodes.get(item).cloned();
-            match res_node {
-                Some(node) => {
-                    temp = node;
+    pub fn insert(&mut self, method: &str, path: String) -> Result<(), String> {
+        let segments: Vec<&str> = path.split('/').collect();
+        let mut names = vec![];
+        let mut node = &mut self.root;
+        for (idx, segment) in segments.iter().enumerate() {
+            match Segment::parse(segment)? {
+                Segment::Static(segment) => {
+                    node = node.nodes.entry(segment.to_owned()).or_default();
                 }
-                None => {
-                    let new_node = Rc::new(RefCell::new(Default::default()));
-                    temp.clone()
-                        .borrow_mut()
-                        .nodes
-                        .insert(item.to_owned(), new_node.clone());
-                    temp = new_node;
+                Segment::Param(name, regex) => {
+                    names.push(name.to_owned());
+                    let regex_str = regex.as_ref().map(Regex::as_str);
+                    let idx = match node
+                        .params
+                        .iter()
+                        .position(|e| e.0.as_ref().map(Regex::as_str) == regex_str)
+                    {
+                        Some(idx) => idx,
+                        None => {
+                            let idx = match regex {
+                                Some(_) => node.params.iter().take_while(|e| e.0.is_some()).count(),
+                                None => node.params.len(),
+                            };
+                            node.params.insert(idx, (regex, Default::default()));
+                            idx
+                        }
+                    };
+                    node = &mut node.params[idx].1;
+                }
+                Segment::CatchAll(name) => {
+                    if idx + 1 != segments.len() {
+                        return Err(format!("catch-all segment must be the last : {}", path));
+                    }
+                    names.push(name.to_owned());
+                    return Self::insert_route(&mut node.catch_all, method, Route { path, names });
                 }
             }
         }
-        let _ = temp.as_ref().borrow_mut().value.insert(path);
+        Self::insert_route(&mut node.routes, method, Route { path, names })
+    }
+
+    fn insert_route(
+        routes: &mut HashMap<String, Route>,
+        method: &str,
+        route: Route,
+    ) -> Result<(), String> {
+        if let Some(old) = routes.get(method) {
+            return Err(format!(
+                "route conflict : {} {} and {} {}",
+                method, old.path, method, route.path
+            ));
+        }
+        routes.insert(method.to_owned(), route);
+        Ok(())
     }
 
-    pub fn search(&self, path: &str) -> Option<QueryResult> {
-        Self::search_by_nodes(path, self.root.clone())
+    pub fn search(&self, method: &str, path: &str) -> Option<QueryResult> {
+        let segments: Vec<&str> = path.split('/').collect();
+        let mut values = vec![];
+        let route = Self::search_by_nodes(&self.root, method, &segments, &mut values)?;
+        let query_fields: Vec<(String, String)> = route.names.iter().cloned().zip(values).collect();
+        Some(QueryResult {
+            path: route.path.clone(),
+            query_fields: if query_fields.is_empty() {
+                None
+            } else {
+                Some(query_fields)
+            },
+        })
     }
 
-    fn search_by_nodes(path: &str, mut temp: Rc<RefCell<TreeNode>>) -> Option<QueryResult> {
-        let mut query_fields: Vec<(String, String)> = vec![];
-        let paths: Vec<&str> = path.split('/').collect();
-        for (idx, item) in paths.iter().enumerate() {
-            let res_node = temp.as_ref().borrow().nodes.get(*item).cloned();
-            match res_node {
-                Some(node) => {
-                    temp = node;
+    //匹配失败时回溯到优先级更低的节点
+    fn search_by_nodes<'a>(
+        node: &'a TreeNode,
+        method: &str,
+        segments: &[&str],
+        values: &mut Vec<String>,
+    ) -> Option<&'a Route> {
+        let Some((segment, rest)) = segments.split_first() else {
+            return node.routes.get(method);
+        };
+        if let Some(route) = node
+            .nodes
+            .get(*segment)
+            .and_then(|node| Self::search_by_nodes(node, method, rest, values))
+        {
+            return Some(route);
+        }
+        if !segment.is_empty() {
+            for (regex, node) in &node.params {
+                if regex.as_ref().is_some_and(|regex| !regex.is_match(segment)) {
+                    continue;
                 }
-                None => {
-                    let temp_path = paths[idx + 1..].join("/").to_string();
-                    for entry in temp.as_ref().borrow().nodes.iter() {
-                        if entry.0.starts_with('{') {
-                            if temp_path.is_empty() && entry.1.as_ref().borrow().value.is_some() {
-                                query_fields.push((
-                                    entry.0[1..entry.0.len() - 1].to_string(),
-                                    item.to_string(),
-                                ));
-                                return Some(QueryResult {
-                                    path: entry.1.as_ref().borrow().value.as_ref().unwrap().clone(),
-                                    query_fields: if query_fields.is_empty() {
-                                        None
-                                    } else {
-                                        Some(query_fields)
-                                    },
-                                });
-                            }
-                            if let Some(query_result) =
-                                Self::search_by_nodes(&temp_path, entry.1.clone())
-                            {
-                                query_fields.push((
-                                    entry.0[1..entry.0.len() - 1].to_string(),
-                                    item.to_string(),
-                                ));
-                                if let Some(mut temp_query_fields) = query_result.query_fields {
-                                    query_fields.append(&mut temp_query_fields);
-                                }
-                                return Some(QueryResult {
-                                    path: query_result.path,
-                                    query_fields: if query_fields.is_empty() {
-                                        None
-                                    } else {
-                                        Some(query_fields)
-                                    },
-                                });
-                            }
-                        }
-                    }
-                    return None;
+                values.push(segment.to_string());
+                if let Some(route) = Self::search_by_nodes(node, method, rest, values) {
+                    return Some(route);
                 }
+                values.pop();
             }
         }
-        temp.as_ref()
-            .borrow()
-            .value
-            .as_ref()
-            .map(|path| QueryResult {
-                path: path.clone(),
-                query_fields: if query_fields.is_empty() {
-                    None
-                } else {
-                    Some(query_fields)
-                },
-            })
+        let rest_path = segments.join("/");
+        if rest_path.is_empty() {
+            return None;
+        }
+        let route = node.catch_all.get(method)?;
+        values.push(rest_path);
+        Some(route)
     }
 }
 
 #[test]
 fn test() {
     let mut pre_trie = Trie::default();
-    pre_trie.insert("/tasks/{tasks_id}/point".to_owned());
-    pre_trie.insert("/tasks/{tasks_id}/point/{user_id}".to_owned());
-    pre_trie.insert("/tasks/{tasks_id}/point/{user_id}/{merchant_id}".to_owned());
-    println!("{:?}", pre_trie.search("/tasks/iu321/point"));
-    println!("{:?}", pre_trie.search("/tasks/iu322/point/user9090"));
-    println!(
-        "{:?}",
-        pre_trie.search("/tasks/iu322/point/user9090/dsdsdsd")
+    pre_trie
+        .insert("GET", "/tasks/{tasks_id}/point".to_owned())
+        .unwrap();
+    pre_trie
+        .insert("GET", "/tasks/{tasks_id}/point/{user_id}".to_owned())
+        .unwrap();
+    pre_trie
+        .insert("GET", "/tasks/{id:[0-9]+}/point/{user_id}".to_owned())
+        .unwrap();
+    pre_trie
+        .insert("GET", "/tasks/me/point/{user_id}".to_owned())
+        .unwrap();
+    pre_trie
+        .insert("DELETE", "/tasks/{tasks_id}/point/{user_id}".to_owned())
+        .unwrap();
+    pre_trie.insert("GET", "/files/{*rest}".to_owned()).unwrap();
+    let search = |method, path| {
+        pre_trie
+            .search(method, path)
+            .map(|e| (e.path, e.query_fields.unwrap_or_default()))
+    };
+    let fields = |fields: &[(&str, &str)]| {
+        fields
+            .iter()
+            .map(|e| (e.0.to_owned(), e.1.to_owned()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        search("GET", "/tasks/iu321/point"),
+        Some((
+            "/tasks/{tasks_id}/point".to_owned(),
+            fields(&[("tasks_id", "iu321")])
+        ))
+    );
+    assert_eq!(
+        search("GET", "/tasks/12/point/u1").unwrap().0,
+        "/tasks/{id:[0-9]+}/point/{user_id}"
+    );
+    assert_eq!(
+        search("GET", "/tasks/me/point/u1").unwrap().0,
+        "/tasks/me/point/{user_id}"
+    );
+    //静态段没有对应的请求方法时回溯到变量段
+    assert_eq!(
+        search("DELETE", "/tasks/me/point/u1"),
+        Some((
+            "/tasks/{tasks_id}/point/{user_id}".to_owned(),
+            fields(&[("tasks_id", "me"), ("user_id", "u1")])
+        ))
+    );
+    assert_eq!(
+        search("GET", "/files/a/b.txt").unwrap().1,
+        fields(&[("rest", "a/b.txt")])
     );
+    assert!(search("GET", "/files/").is_none());
+    assert!(search("GET", "/tasks//point").is_none());
+    assert!(search("POST", "/tasks/1/point").is_none());
+    assert!(pre_trie
+        .insert("GET", "/tasks/{other}/point".to_owned())
+        .is_err());
+    assert!(pre_trie.insert("GET", "/files/{*a}/b".to_owned()).is_err());
+    assert!(pre_trie.insert("GET", "/x/{id:[}".to_owned()).is_err());
 }
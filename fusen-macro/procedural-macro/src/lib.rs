@@ -1,7 +1,11 @@
 use fusen_derive_macro::fusen_attr;
 use proc_macro::TokenStream;
 use quote::{quote, ToTokens};
-use syn::{parse::Parser, parse_macro_input, Attribute, DeriveInput, Meta};
+use syn::{
+    parse::Parser, parse_macro_input, punctuated::Punctuated, token::Comma, Attribute, DeriveInput,
+    FnArg, GenericArgument, GenericParam, LitStr, Meta, Pat, PathArguments, ReturnType, Signature,
+    Type,
+};
 
 mod data;
 mod handler_macro;
@@ -94,7 +98,8 @@ pub fn url_config(attr: TokenStream, item: TokenStream) -> TokenStream {
 fn get_asset_by_attrs(attrs: &Vec<Attribute>) -> Result<ResourceAttr, syn::Error> {
     for attr in attrs {
         if let Meta::List(list) = &attr.meta {
-            if let Some(segment) = list.path.segments.first() {
+            //支持fusen_rs::fusen_procedural_macro::asset这样的完整路径
+            if let Some(segment) = list.path.segments.last() {
                 if segment.ident == "asset" {
                     return ResourceAttr::from_attr(list.tokens.clone().into());
                 }
@@ -104,6 +109,262 @@ fn get_asset_by_attrs(attrs: &Vec<Attribute>) -> Result<ResourceAttr, syn::Error
     Ok(ResourceAttr::default())
 }
 
+//返回值声明为Result<T, E>且E不是FusenError时,E作为业务异常返回给客户端
+fn get_business_result(output: &ReturnType) -> Option<(Type, Type)> {
+    let ReturnType::Type(_, ty) = output else {
+        return None;
+    };
+    let Type::Path(path) = ty.as_ref() else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Result" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    let mut types = args.args.iter().filter_map(|e| match e {
+        GenericArgument::Type(ty) => Some(ty.clone()),
+        _ => None,
+    });
+    let (Some(ok_type), Some(err_type), None) = (types.next(), types.next(), types.next()) else {
+        return None;
+    };
+    if let Type::Path(err_path) = &err_type {
+        if err_path
+            .path
+            .segments
+            .last()
+            .is_some_and(|e| e.ident == "FusenError")
+        {
+            return None;
+        }
+    }
+    Some((ok_type, err_type))
+}
+
+//服务端方法中声明的&RequestContext参数由框架注入,不参与序列化
+fn is_request_context(ty: &Type) -> bool {
+    let Type::Reference(reference) = ty else {
+        return false;
+    };
+    let Type::Path(path) = reference.elem.as_ref() else {
+        return false;
+    };
+    path.path
+        .segments
+        .last()
+        .is_some_and(|e| e.ident == "RequestContext")
+}
+
+fn is_option(ty: &Type) -> bool {
+    let Type::Path(path) = ty else {
+        return false;
+    };
+    path.path
+        .segments
+        .last()
+        .is_some_and(|e| e.ident == "Option")
+}
+
+//引用参数按值反序列化,&str对应String,&[T]对应Vec<T>
+fn get_owned_type(ty: &Type) -> Type {
+    let Type::Reference(reference) = ty else {
+        return ty.clone();
+    };
+    match reference.elem.as_ref() {
+        Type::Path(path) if path.path.is_ident("str") => syn::parse_quote!(String),
+        Type::Slice(slice) => {
+            let elem = &slice.elem;
+            syn::parse_quote!(Vec<#elem>)
+        }
+        elem => elem.clone(),
+    }
+}
+
+fn contains_impl_trait(ty: &Type) -> bool {
+    contains_type(ty, &|ty| matches!(ty, Type::ImplTrait(_)))
+}
+
+fn contains_reference(ty: &Type) -> bool {
+    contains_type(ty, &|ty| matches!(ty, Type::Reference(_)))
+}
+
+fn contains_type(ty: &Type, predicate: &dyn Fn(&Type) -> bool) -> bool {
+    if predicate(ty) {
+        return true;
+    }
+    match ty {
+        Type::Reference(reference) => contains_type(&reference.elem, predicate),
+        Type::Slice(slice) => contains_type(&slice.elem, predicate),
+        Type::Array(array) => contains_type(&array.elem, predicate),
+        Type::Paren(paren) => contains_type(&paren.elem, predicate),
+        Type::Tuple(tuple) => tuple.elems.iter().any(|e| contains_type(e, predicate)),
+        Type::Path(path) => path.path.segments.iter().any(|segment| {
+            let PathArguments::AngleBracketed(args) = &segment.arguments else {
+                return false;
+            };
+            args.args.iter().any(|arg| match arg {
+                GenericArgument::Type(ty) => contains_type(ty, predicate),
+                _ => false,
+            })
+        }),
+        _ => false,
+    }
+}
+
+//远程调用的方法签名需要满足: async, &self, 参数为普通标识符且可以序列化, 没有泛型类型参数
+fn check_rpc_method(sig: &Signature) -> Result<(), syn::Error> {
+    let ident = &sig.ident;
+    if sig.asyncness.is_none() {
+        return Err(syn::Error::new_spanned(
+            sig.fn_token,
+            format!("fusen_trait method `{ident}` must be async, or provide a default body to keep it local"),
+        ));
+    }
+    if let Some(param) = sig
+        .generics
+        .params
+        .iter()
+        .find(|e| !matches!(e, GenericParam::Lifetime(_)))
+    {
+        return Err(syn::Error::new_spanned(
+            param,
+            format!(
+                "fusen_trait method `{ident}` can not declare generic type or const parameters"
+            ),
+        ));
+    }
+    match sig.inputs.first() {
+        Some(FnArg::Receiver(receiver))
+            if receiver.reference.is_some() && receiver.mutability.is_none() => {}
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &sig.inputs,
+                format!("fusen_trait method `{ident}` must take &self"),
+            ))
+        }
+    }
+    for input in sig.inputs.iter().skip(1) {
+        let FnArg::Typed(pat_type) = input else {
+            continue;
+        };
+        if !matches!(pat_type.pat.as_ref(), Pat::Ident(_)) {
+            return Err(syn::Error::new_spanned(
+                &pat_type.pat,
+                "fusen_trait arguments must be plain identifiers",
+            ));
+        }
+        let owned = match pat_type.ty.as_ref() {
+            Type::Reference(reference) if reference.mutability.is_some() => {
+                return Err(syn::Error::new_spanned(
+                    &pat_type.ty,
+                    "fusen_trait arguments can not be &mut, use & or an owned type",
+                ));
+            }
+            Type::Reference(reference) => reference.elem.as_ref(),
+            ty => ty,
+        };
+        //服务端只能把外层的引用还原为对应的值,Option<&str>等内部的引用无法反序列化
+        if contains_reference(owned) {
+            return Err(syn::Error::new_spanned(
+                &pat_type.ty,
+                "fusen_trait arguments can only borrow at the top level, use an owned type such as Option<String> or Vec<String>",
+            ));
+        }
+        if contains_impl_trait(&pat_type.ty) {
+            return Err(syn::Error::new_spanned(
+                &pat_type.ty,
+                "fusen_trait arguments can not be impl Trait, use a concrete type",
+            ));
+        }
+    }
+    if let ReturnType::Type(_, ty) = &sig.output {
+        if contains_impl_trait(ty) {
+            return Err(syn::Error::new_spanned(
+                ty,
+                "fusen_trait return type can not be impl Trait, use a concrete type",
+            ));
+        }
+        if contains_reference(ty) {
+            return Err(syn::Error::new_spanned(
+                ty,
+                "fusen_trait return type can not contain a reference, use an owned type",
+            ));
+        }
+    }
+    Ok(())
+}
+
+const FIELD_BINDINGS: [(&str, &str); 4] = [
+    ("path", "Path"),
+    ("query", "Query"),
+    ("header", "Header"),
+    ("body", "Body"),
+];
+
+fn is_field_binding(attr: &Attribute) -> bool {
+    FIELD_BINDINGS
+        .iter()
+        .any(|(name, _)| attr.path().is_ident(name))
+}
+
+//参数上声明的#[path] #[query] #[header] #[body],可以指定名称如#[header("x-tenant-id")]
+//返回FieldBinding的变体及参数名,只要有一个参数声明了绑定就按绑定传递,未声明的参数作为query
+fn get_fields_binding(
+    inputs: &Punctuated<FnArg, Comma>,
+) -> Result<Option<Vec<(syn::Ident, String)>>, syn::Error> {
+    let mut bindings = vec![];
+    let mut declared = false;
+    let mut has_body = false;
+    for input in inputs {
+        let FnArg::Typed(pat_type) = input else {
+            continue;
+        };
+        if is_request_context(&pat_type.ty) {
+            continue;
+        }
+        let field_name = pat_type.pat.to_token_stream().to_string();
+        let mut binding = ("Query", field_name.clone());
+        for attr in pat_type.attrs.iter().filter(|e| is_field_binding(e)) {
+            let (name, variant) = FIELD_BINDINGS
+                .iter()
+                .find(|(name, _)| attr.path().is_ident(name))
+                .unwrap();
+            if *variant == "Body" {
+                if has_body {
+                    return Err(syn::Error::new_spanned(attr, "only one #[body] is allowed"));
+                }
+                has_body = true;
+            }
+            let field_name = match &attr.meta {
+                Meta::List(list) => list.parse_args::<LitStr>()?.value(),
+                _ if *name == "header" => field_name.replace('_', "-"),
+                _ => field_name.clone(),
+            };
+            //请求头名称不区分大小写,服务端收到的均为小写
+            binding = match *name {
+                "header" => (variant, field_name.to_lowercase()),
+                _ => (variant, field_name),
+            };
+            declared = true;
+        }
+        let variant = syn::Ident::new(binding.0, proc_macro2::Span::call_site());
+        bindings.push((variant, binding.1));
+    }
+    Ok(declared.then_some(bindings))
+}
+
+//绑定声明只用于生成代码,需要从方法签名中移除
+fn remove_fields_binding(inputs: &mut Punctuated<FnArg, Comma>) {
+    for input in inputs.iter_mut() {
+        if let FnArg::Typed(pat_type) = input {
+            pat_type.attrs.retain(|e| !is_field_binding(e));
+        }
+    }
+}
+
 fusen_attr! {
     ResourceAttr,
     path,
@@ -119,7 +380,9 @@ fusen_attr! {
     FusenAttr,
     id,
     version,
-    group
+    group,
+    openapi,
+    descriptor
 }
 
 fusen_attr! {
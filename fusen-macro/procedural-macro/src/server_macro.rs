@@ -1,8 +1,14 @@
 use proc_macro::TokenStream;
-use quote::{quote, ToTokens};
-use syn::{parse_macro_input, FnArg, ImplItem, ItemImpl};
+use quote::{quote, quote_spanned, ToTokens};
+use syn::{
+    ext::IdentExt, parse_macro_input, punctuated::Punctuated, token::Comma, FnArg, ImplItem,
+    ItemImpl, ReturnType, Type,
+};
 
-use crate::{get_asset_by_attrs, FusenAttr};
+use crate::{
+    get_asset_by_attrs, get_business_result, get_fields_binding, get_owned_type,
+    is_request_context, remove_fields_binding, FusenAttr,
+};
 
 pub fn fusen_server(attr: FusenAttr, item: TokenStream) -> TokenStream {
     let version = match attr.version {
@@ -14,64 +20,81 @@ pub fn fusen_server(attr: FusenAttr, item: TokenStream) -> TokenStream {
         None => quote!(None),
     };
     let org_item = parse_macro_input!(item as ItemImpl);
-    let methods_info = match get_resource_by_server(org_item.clone()) {
-        Ok(methods_info) => methods_info.into_iter().fold(vec![], |mut vec, e| {
-            vec.push(serde_json::to_string(&e).unwrap());
-            vec
-        }),
+    let Some((_, trait_path, _)) = org_item.trait_.clone() else {
+        return syn::Error::new_spanned(
+            org_item.self_ty,
+            "fusen_server must label to impl of fusen_trait",
+        )
+        .into_compile_error()
+        .into();
+    };
+    let item_self = org_item.self_ty.clone();
+    //路由信息取自接口定义,服务端声明的路由和参数绑定需要与接口一致
+    let resource = quote!(<#item_self as #trait_path>::FUSEN_RESOURCE);
+    let checks = match get_resource_checks(&org_item, &resource) {
+        Ok(checks) => checks,
         Err(err) => return err.into_compile_error().into(),
     };
     let id = match attr.id {
         Some(id) => {
             quote!(#id)
         }
-        None => {
-            let id = org_item.trait_.as_ref().unwrap().1.segments[0]
-                .ident
-                .to_string();
-            quote!(#id)
-        }
+        None => quote!(#resource.id),
     };
     let item = org_item.clone();
     let org_item = get_server_item(org_item);
-    let item_self = item.self_ty;
     let items_fn = item.items.iter().fold(vec![], |mut vec, e| {
         if let ImplItem::Fn(fn_item) = e {
-            let method = &fn_item.sig.ident;
+            //非async或返回值不是Result的方法为接口中的本地方法,由编译期检查保证与接口一致
+            if fn_item.sig.asyncness.is_none() || !is_result(&fn_item.sig.output) {
+                return vec;
+            }
+            let ident = &fn_item.sig.ident;
+            let method = ident.unraw().to_string();
+            let map_err = match get_business_result(&fn_item.sig.output) {
+                Some(_) => quote!(fusen_rs::fusen_common::error::FusenError::business(&info)),
+                None => quote!(info),
+            };
             let mut req_pat = vec![];
-            let mut req_type = vec![];
             let request = fn_item.sig.inputs.iter().fold(vec![], |mut vec, e| {
                 if let FnArg::Typed(input) = e {
+                    if is_request_context(&input.ty) {
+                        return vec;
+                    }
                     let request = &input.pat;
-                    let request_type = &input.ty;
+                    let request_type = get_owned_type(&input.ty);
                     let token = quote! {
-                            let result : Result<#request_type,_>  = serde_json::from_slice(req_poi_param[idx].as_bytes());
-                            if let Err(err) = result {
-                                param.get_mut_response().set_response(Err(fusen_rs::fusen_common::error::FusenError::from(err.to_string())));
-                                return param;
-                            }
-                            let #request : #request_type = result.unwrap();
+                            let #request : #request_type = match req_poi_param[idx].deserialize() {
+                                Ok(res) => res,
+                                Err(err) => {
+                                    param.get_mut_response().set_response(Err(err));
+                                    return param;
+                                }
+                            };
                             idx += 1;
                     };
-                    req_pat.push(request);
-                    req_type.push(request_type);
+                    //引用参数反序列化为对应的值后传入引用
+                    req_pat.push(match input.ty.as_ref() {
+                        Type::Reference(_) => quote!(&#request),
+                        _ => quote!(#request),
+                    });
                     vec.push(token);
                 }
                 vec
             },
             );
             vec.push(quote! {
-                if &param.get_context_info().get_method_name()[..] == stringify!(#method) {
-                    let fields_name = vec![#(
-                        stringify!(#req_pat),
-                    )*];
-                    let fields_ty = vec![#(
-                        stringify!(#req_type),
-                    )*];
-                let req_poi_param = match param.get_mut_request().get_fields(fields_name,fields_ty) {
+                if &param.get_context_info().get_method_name()[..] == #method {
+                //不是接口中的方法时编译期检查会报错
+                const METHOD: Option<&fusen_rs::fusen_common::server::TraitMethod> = #resource.find_method(#method);
+                let fields = match METHOD.and_then(|e| e.bindings) {
+                    Some(bindings) => param.get_binding_fields(bindings.to_vec()),
+                    None => param.get_request().get_fields(METHOD.map_or(&[][..], |e| e.fields).to_vec()),
+                };
+                let req_poi_param = match fields {
                      Ok(res) => res,
                      Err(err) => {
-                        param.get_mut_response().set_response(Err(fusen_rs::fusen_common::error::FusenError::from(err.to_string())));
+                        param.get_mut_response().set_response(Err(fusen_rs::fusen_common::error::FusenError::from(err)));
                         return param;
                      }
                 };
@@ -79,20 +102,21 @@ pub fn fusen_server(attr: FusenAttr, item: TokenStream) -> TokenStream {
                 #(
                     #request
                 )*
-                let res = self.#method(
+                let res = self.#ident(
                     #(
                         #req_pat,
                     )*
                 ).await;
+                let serialization = param.get_meta_data().get_accept_serialization();
                 param.get_mut_response().set_response( match res {
                     Ok(res) => {
-                        let res = fusen_rs::fusen_common::codec::object_to_bytes(&res);
+                        let res = fusen_rs::fusen_common::codec::SerializationFormat::serialize(&serialization, &res);
                         match res {
                             Ok(res) => Ok(res),
                             Err(err) => Err(fusen_rs::fusen_common::error::FusenError::from(err.to_string()))
                         }
                     },
-                    Err(info) => Err(info)
+                    Err(info) => Err(#map_err)
                 });
                 return param;
             }
@@ -105,18 +129,15 @@ pub fn fusen_server(attr: FusenAttr, item: TokenStream) -> TokenStream {
 
         #org_item
 
+        #(#checks)*
+
         impl fusen_rs::fusen_common::server::RpcServer for #item_self {
             fn invoke (&'static self, param : fusen_rs::fusen_common::FusenContext) -> fusen_rs::fusen_common::FusenFuture<fusen_rs::fusen_common::FusenContext> {
                 let rpc = self;
                 Box::pin(async move {rpc.prv_invoke(param).await})
             }
             fn get_info(&self) -> fusen_rs::fusen_common::server::ServerInfo {
-
-               let mut methods : Vec<fusen_rs::fusen_common::MethodResource> = vec![];
-               #(
-                   methods.push(fusen_rs::fusen_common::MethodResource::new_macro(#methods_info));
-               )*
-               fusen_rs::fusen_common::server::ServerInfo::new(#id,#version,#group,methods)
+               fusen_rs::fusen_common::server::ServerInfo::new(#id,#version,#group,#resource.get_methods())
             }
         }
 
@@ -141,6 +162,26 @@ fn get_server_item(item: ItemImpl) -> proc_macro2::TokenStream {
     let ident = item.self_ty.to_token_stream();
     let fn_items = item.items.iter().fold(vec![], |mut vec, e| {
         if let ImplItem::Fn(fn_item) = e {
+            let mut fn_item = fn_item.clone();
+            //移除&RequestContext参数以保持与trait签名一致,在方法体中从当前请求上下文获取
+            let mut inputs = Punctuated::<FnArg, Comma>::new();
+            let mut stmts = vec![];
+            for input in fn_item.sig.inputs {
+                match &input {
+                    FnArg::Typed(pat_type) if is_request_context(&pat_type.ty) => {
+                        let pat = &pat_type.pat;
+                        let ty = &pat_type.ty;
+                        stmts.push(syn::parse_quote! {
+                            let #pat: #ty = &fusen_rs::context::RequestContext::current_or_default();
+                        });
+                    }
+                    _ => inputs.push(input),
+                }
+            }
+            remove_fields_binding(&mut inputs);
+            fn_item.sig.inputs = inputs;
+            stmts.append(&mut fn_item.block.stmts);
+            fn_item.block.stmts = stmts;
             vec.push(fn_item);
         }
         vec
@@ -155,33 +196,122 @@ fn get_server_item(item: ItemImpl) -> proc_macro2::TokenStream {
     }
 }
 
-fn get_resource_by_server(item: ItemImpl) -> Result<Vec<(String, String, String)>, syn::Error> {
-    let mut res = vec![];
-    let attrs = &item.attrs;
-    let resource = get_asset_by_attrs(attrs)?;
-    let parent_path = match resource.path {
-        Some(id) => id,
-        None => "/".to_owned() + &item.trait_.unwrap().1.segments[0].ident.to_string(),
+fn is_result(output: &ReturnType) -> bool {
+    let ReturnType::Type(_, ty) = output else {
+        return false;
     };
-    let parent_method = match resource.method {
-        Some(method) => method,
-        None => "POST".to_string(),
+    let Type::Path(path) = ty.as_ref() else {
+        return false;
     };
-    for fn_item in item.items.iter() {
-        if let ImplItem::Fn(item_fn) = fn_item {
-            let resource = get_asset_by_attrs(&item_fn.attrs)?;
-            let path = match resource.path {
-                Some(path) => path,
-                None => "/".to_owned() + &item_fn.sig.ident.to_string(),
+    path.path
+        .segments
+        .last()
+        .is_some_and(|e| e.ident.to_string().ends_with("Result"))
+}
+
+//服务端仍然声明了#[asset]或参数绑定时,在编译期检查是否与接口一致
+fn get_resource_checks(
+    item: &ItemImpl,
+    resource: &proc_macro2::TokenStream,
+) -> Result<Vec<proc_macro2::TokenStream>, syn::Error> {
+    let mut checks = vec![];
+    //实现上的#[asset]只与接口上声明的部分比较,未声明的部分以FUSEN_RESOURCE为准
+    let asset = get_asset_by_attrs(&item.attrs)?;
+    if asset.path.is_some() || asset.method.is_some() {
+        let path = option_quote(asset.path.as_deref());
+        let method = option_quote(asset.method.as_deref());
+        let message = format!(
+            "fusen_server : route {} {} of the impl does not match the fusen_trait",
+            asset.method.as_deref().unwrap_or("_"),
+            asset
+                .path
+                .as_deref()
+                .unwrap_or("_")
+                .replace('{', "{{")
+                .replace('}', "}}"),
+        );
+        checks.push(quote! {
+            const _: () = {
+                if !#resource.is_route_match(#path, #method) {
+                    panic!(#message);
+                }
             };
-            let method = match resource.method {
-                Some(method) => method,
-                None => parent_method.clone(),
+        });
+    }
+    for fn_item in item.items.iter() {
+        let ImplItem::Fn(item_fn) = fn_item else {
+            continue;
+        };
+        let ident = &item_fn.sig.ident;
+        let name = ident.unraw().to_string();
+        if item_fn.sig.asyncness.is_none() {
+            continue;
+        }
+        let message = format!(
+            "fusen_server : `{}` is not a rpc method of the fusen_trait, local methods with a default body can only be overridden by non-async methods",
+            name
+        );
+        checks.push(quote_spanned! {ident.span()=>
+            const _: () = {
+                if #resource.find_method(#name).is_none() {
+                    panic!(#message);
+                }
             };
-            let mut parent_path = parent_path.clone();
-            parent_path.push_str(&path);
-            res.push((item_fn.sig.ident.to_string(), parent_path, method));
+        });
+        let asset = get_asset_by_attrs(&item_fn.attrs)?;
+        if asset.path.is_some() || asset.method.is_some() {
+            let path = option_quote(asset.path.as_deref());
+            let method = option_quote(asset.method.as_deref());
+            //panic!中的{}需要转义
+            let message = format!(
+                "fusen_server : route {} {} of `{}` does not match the fusen_trait",
+                asset.method.as_deref().unwrap_or("_"),
+                asset
+                    .path
+                    .as_deref()
+                    .unwrap_or("_")
+                    .replace('{', "{{")
+                    .replace('}', "}}"),
+                name
+            );
+            checks.push(quote_spanned! {ident.span()=>
+                const _: () = {
+                    //不是接口中的方法时由上面的检查报错
+                    let matched = match #resource.find_method(#name) {
+                        Some(trait_method) => trait_method.is_route_match(#resource.path, #path, #method),
+                        None => true,
+                    };
+                    if !matched {
+                        panic!(#message);
+                    }
+                };
+            });
+        }
+        if let Some(bindings) = get_fields_binding(&item_fn.sig.inputs)? {
+            let (binding, field): (Vec<_>, Vec<_>) = bindings.into_iter().unzip();
+            let message = format!(
+                "fusen_server : parameter bindings of `{}` do not match the fusen_trait",
+                name
+            );
+            checks.push(quote_spanned! {ident.span()=>
+                const _: () = {
+                    let matched = match #resource.find_method(#name) {
+                        Some(trait_method) => trait_method.is_binding_match(&[#((fusen_rs::fusen_common::FieldBinding::#binding, #field),)*]),
+                        None => true,
+                    };
+                    if !matched {
+                        panic!(#message);
+                    }
+                };
+            });
         }
     }
-    Ok(res)
+    Ok(checks)
+}
+
+fn option_quote(value: Option<&str>) -> proc_macro2::TokenStream {
+    match value {
+        Some(value) => quote!(Some(#value)),
+        None => quote!(None),
+    }
 }
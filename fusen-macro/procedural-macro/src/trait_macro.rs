@@ -1,8 +1,14 @@
-use crate::{get_asset_by_attrs, FusenAttr};
+use crate::{
+    check_rpc_method, get_asset_by_attrs, get_business_result, get_fields_binding, get_owned_type,
+    is_option, remove_fields_binding, FusenAttr,
+};
 use proc_macro::TokenStream;
 use quote::{quote, ToTokens};
 use std::collections::HashMap;
-use syn::{parse_macro_input, FnArg, ItemTrait, ReturnType, TraitItem};
+use syn::{
+    ext::IdentExt, parse_macro_input, punctuated::Punctuated, token::Comma, FnArg, ItemTrait,
+    ReturnType, TraitItem, TraitItemFn, Type,
+};
 
 pub fn fusen_trait(attr: FusenAttr, item: TokenStream) -> TokenStream {
     let group = match attr.group {
@@ -14,6 +20,19 @@ pub fn fusen_trait(attr: FusenAttr, item: TokenStream) -> TokenStream {
         None => quote!(None),
     };
     let input = parse_macro_input!(item as ItemTrait);
+    if !input.generics.params.is_empty() {
+        return syn::Error::new_spanned(
+            &input.generics,
+            "fusen_trait can not declare generic parameters",
+        )
+        .into_compile_error()
+        .into();
+    }
+    for item_fn in get_rpc_methods(&input) {
+        if let Err(err) = check_rpc_method(&item_fn.sig) {
+            return err.into_compile_error().into();
+        }
+    }
     let mut methods_cache = HashMap::new();
     let methods_info = match get_resource_by_trait(input.clone()) {
         Ok(methods_info) => methods_info.into_iter().fold(vec![], |mut vec, e| {
@@ -23,6 +42,10 @@ pub fn fusen_trait(attr: FusenAttr, item: TokenStream) -> TokenStream {
         }),
         Err(err) => return err.into_compile_error().into(),
     };
+    let (parent_path, parent_method) = match get_parent_resource(&input) {
+        Ok(parent) => parent,
+        Err(err) => return err.into_compile_error().into(),
+    };
     let id = match attr.id {
         Some(trait_id) => {
             quote!(#trait_id)
@@ -32,21 +55,36 @@ pub fn fusen_trait(attr: FusenAttr, item: TokenStream) -> TokenStream {
             quote!(#id)
         }
     };
-    let item_trait = get_item_trait(input.clone());
     let trait_ident = &input.ident;
     let vis = &input.vis;
-    let items = &input.items;
-    let mut sig_item = vec![];
-    for item in items {
-        if let TraitItem::Fn(item) = item {
-            sig_item.push(item.sig.clone());
-        }
+    let sig_item: Vec<_> = get_rpc_methods(&input)
+        .map(|item_fn| item_fn.sig.clone())
+        .collect();
+    let openapi = attr.openapi.is_some_and(|e| e == "true");
+    if attr.descriptor.is_some() && !openapi {
+        return syn::Error::new_spanned(
+            &input.ident,
+            "fusen_trait descriptor requires openapi = true",
+        )
+        .into_compile_error()
+        .into();
     }
     let mut fn_quote = vec![];
+    let mut openapi_quote = vec![];
+    let mut resource_quote = vec![];
     for item in sig_item {
         let asyncable = item.asyncness;
         let ident = item.ident;
-        let inputs = item.inputs;
+        //r#type等原始标识符对应的方法名为type
+        let method_name = ident.unraw().to_string();
+        let generics = item.generics;
+        let where_clause = &generics.where_clause;
+        let bindings = match get_fields_binding(&item.inputs) {
+            Ok(bindings) => bindings,
+            Err(err) => return err.into_compile_error().into(),
+        };
+        let mut inputs = item.inputs;
+        remove_fields_binding(&mut inputs);
         let mut fields_ty = vec![];
         let req = inputs.iter().fold(vec![], |mut vec, e| {
             if let FnArg::Typed(req) = e {
@@ -56,56 +94,191 @@ pub fn fusen_trait(attr: FusenAttr, item: TokenStream) -> TokenStream {
             vec
         });
         let output = item.output;
-        let output_type = match &output {
-            ReturnType::Default => {
-                quote! {()}
+        let (output_type, fn_output, map_err) = match get_business_result(&output) {
+            Some((ok_type, err_type)) => (
+                ok_type.to_token_stream(),
+                quote!(Result<#ok_type,fusen_rs::fusen_common::error::CallError<#err_type>>),
+                quote!(.map_err(fusen_rs::fusen_common::error::CallError::from)),
+            ),
+            None => {
+                let output_type = match &output {
+                    ReturnType::Default => {
+                        quote! {()}
+                    }
+                    ReturnType::Type(_, res_type) => res_type.to_token_stream(),
+                };
+                (
+                    output_type.clone(),
+                    quote!(Result<#output_type,fusen_rs::fusen_common::error::FusenError>),
+                    quote!(),
+                )
+            }
+        };
+        //返回值为Option或()时,响应为null对应None或()
+        let null_response = match get_business_result(&output) {
+            Some((ok_type, _)) => get_null_value(&ok_type),
+            None => match &output {
+                ReturnType::Default => Some(quote!(())),
+                ReturnType::Type(_, res_type) => get_null_value(res_type),
+            },
+        }
+        .map(|value| {
+            quote! {
+                let res = match res {
+                    Err(fusen_rs::fusen_common::error::FusenError::Null) => Ok(#value),
+                    res => res,
+                };
+            }
+        });
+        let (methos_path, methos_type) = methods_cache.get(&method_name).unwrap();
+        let resource_bindings = match &bindings {
+            Some(bindings) => {
+                let (binding, name): (Vec<_>, Vec<_>) = bindings.iter().cloned().unzip();
+                quote!(Some(&[#((fusen_rs::fusen_common::FieldBinding::#binding, #name),)*]))
+            }
+            None => quote!(None),
+        };
+        resource_quote.push(quote! {
+            fusen_rs::fusen_common::server::TraitMethod {
+                name: #method_name,
+                path: #methos_path,
+                method: #methos_type,
+                fields: &[#(#fields_ty,)*],
+                bindings: #resource_bindings,
+            }
+        });
+        if openapi {
+            let params = get_params_doc(&inputs, bindings.as_ref());
+            openapi_quote.push(quote! {
+                fusen_rs::fusen_common::openapi::MethodDoc::new(
+                    #method_name,
+                    #methos_path,
+                    #methos_type,
+                    vec![#(#params,)*],
+                    generator.subschema_for::<#output_type>(),
+                )
+            });
+        }
+        let json = quote!(fusen_rs::fusen_common::codec::SerializationType::JSON);
+        let (serialization, fields_serialization, request) = match bindings {
+            None => (
+                if methos_type.to_lowercase() == "post" {
+                    quote!(self.client.get_serialization(#id))
+                } else {
+                    json.clone()
+                },
+                vec![quote!(serialization); req.len()],
+                quote!(fusen_rs::fusen_common::FusenRequest::new_for_client(#methos_type,vec![#(#fields_ty.to_string(),)*],req_vec,serialization)),
+            ),
+            //只有body中的参数使用客户端配置的序列化方式
+            Some(bindings) => {
+                let fields_serialization = bindings
+                    .iter()
+                    .map(|(binding, _)| match binding == "Body" {
+                        true => quote!(serialization),
+                        false => json.clone(),
+                    })
+                    .collect();
+                let (binding, name): (Vec<_>, Vec<_>) = bindings.into_iter().unzip();
+                (
+                    quote!(self.client.get_serialization(#id)),
+                    fields_serialization,
+                    quote!(
+                        fusen_rs::fusen_common::FusenRequest::new_for_client_binding(
+                            vec![#((fusen_rs::fusen_common::FieldBinding::#binding, #name.to_owned()),)*],
+                            req_vec,
+                            serialization
+                        )
+                    ),
+                )
             }
-            ReturnType::Type(_, res_type) => res_type.to_token_stream(),
         };
-        let (methos_path, methos_type) = methods_cache.get(&ident.to_string()).unwrap();
         fn_quote.push(
             quote! {
                     #[allow(non_snake_case)]
-                    pub #asyncable fn #ident (#inputs) -> Result<#output_type,fusen_rs::fusen_common::error::FusenError> {
-                    let mut req_vec : Vec<String> = vec![];
-                    let fields_ty = vec![
-                    #(
-                        #fields_ty.to_string(),
-                    )*];
+                    pub #asyncable fn #ident #generics (#inputs) -> #fn_output #where_clause {
+                    let mut req_vec = vec![];
+                    let serialization = #serialization;
                     #(
-                        let mut res_poi_str = serde_json::to_string(&#req);
+                        let mut res_poi_str = fusen_rs::fusen_common::codec::SerializationFormat::serialize(&#fields_serialization, &#req);
                         if let Err(err) = res_poi_str {
-                            return Err(fusen_rs::fusen_common::error::FusenError::from(err.to_string()));
+                            return Err(fusen_rs::fusen_common::error::FusenError::from(err.to_string()).into());
                         }
                         req_vec.push(res_poi_str.unwrap());
                     )*
                     let version : Option<&str> = #version;
                     let group : Option<&str> = #group;
                     let mut mate_data = fusen_rs::fusen_common::MetaData::new();
-                    let mut request = fusen_rs::fusen_common::FusenRequest::new_for_client(#methos_type,fields_ty,req_vec);
+                    let mut request = #request;
                     let mut context = fusen_rs::fusen_common::FusenContext::new(
                         fusen_rs::fusen_common::logs::get_uuid(),
                         fusen_rs::fusen_common::ContextInfo::default()
                             .path(fusen_rs::fusen_common::Path::new(#methos_type,#methos_path.to_string()))
                             .version(version.map(|e|e.to_string()))
                             .class_name(#id.to_owned())
-                            .method_name(stringify!(#ident).to_string())
+                            .method_name(#method_name.to_string())
                             .group(group.map(|e|e.to_string())),
                         request,
                         mate_data,
                     );
                     context.get_mut_response().insert_return_ty(stringify!(#output_type));
                     let res : Result<#output_type,fusen_rs::fusen_common::error::FusenError> = self.client.invoke::<#output_type>(context).await;
-                    return res;
+                    #null_response
+                    return res #map_err;
                 }
             }
         );
     }
+    let item_trait = get_item_trait(
+        input.clone(),
+        quote! {
+            fusen_rs::fusen_common::server::TraitResource {
+                id: #id,
+                path: #parent_path,
+                method: #parent_method,
+                methods: &[#(#resource_quote,)*],
+            }
+        },
+    );
     let rpc_client = syn::Ident::new(&format!("{}Client", trait_ident), trait_ident.span());
+    let openapi = openapi.then(|| {
+        quote! {
+            pub fn get_openapi(
+                generator: &mut fusen_rs::fusen_common::openapi::SchemaGenerator,
+            ) -> fusen_rs::fusen_common::openapi::ServiceDoc {
+                let version : Option<&str> = #version;
+                let group : Option<&str> = #group;
+                fusen_rs::fusen_common::openapi::ServiceDoc::new(#id, version, group, vec![#(#openapi_quote,)*])
+            }
+        }
+    });
+
+    //参数的JSON Schema在编译后才能生成,在cargo test时与已提交的描述文件比较
+    let descriptor = attr.descriptor.map(|path| {
+        let test_ident = syn::Ident::new(
+            &format!("check_{}_descriptor", trait_ident),
+            trait_ident.span(),
+        );
+        quote! {
+            #[cfg(test)]
+            #[test]
+            #[allow(non_snake_case)]
+            fn #test_ident() {
+                if let Err(err) = fusen_rs::fusen_common::openapi::check_descriptor(
+                    std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(#path),
+                    &[#rpc_client::get_openapi],
+                ) {
+                    panic!("{}", err);
+                }
+            }
+        }
+    });
 
     let expanded = quote! {
         #item_trait
 
+        #descriptor
+
         #[derive(Clone)]
         #vis struct #rpc_client {
             client : std::sync::Arc<fusen_rs::client::FusenClient>
@@ -126,48 +299,124 @@ pub fn fusen_trait(attr: FusenAttr, item: TokenStream) -> TokenStream {
             fusen_rs::fusen_common::server::ServerInfo::new(#id,#version,#group,methods)
         }
 
+        #openapi
+
        }
 
     };
     TokenStream::from(expanded)
 }
 
-fn get_item_trait(item: ItemTrait) -> proc_macro2::TokenStream {
+//参数名与绑定方式同客户端请求一致,未声明绑定时由请求方法决定参数位置
+fn get_params_doc(
+    inputs: &Punctuated<FnArg, Comma>,
+    bindings: Option<&Vec<(syn::Ident, String)>>,
+) -> Vec<proc_macro2::TokenStream> {
+    let mut params = vec![];
+    for (idx, ty) in inputs
+        .iter()
+        .filter_map(|e| match e {
+            FnArg::Typed(input) => Some(input),
+            FnArg::Receiver(_) => None,
+        })
+        .enumerate()
+    {
+        let (name, binding) = match bindings.and_then(|e| e.get(idx)) {
+            Some((binding, name)) => (
+                name.clone(),
+                quote!(Some(fusen_rs::fusen_common::FieldBinding::#binding)),
+            ),
+            None => (ty.pat.to_token_stream().to_string(), quote!(None)),
+        };
+        let required = !is_option(&ty.ty);
+        let ty = get_owned_type(&ty.ty);
+        params.push(quote! {
+            fusen_rs::fusen_common::openapi::ParamDoc::new(
+                #name,
+                #binding,
+                #required,
+                generator.subschema_for::<#ty>(),
+            )
+        });
+    }
+    params
+}
+
+//有默认实现的方法只在本地使用,不生成客户端方法与路由
+fn get_rpc_methods(item: &ItemTrait) -> impl Iterator<Item = &TraitItemFn> {
+    item.items.iter().filter_map(|e| match e {
+        TraitItem::Fn(item_fn) if item_fn.default.is_none() => Some(item_fn),
+        _ => None,
+    })
+}
+
+fn get_null_value(ty: &Type) -> Option<proc_macro2::TokenStream> {
+    match ty {
+        Type::Tuple(tuple) if tuple.elems.is_empty() => Some(quote!(())),
+        ty if is_option(ty) => Some(quote!(None)),
+        _ => None,
+    }
+}
+
+//FUSEN_RESOURCE为接口声明的路由信息,供#[fusen_server]使用
+fn get_item_trait(item: ItemTrait, resource: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
     let trait_ident = &item.ident;
-    let item_fn = item.items.iter().fold(vec![], |mut vec, e| {
-        if let TraitItem::Fn(item_fn) = e {
-            let asyncable = &item_fn.sig.asyncness;
-            let ident = &item_fn.sig.ident;
-            let inputs = &item_fn.sig.inputs;
-            let attrs = &item_fn.attrs;
-            let output_type = match &item_fn.sig.output {
-                ReturnType::Default => {
-                    quote! {()}
-                }
-                ReturnType::Type(_, res_type) => res_type.to_token_stream(),
-            };
-            vec.push(quote! {
-                   #(#attrs)*
-                   #asyncable fn #ident (#inputs) -> fusen_rs::fusen_common::FusenResult<#output_type>;
-            });
+    let colon_token = &item.colon_token;
+    let supertraits = &item.supertraits;
+    let trait_items = item.items.iter().fold(vec![], |mut vec, e| {
+        match e {
+            TraitItem::Fn(item_fn) if item_fn.default.is_none() => {
+                let asyncable = &item_fn.sig.asyncness;
+                let ident = &item_fn.sig.ident;
+                let generics = &item_fn.sig.generics;
+                let where_clause = &generics.where_clause;
+                let mut inputs = item_fn.sig.inputs.clone();
+                remove_fields_binding(&mut inputs);
+                let attrs = &item_fn.attrs;
+                let output_type = match &item_fn.sig.output {
+                    ReturnType::Default => {
+                        quote! {fusen_rs::fusen_common::FusenResult<()>}
+                    }
+                    //声明了业务异常时保持原样
+                    ReturnType::Type(_, res_type)
+                        if get_business_result(&item_fn.sig.output).is_some() =>
+                    {
+                        res_type.to_token_stream()
+                    }
+                    ReturnType::Type(_, res_type) => {
+                        quote! {fusen_rs::fusen_common::FusenResult<#res_type>}
+                    }
+                };
+                vec.push(quote! {
+                    #(#attrs)*
+                    #[allow(async_fn_in_trait)]
+                    #[allow(non_snake_case)]
+                    #asyncable fn #ident #generics (#inputs) -> #output_type #where_clause;
+                });
+            }
+            //默认方法及其他关联项保持原样
+            TraitItem::Fn(item_fn) => vec.push(quote! {
+                #[allow(async_fn_in_trait)]
+                #item_fn
+            }),
+            item => vec.push(item.to_token_stream()),
         }
         vec
     });
     quote! {
-        pub trait #trait_ident {
+        pub trait #trait_ident #colon_token #supertraits {
+           #[doc(hidden)]
+           const FUSEN_RESOURCE: fusen_rs::fusen_common::server::TraitResource = #resource;
            #(
-               #[allow(async_fn_in_trait)]
-               #[allow(non_snake_case)]
-               #item_fn
+               #trait_items
             )*
         }
     }
 }
 
-fn get_resource_by_trait(item: ItemTrait) -> Result<Vec<(String, String, String)>, syn::Error> {
-    let mut res = vec![];
-    let attrs = &item.attrs;
-    let resource = get_asset_by_attrs(attrs)?;
+//接口上声明的父路径与请求方法,缺省为/{TraitName}与POST
+fn get_parent_resource(item: &ItemTrait) -> Result<(String, String), syn::Error> {
+    let resource = get_asset_by_attrs(&item.attrs)?;
     let parent_path = match resource.path {
         Some(path) => path,
         None => "/".to_owned() + &item.ident.to_string(),
@@ -176,21 +425,25 @@ fn get_resource_by_trait(item: ItemTrait) -> Result<Vec<(String, String, String)
         Some(method) => method,
         None => "POST".to_string(),
     };
-    for fn_item in item.items.iter() {
-        if let TraitItem::Fn(item_fn) = fn_item {
-            let resource = get_asset_by_attrs(&item_fn.attrs)?;
-            let path = match resource.path {
-                Some(path) => path,
-                None => "/".to_owned() + &item_fn.sig.ident.to_string(),
-            };
-            let method = match resource.method {
-                Some(method) => method,
-                None => parent_method.clone(),
-            };
-            let mut parent_path = parent_path.clone();
-            parent_path.push_str(&path);
-            res.push((item_fn.sig.ident.to_string(), parent_path, method));
-        }
+    Ok((parent_path, parent_method))
+}
+
+fn get_resource_by_trait(item: ItemTrait) -> Result<Vec<(String, String, String)>, syn::Error> {
+    let mut res = vec![];
+    let (parent_path, parent_method) = get_parent_resource(&item)?;
+    for item_fn in get_rpc_methods(&item) {
+        let resource = get_asset_by_attrs(&item_fn.attrs)?;
+        let path = match resource.path {
+            Some(path) => path,
+            None => "/".to_owned() + &item_fn.sig.ident.unraw().to_string(),
+        };
+        let method = match resource.method {
+            Some(method) => method,
+            None => parent_method.clone(),
+        };
+        let mut parent_path = parent_path.clone();
+        parent_path.push_str(&path);
+        res.push((item_fn.sig.ident.unraw().to_string(), parent_path, method));
     }
     Ok(res)
 }
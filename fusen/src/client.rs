@@ -1,32 +1,123 @@
+use crate::context::{RequestContext, CONSUMER_APPLICATION, UNIQUE_IDENTIFIER};
 use crate::filter::FusenFilter;
 use crate::handler::HandlerContext;
-use fusen_common::codec::json_field_compatible;
+use crate::support::compression::{CompressionEncoding, CONTENT_ENCODING, GRPC_ENCODING};
+use crate::support::deadline::{self, encode_grpc_timeout, FUSEN_TIMEOUT, GRPC_TIMEOUT};
+use bytes::Bytes;
+use fusen_common::codec::{json_field_compatible, SerializationFormat, SerializationType};
 use fusen_common::error::FusenError;
+use fusen_common::logs::get_uuid;
 use fusen_common::register::Type;
-use fusen_common::FusenContext;
+use fusen_common::{ContextInfo, Direction, FusenContext, FusenRequest, MetaData, Path};
+use fusen_procedural_macro::Data;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
 use std::sync::Arc;
 
+#[derive(Serialize, Deserialize, Default, Clone, Data)]
+pub struct ClientInfo {
+    id: String,
+    serialization: Option<SerializationType>,
+    compression: Option<CompressionEncoding>,
+}
+
+impl ClientInfo {
+    pub fn new(id: String) -> Self {
+        ClientInfo {
+            id,
+            ..Default::default()
+        }
+    }
+}
+
 pub struct FusenClient {
+    application_name: String,
     server_type: Type,
     client_filter: &'static dyn FusenFilter,
     handle_context: Arc<HandlerContext>,
+    client_infos: Arc<HashMap<String, ClientInfo>>,
 }
 
 impl FusenClient {
     pub fn build(
+        application_name: String,
         server_type: Type,
         client_filter: &'static dyn FusenFilter,
         handle_context: Arc<HandlerContext>,
+        client_infos: Arc<HashMap<String, ClientInfo>>,
     ) -> FusenClient {
         FusenClient {
+            application_name,
             server_type,
             client_filter,
             handle_context,
+            client_infos,
+        }
+    }
+
+    pub fn get_serialization(&self, id: &str) -> SerializationType {
+        match self.server_type {
+            //Java服务只支持JSON
+            Type::Dubbo | Type::SpringCloud => SerializationType::JSON,
+            _ => self
+                .client_infos
+                .get(id)
+                .and_then(|e| e.serialization)
+                .unwrap_or_default(),
         }
     }
 
-    pub async fn invoke<Res>(&self, mut context: FusenContext) -> Result<Res, FusenError>
+    pub async fn invoke<Res>(&self, context: FusenContext) -> Result<Res, FusenError>
+    where
+        Res: Send + Sync + Serialize + for<'a> Deserialize<'a> + Default,
+    {
+        let serialization = self.get_serialization(context.get_context_info().get_class_name());
+        self.call(context, serialization).await
+    }
+
+    //泛化调用,不需要编译期的接口定义,参数与返回值统一使用JSON
+    pub async fn invoke_generic(
+        &self,
+        service: &str,
+        version: Option<&str>,
+        group: Option<&str>,
+        method: &str,
+        args: Vec<Value>,
+    ) -> Result<Value, FusenError> {
+        let mut fields_ty = vec![];
+        let mut bodys = vec![];
+        for (idx, arg) in args.iter().enumerate() {
+            fields_ty.push(format!("arg{}", idx));
+            bodys.push(Bytes::from(
+                serde_json::to_vec(arg).map_err(|e| FusenError::from(e.to_string()))?,
+            ));
+        }
+        let request =
+            FusenRequest::new_for_client("POST", fields_ty, bodys, SerializationType::JSON);
+        let mut context = FusenContext::new(
+            get_uuid(),
+            ContextInfo::default()
+                .path(Path::new("POST", format!("/{}/{}", service, method)))
+                .version(version.map(|e| e.to_owned()))
+                .class_name(service.to_owned())
+                .method_name(method.to_owned())
+                .group(group.map(|e| e.to_owned())),
+            request,
+            MetaData::new(),
+        );
+        context.get_mut_response().insert_return_ty("Value");
+        match self.call(context, SerializationType::JSON).await {
+            Err(FusenError::Null) => Ok(Value::Null),
+            res => res,
+        }
+    }
+
+    async fn call<Res>(
+        &self,
+        mut context: FusenContext,
+        serialization: SerializationType,
+    ) -> Result<Res, FusenError>
     where
         Res: Send + Sync + Serialize + for<'a> Deserialize<'a> + Default,
     {
@@ -35,15 +126,86 @@ impl FusenClient {
             .get_controller(&context.get_context_info().get_handler_key())
             .get_aspect();
         context.insert_server_type(self.server_type.clone());
-        let context = aspect_handler.aroud_(self.client_filter, context).await?;
+        context.set_direction(Direction::Client);
+        if serialization != SerializationType::JSON {
+            context
+                .get_mut_request()
+                .get_mut_headers()
+                .insert("accept".to_owned(), serialization.content_type().to_owned());
+        }
+        if let Some(compression) = self
+            .client_infos
+            .get(context.get_context_info().get_class_name())
+            .and_then(|e| e.compression)
+        {
+            let key = match self.server_type {
+                Type::Dubbo => GRPC_ENCODING,
+                _ => CONTENT_ENCODING,
+            };
+            context
+                .get_mut_request()
+                .get_mut_headers()
+                .insert(key.to_owned(), compression.as_str().to_owned());
+        }
+        //在服务端处理请求时发起的调用,自动携带上游的attachments
+        let headers = context.get_mut_request().get_mut_headers();
+        if let Some(request_context) = RequestContext::current() {
+            for (key, value) in request_context.get_attachments() {
+                headers.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+            headers
+                .entry(UNIQUE_IDENTIFIER.to_owned())
+                .or_insert_with(|| request_context.get_unique_identifier().clone());
+        }
+        if !self.application_name.is_empty() {
+            headers.insert(
+                CONSUMER_APPLICATION.to_owned(),
+                self.application_name.clone(),
+            );
+        }
+        //传递当前请求剩余的超时时间
+        let deadline = deadline::current_deadline();
+        if let Some(deadline) = deadline {
+            let timeout = deadline.saturating_duration_since(std::time::Instant::now());
+            if timeout.is_zero() {
+                return Err(deadline::deadline_exceeded());
+            }
+            let headers = context.get_mut_request().get_mut_headers();
+            headers.insert(FUSEN_TIMEOUT.to_owned(), timeout.as_millis().to_string());
+            if let Type::Dubbo = self.server_type {
+                headers.insert(GRPC_TIMEOUT.to_owned(), encode_grpc_timeout(timeout));
+            }
+        }
+        let context =
+            deadline::scope(deadline, aspect_handler.aroud_(self.client_filter, context)).await??;
         let return_ty = context.get_response().get_response_ty().unwrap();
+        let is_head = matches!(context.get_context_info().get_path(), Path::HEAD(_));
+        let serialization = context
+            .get_response()
+            .get_headers()
+            .get("content-type")
+            .map_or(SerializationType::JSON, |e| {
+                SerializationType::from(e.as_str())
+            });
         match context.into_response().into_response() {
-            Ok(res) => {
-                let response = json_field_compatible(return_ty, res)?;
-                let response: Res =
-                    serde_json::from_str(&response).map_err(|e| FusenError::from(e.to_string()))?;
-                Ok(response)
-            }
+            //HEAD响应没有响应体
+            Ok(_) if is_head => Ok(Res::default()),
+            Ok(res) => match serialization {
+                SerializationType::JSON => {
+                    let response = json_field_compatible(return_ty, res)?;
+                    let response: Res = serde_json::from_str(&response)
+                        .map_err(|e| FusenError::from(e.to_string()))?;
+                    Ok(response)
+                }
+                serialization => {
+                    if res.is_empty() {
+                        return Err(FusenError::Null);
+                    }
+                    serialization
+                        .deserialize(&res)
+                        .map_err(|e| FusenError::from(e.to_string()))
+                }
+            },
             Err(err) => Err(err),
         }
     }
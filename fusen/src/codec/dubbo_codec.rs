@@ -0,0 +1,553 @@
+use crate::support::{
+    deadline::{parse_millis, DUBBO_TIMEOUT, FUSEN_TIMEOUT},
+    hessian::{HessianDecoder, HessianEncoder},
+    triple::{get_java_exception, get_rpc_exception_code, RPC_EXCEPTION},
+};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use fusen_common::{
+    codec::SerializationType, error::FusenError, logs::get_trade_id, ContextInfo, Direction,
+    FusenContext, FusenRequest, MetaData, Path,
+};
+use fusen_procedural_macro::Data;
+use serde_json::{Map, Value};
+use std::{
+    sync::atomic::{AtomicI64, Ordering},
+    time::Instant,
+};
+
+pub const MAGIC: [u8; 2] = [0xda, 0xbb];
+pub const HEADER_LENGTH: usize = 16;
+const MAX_BODY_LENGTH: usize = 8 * 1024 * 1024;
+const FLAG_REQUEST: u8 = 0x80;
+const FLAG_TWOWAY: u8 = 0x40;
+const FLAG_EVENT: u8 = 0x20;
+const HESSIAN2_SERIALIZATION_ID: u8 = 2;
+const DUBBO_VERSION: &str = "2.0.2";
+const GENERIC_METHOD: &str = "$invoke";
+const GENERIC_PARAMETER_TYPES: &str = "Ljava/lang/String;[Ljava/lang/String;[Ljava/lang/Object;";
+
+pub const STATUS_OK: u8 = 20;
+pub const STATUS_BAD_REQUEST: u8 = 40;
+pub const STATUS_SERVICE_NOT_FOUND: u8 = 60;
+pub const STATUS_SERVICE_ERROR: u8 = 70;
+
+const RESPONSE_WITH_EXCEPTION: i32 = 0;
+const RESPONSE_VALUE: i32 = 1;
+const RESPONSE_NULL_VALUE: i32 = 2;
+const RESPONSE_WITH_EXCEPTION_WITH_ATTACHMENTS: i32 = 3;
+const RESPONSE_VALUE_WITH_ATTACHMENTS: i32 = 4;
+const RESPONSE_NULL_VALUE_WITH_ATTACHMENTS: i32 = 5;
+
+static REQUEST_ID: AtomicI64 = AtomicI64::new(0);
+
+pub fn next_request_id() -> i64 {
+    REQUEST_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+#[derive(Debug, Clone, Data)]
+pub struct DubboFrame {
+    id: i64,
+    flag: u8,
+    status: u8,
+    body: Bytes,
+}
+
+impl DubboFrame {
+    pub fn new_request(id: i64, two_way: bool, body: Bytes) -> Self {
+        let mut flag = FLAG_REQUEST | HESSIAN2_SERIALIZATION_ID;
+        if two_way {
+            flag |= FLAG_TWOWAY;
+        }
+        DubboFrame {
+            id,
+            flag,
+            status: 0,
+            body,
+        }
+    }
+
+    pub fn new_response(id: i64, status: u8, body: Bytes) -> Self {
+        DubboFrame {
+            id,
+            flag: HESSIAN2_SERIALIZATION_ID,
+            status,
+            body,
+        }
+    }
+
+    pub fn new_heartbeat(id: i64) -> Self {
+        DubboFrame {
+            id,
+            flag: FLAG_REQUEST | FLAG_TWOWAY | FLAG_EVENT | HESSIAN2_SERIALIZATION_ID,
+            status: 0,
+            body: Bytes::from_static(b"N"),
+        }
+    }
+
+    pub fn heartbeat_response(&self) -> Self {
+        DubboFrame {
+            id: self.id,
+            flag: FLAG_EVENT | HESSIAN2_SERIALIZATION_ID,
+            status: STATUS_OK,
+            body: Bytes::from_static(b"N"),
+        }
+    }
+
+    pub fn is_request(&self) -> bool {
+        self.flag & FLAG_REQUEST != 0
+    }
+
+    pub fn is_two_way(&self) -> bool {
+        self.flag & FLAG_TWOWAY != 0
+    }
+
+    pub fn is_event(&self) -> bool {
+        self.flag & FLAG_EVENT != 0
+    }
+
+    pub fn decode(buf: &mut BytesMut) -> Result<Option<DubboFrame>, crate::Error> {
+        if buf.len() < HEADER_LENGTH {
+            return Ok(None);
+        }
+        if buf[0..2] != MAGIC {
+            return Err("dubbo decode error : bad magic".into());
+        }
+        let body_len = i32::from_be_bytes([buf[12], buf[13], buf[14], buf[15]]);
+        if body_len < 0 || body_len as usize > MAX_BODY_LENGTH {
+            return Err(format!("dubbo decode error : bad body length {}", body_len).into());
+        }
+        if buf.len() < HEADER_LENGTH + body_len as usize {
+            buf.reserve(HEADER_LENGTH + body_len as usize - buf.len());
+            return Ok(None);
+        }
+        let mut header = buf.split_to(HEADER_LENGTH);
+        header.advance(2);
+        let flag = header.get_u8();
+        let status = header.get_u8();
+        let id = header.get_i64();
+        let body = buf.split_to(body_len as usize).freeze();
+        if flag & 0x1f != HESSIAN2_SERIALIZATION_ID {
+            return Err(format!(
+                "dubbo decode error : not support serialization {}",
+                flag & 0x1f
+            )
+            .into());
+        }
+        Ok(Some(DubboFrame {
+            id,
+            flag,
+            status,
+            body,
+        }))
+    }
+
+    pub fn encode(&self) -> Bytes {
+        let mut buf = BytesMut::with_capacity(HEADER_LENGTH + self.body.len());
+        buf.put_slice(&MAGIC);
+        buf.put_u8(self.flag);
+        buf.put_u8(self.status);
+        buf.put_i64(self.id);
+        buf.put_i32(self.body.len() as i32);
+        buf.put_slice(&self.body);
+        buf.freeze()
+    }
+}
+
+#[derive(Debug, Default, Data)]
+pub struct DubboRequest {
+    dubbo_version: String,
+    path: String,
+    version: Option<String>,
+    method: String,
+    parameter_types: String,
+    args: Vec<Value>,
+    attachments: Map<String, Value>,
+}
+
+impl DubboRequest {
+    pub fn decode(body: &[u8]) -> Result<Self, crate::Error> {
+        let mut decoder = HessianDecoder::new(body);
+        let dubbo_version = decoder.read_string()?;
+        let path = decoder.read_string()?;
+        let version = decoder.read_string()?;
+        let method = decoder.read_string()?;
+        let parameter_types = decoder.read_string()?;
+        let mut args = vec![];
+        for _ in 0..get_parameter_count(&parameter_types) {
+            args.push(decoder.read_value()?);
+        }
+        let attachments = if decoder.has_remaining() {
+            match decoder.read_value()? {
+                Value::Object(map) => map,
+                _ => Default::default(),
+            }
+        } else {
+            Default::default()
+        };
+        Ok(DubboRequest {
+            dubbo_version,
+            path,
+            version: if version.is_empty() || version == "0.0.0" {
+                None
+            } else {
+                Some(version)
+            },
+            method,
+            parameter_types,
+            args,
+            attachments,
+        })
+    }
+
+    pub fn encode(&self) -> Bytes {
+        let mut encoder = HessianEncoder::new();
+        encoder.write_string(&self.dubbo_version);
+        encoder.write_string(&self.path);
+        encoder.write_string(self.version.as_deref().unwrap_or("0.0.0"));
+        encoder.write_string(&self.method);
+        encoder.write_string(&self.parameter_types);
+        if self.method == GENERIC_METHOD && self.args.len() == 3 {
+            encoder.write_value(&self.args[0]);
+            encoder.write_value(&self.args[1]);
+            encoder.write_typed_list("[object", self.args[2].as_array().map_or(&[], |e| e));
+        } else {
+            for arg in &self.args {
+                encoder.write_value(arg);
+            }
+        }
+        encoder.write_map(&self.attachments);
+        encoder.into_bytes()
+    }
+
+    //泛化调用时取出真实的方法名与参数
+    fn into_method_and_args(self) -> (String, Vec<Value>) {
+        if self.method == GENERIC_METHOD && self.args.len() == 3 {
+            let mut args = self.args;
+            let real_args = match args.pop() {
+                Some(Value::Array(args)) => args,
+                _ => vec![],
+            };
+            let method = match args.swap_remove(0) {
+                Value::String(method) => method,
+                method => method.to_string(),
+            };
+            (method, real_args)
+        } else {
+            (self.method, self.args)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum DubboResult {
+    Value(Value),
+    Null,
+    Exception(Value),
+}
+
+#[derive(Debug, Data)]
+pub struct DubboResponse {
+    status: u8,
+    result: Result<DubboResult, String>,
+    attachments: Map<String, Value>,
+}
+
+impl DubboResponse {
+    pub fn decode(frame: &DubboFrame) -> Result<Self, crate::Error> {
+        let mut decoder = HessianDecoder::new(frame.get_body());
+        if *frame.get_status() != STATUS_OK {
+            let message = decoder.read_string().unwrap_or_default();
+            return Ok(DubboResponse {
+                status: *frame.get_status(),
+                result: Err(message),
+                attachments: Default::default(),
+            });
+        }
+        let flag = decoder.read_int()?;
+        let result = match flag {
+            RESPONSE_VALUE | RESPONSE_VALUE_WITH_ATTACHMENTS => {
+                DubboResult::Value(decoder.read_value()?)
+            }
+            RESPONSE_NULL_VALUE | RESPONSE_NULL_VALUE_WITH_ATTACHMENTS => DubboResult::Null,
+            RESPONSE_WITH_EXCEPTION | RESPONSE_WITH_EXCEPTION_WITH_ATTACHMENTS => {
+                DubboResult::Exception(decoder.read_value_with_type()?)
+            }
+            flag => {
+                return Err(format!("dubbo decode error : unknown response flag {}", flag).into())
+            }
+        };
+        let mut attachments = Map::new();
+        if flag >= RESPONSE_WITH_EXCEPTION_WITH_ATTACHMENTS {
+            if let Value::Object(map) = decoder.read_value()? {
+                attachments = map;
+            }
+        }
+        Ok(DubboResponse {
+            status: STATUS_OK,
+            result: Ok(result),
+            attachments,
+        })
+    }
+
+    pub fn encode(&self, with_attachments: bool) -> Bytes {
+        let mut encoder = HessianEncoder::new();
+        match &self.result {
+            Err(message) => encoder.write_string(message),
+            Ok(result) => {
+                let offset = if with_attachments { 3 } else { 0 };
+                match result {
+                    DubboResult::Value(value) => {
+                        encoder.write_int(RESPONSE_VALUE + offset);
+                        encoder.write_value(value);
+                    }
+                    DubboResult::Null => encoder.write_int(RESPONSE_NULL_VALUE + offset),
+                    DubboResult::Exception(value) => {
+                        encoder.write_int(RESPONSE_WITH_EXCEPTION + offset);
+                        match value.as_object() {
+                            Some(map) => {
+                                let mut fields = map.clone();
+                                match fields.remove("@type") {
+                                    Some(Value::String(class_name)) => {
+                                        encoder.write_object(&class_name, &fields)
+                                    }
+                                    _ => encoder.write_value(value),
+                                }
+                            }
+                            None => encoder.write_value(value),
+                        }
+                    }
+                }
+                if with_attachments {
+                    encoder.write_map(&self.attachments);
+                }
+            }
+        }
+        encoder.into_bytes()
+    }
+}
+
+//Throwable的异常信息字段为detailMessage
+fn get_rpc_exception(err: &FusenError) -> Value {
+    serde_json::json!({
+        "@type": RPC_EXCEPTION,
+        "code": get_rpc_exception_code(err),
+        "detailMessage": err.to_string(),
+    })
+}
+
+fn get_parameter_count(parameter_types: &str) -> usize {
+    let mut count = 0;
+    let mut chars = parameter_types.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '[' => continue,
+            'L' => {
+                for ch in chars.by_ref() {
+                    if ch == ';' {
+                        break;
+                    }
+                }
+                count += 1;
+            }
+            _ => count += 1,
+        }
+    }
+    count
+}
+
+fn support_response_attachments(dubbo_version: &str) -> bool {
+    let version: Vec<u32> = dubbo_version
+        .split('.')
+        .map(|e| e.parse().unwrap_or_default())
+        .collect();
+    version >= vec![2, 0, 2]
+}
+
+pub struct DubboCodec;
+
+impl DubboCodec {
+    //客户端统一使用泛化调用,无需知道Java侧的参数类型
+    pub fn encode_request(&self, context: &FusenContext) -> Result<DubboFrame, FusenError> {
+        let context_info = context.get_context_info();
+        let request = context.get_request();
+        let fields: Vec<String> = match request.get_query_fields() {
+            Some(fields) => fields.iter().map(|e| e.1.clone()).collect(),
+            None => {
+                if request.get_body().is_empty() {
+                    vec![]
+                } else {
+                    serde_json::from_slice(request.get_body())
+                        .map_err(|e| FusenError::from(e.to_string()))?
+                }
+            }
+        };
+        let mut args = vec![];
+        for field in fields {
+            let arg = match serde_json::from_str::<Value>(&field) {
+                Ok(arg) => arg,
+                Err(_) => Value::String(field),
+            };
+            args.push(arg);
+        }
+        let mut attachments = Map::new();
+        attachments.insert(
+            "path".to_owned(),
+            Value::from(context_info.get_class_name().clone()),
+        );
+        attachments.insert(
+            "interface".to_owned(),
+            Value::from(context_info.get_class_name().clone()),
+        );
+        attachments.insert("generic".to_owned(), Value::from("true"));
+        if let Some(version) = context_info.get_version() {
+            attachments.insert("version".to_owned(), Value::from(version.clone()));
+        }
+        if let Some(group) = context_info.get_group() {
+            attachments.insert("group".to_owned(), Value::from(group.clone()));
+        }
+        for (key, value) in request.get_headers() {
+            attachments.insert(key.clone(), Value::from(value.clone()));
+        }
+        //Java服务端读取timeout附件
+        if let Some(timeout) = request.get_headers().get(FUSEN_TIMEOUT) {
+            attachments.insert(DUBBO_TIMEOUT.to_owned(), Value::from(timeout.clone()));
+        }
+        let request = DubboRequest {
+            dubbo_version: DUBBO_VERSION.to_owned(),
+            path: context_info.get_class_name().clone(),
+            version: context_info.get_version().clone(),
+            method: GENERIC_METHOD.to_owned(),
+            parameter_types: GENERIC_PARAMETER_TYPES.to_owned(),
+            args: vec![
+                Value::from(context_info.get_method_name().clone()),
+                Value::Null,
+                Value::Array(args),
+            ],
+            attachments,
+        };
+        Ok(DubboFrame::new_request(
+            next_request_id(),
+            true,
+            request.encode(),
+        ))
+    }
+
+    pub fn decode_response(&self, frame: DubboFrame) -> Result<Bytes, FusenError> {
+        let response = DubboResponse::decode(&frame).map_err(FusenError::from)?;
+        match response.result {
+            Ok(DubboResult::Value(value)) => Ok(Bytes::from(value.to_string())),
+            Ok(DubboResult::Null) => Err(FusenError::Null),
+            Ok(DubboResult::Exception(value)) => Err(match get_java_exception(&value, 0) {
+                Some(exception) => exception.into(),
+                None => FusenError::from(value.to_string()),
+            }),
+            Err(message) => Err(FusenError::from(format!(
+                "dubbo status {} : {}",
+                response.status, message
+            ))),
+        }
+    }
+
+    pub fn decode_request(&self, frame: &DubboFrame) -> Result<(FusenContext, bool), crate::Error> {
+        let request = DubboRequest::decode(frame.get_body())?;
+        let with_attachments = support_response_attachments(&request.dubbo_version);
+        let mut meta_data = MetaData::new();
+        for (key, value) in &request.attachments {
+            meta_data.insert(
+                key.clone(),
+                value.as_str().map_or(value.to_string(), |e| e.to_owned()),
+            );
+        }
+        let version = request.version.clone();
+        let class_name = request.path.clone();
+        let (method, args) = request.into_method_and_args();
+        let fields: Vec<String> = args.iter().map(|e| e.to_string()).collect();
+        let unique_identifier = meta_data
+            .get_value("unique_identifier")
+            .map_or(get_trade_id(), |e| e.clone());
+        let context = FusenContext::new(
+            unique_identifier,
+            ContextInfo::default()
+                .path(Path::POST(format!("/{}/{}", class_name, method)))
+                .class_name(class_name)
+                .method_name(method)
+                .version(version),
+            FusenRequest::new(None, Bytes::from(serde_json::to_vec(&fields)?))
+                .serialization(SerializationType::JSON),
+            meta_data,
+        );
+        let deadline = context
+            .get_meta_data()
+            .get_value(DUBBO_TIMEOUT)
+            .and_then(|e| parse_millis(e))
+            .map(|e| Instant::now() + e);
+        Ok((
+            context.deadline(deadline).direction(Direction::Server),
+            with_attachments,
+        ))
+    }
+
+    pub fn encode_response(
+        &self,
+        id: i64,
+        with_attachments: bool,
+        context: Result<FusenContext, FusenError>,
+    ) -> DubboFrame {
+        let result = match context.map(|e| e.into_response().into_response()) {
+            Ok(Ok(bytes)) => match serde_json::from_slice::<Value>(&bytes) {
+                Ok(Value::Null) => Ok(DubboResult::Null),
+                Ok(value) => Ok(DubboResult::Value(value)),
+                Err(_) => Ok(DubboResult::Value(Value::String(
+                    String::from_utf8_lossy(&bytes).to_string(),
+                ))),
+            },
+            Ok(Err(FusenError::Null)) | Err(FusenError::Null) => Ok(DubboResult::Null),
+            Ok(Err(FusenError::NotFind)) | Err(FusenError::NotFind) => Err(FusenError::NotFind),
+            //业务与处理异常按照RpcException返回,Java消费端可以拿到原始的异常信息与异常码
+            Ok(Err(err)) | Err(err) => Ok(DubboResult::Exception(get_rpc_exception(&err))),
+        };
+        let status = match &result {
+            Ok(_) => STATUS_OK,
+            Err(_) => STATUS_SERVICE_NOT_FOUND,
+        };
+        let response = DubboResponse {
+            status,
+            result: result.map_err(|e| e.to_string()),
+            attachments: Default::default(),
+        };
+        DubboFrame::new_response(id, status, response.encode(with_attachments))
+    }
+}
+
+#[test]
+fn test() {
+    assert_eq!(get_parameter_count("Ljava/lang/String;I[J[Lcom/Dto;"), 4);
+    let context = FusenContext::new(
+        "test".to_owned(),
+        ContextInfo::default()
+            .class_name("org.apache.dubbo.springboot.demo.DemoService".to_owned())
+            .method_name("sayHello".to_owned()),
+        FusenRequest::new(None, Bytes::from("[\"\\\"world\\\"\"]")),
+        MetaData::new(),
+    );
+    let codec = DubboCodec;
+    let frame = codec.encode_request(&context).unwrap();
+    let mut buf = BytesMut::from(&frame.encode()[..]);
+    let frame = DubboFrame::decode(&mut buf).unwrap().unwrap();
+    assert!(frame.is_request() && frame.is_two_way() && !frame.is_event());
+    let (context, with_attachments) = codec.decode_request(&frame).unwrap();
+    assert!(with_attachments);
+    assert_eq!(context.get_context_info().get_method_name(), "sayHello");
+    let request = FusenRequest::new(None, context.get_request().get_body().clone());
+    let fields = request.get_fields(vec!["name"]).unwrap();
+    assert_eq!(fields[0].deserialize::<String>().unwrap(), "world");
+    //处理异常以RpcException返回,帧状态为OK
+    let frame = codec.encode_response(1, true, Err(FusenError::from("bad state")));
+    assert_eq!(*frame.get_status(), STATUS_OK);
+    let err = codec.decode_response(frame).unwrap_err();
+    let exception = err.java_exception().unwrap();
+    assert_eq!(exception.get_class_name(), RPC_EXCEPTION);
+    assert_eq!(err.to_string(), format!("{}: bad state", RPC_EXCEPTION));
+    let frame = codec.encode_response(2, false, Err(FusenError::NotFind));
+    assert_eq!(*frame.get_status(), STATUS_SERVICE_NOT_FOUND);
+}
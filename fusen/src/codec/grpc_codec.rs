@@ -1,11 +1,98 @@
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use fusen_common::error::{FusenError, StatusCode};
 use prost::Message;
-use std::marker::PhantomData;
+use std::{io::Read, marker::PhantomData};
 
-use crate::support::triple::get_buf;
+use crate::support::compression::{CompressionEncoding, MIN_COMPRESS_SIZE};
 
 use super::BodyCodec;
 
+pub const GRPC_HEADER_LENGTH: usize = 5;
+//与grpc-java/grpc-go默认值保持一致
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+
+#[derive(Debug)]
+pub struct GrpcFrame {
+    pub compressed: bool,
+    pub message: Bytes,
+}
+
+//gRPC Length-Prefixed-Message解码器,按DATA帧增量输入,消息可以跨帧也可以一帧多条
+pub struct GrpcFrameDecoder {
+    buf: BytesMut,
+    max_message_size: usize,
+}
+
+impl GrpcFrameDecoder {
+    pub fn new(max_message_size: usize) -> Self {
+        GrpcFrameDecoder {
+            buf: BytesMut::new(),
+            max_message_size,
+        }
+    }
+
+    pub fn decode(&mut self, data: &[u8]) -> Result<Vec<GrpcFrame>, FusenError> {
+        self.buf.extend_from_slice(data);
+        let mut frames = vec![];
+        while self.buf.len() >= GRPC_HEADER_LENGTH {
+            let compressed = match self.buf[0] {
+                0 => false,
+                1 => true,
+                flag => {
+                    return Err(FusenError::from(format!(
+                        "grpc message bad compressed flag {}",
+                        flag
+                    )))
+                }
+            };
+            let len =
+                u32::from_be_bytes([self.buf[1], self.buf[2], self.buf[3], self.buf[4]]) as usize;
+            if len > self.max_message_size {
+                return Err(FusenError::status(
+                    StatusCode::ResourceExhausted,
+                    format!(
+                        "grpc message larger than max ({} vs. {})",
+                        len, self.max_message_size
+                    ),
+                ));
+            }
+            if self.buf.len() < GRPC_HEADER_LENGTH + len {
+                self.buf.reserve(GRPC_HEADER_LENGTH + len - self.buf.len());
+                break;
+            }
+            self.buf.advance(GRPC_HEADER_LENGTH);
+            frames.push(GrpcFrame {
+                compressed,
+                message: self.buf.split_to(len).freeze(),
+            });
+        }
+        Ok(frames)
+    }
+
+    pub fn finish(&self) -> Result<(), FusenError> {
+        if self.buf.is_empty() {
+            Ok(())
+        } else {
+            Err(FusenError::from(format!(
+                "grpc body ended with incomplete message, remaining {} bytes",
+                self.buf.len()
+            )))
+        }
+    }
+}
+
+pub fn encode_frame(message: &[u8], compressed: bool) -> Result<Bytes, FusenError> {
+    let len = u32::try_from(message.len())
+        .map_err(|_| FusenError::status(StatusCode::ResourceExhausted, "grpc message too large"))?;
+    let mut buf = BytesMut::with_capacity(GRPC_HEADER_LENGTH + message.len());
+    buf.put_u8(compressed as u8);
+    buf.put_u32(len);
+    buf.put_slice(message);
+    Ok(buf.freeze())
+}
+
 pub struct GrpcBodyCodec<D, U, T> {
+    max_message_size: usize,
     _d: PhantomData<D>,
     _u: PhantomData<U>,
     _t: PhantomData<T>,
@@ -13,12 +100,72 @@ pub struct GrpcBodyCodec<D, U, T> {
 
 impl<D, U, T> GrpcBodyCodec<D, U, T> {
     pub fn new() -> Self {
+        Self::with_max_message_size(DEFAULT_MAX_MESSAGE_SIZE)
+    }
+
+    pub fn with_max_message_size(max_message_size: usize) -> Self {
         GrpcBodyCodec {
+            max_message_size,
             _d: PhantomData,
             _u: PhantomData,
             _t: PhantomData,
         }
     }
+
+    pub fn frame_decoder(&self) -> GrpcFrameDecoder {
+        GrpcFrameDecoder::new(self.max_message_size)
+    }
+}
+
+impl<D, U, T> GrpcBodyCodec<D, U, T>
+where
+    U: Message + Default,
+{
+    //一元调用只允许一条消息
+    pub fn decode_frames(
+        &self,
+        mut frames: Vec<GrpcFrame>,
+        compression: Option<CompressionEncoding>,
+    ) -> Result<U, FusenError> {
+        if frames.len() != 1 {
+            return Err(FusenError::from(format!(
+                "grpc unary call expects one message, got {}",
+                frames.len()
+            )));
+        }
+        let GrpcFrame {
+            compressed,
+            message,
+        } = frames.remove(0);
+        let message = if compressed {
+            //压缩标识为1时使用grpc-encoding声明的方式解压
+            let compression = compression.ok_or(FusenError::from(
+                "grpc message compressed without grpc-encoding",
+            ))?;
+            //边解压边计数,超出最大长度时立即返回
+            let mut buf = vec![];
+            compression
+                .decoder(&message)
+                .and_then(|e| {
+                    e.take(self.max_message_size as u64 + 1)
+                        .read_to_end(&mut buf)
+                })
+                .map_err(|e| FusenError::from(e.to_string()))?;
+            if buf.len() > self.max_message_size {
+                return Err(FusenError::status(
+                    StatusCode::ResourceExhausted,
+                    format!(
+                        "grpc decompressed message larger than max {}",
+                        self.max_message_size
+                    ),
+                ));
+            }
+            Bytes::from(buf)
+        } else {
+            message
+        };
+        U::decode(message).map_err(|e| FusenError::from(e.to_string()))
+    }
 }
 
 impl<D, U, T> Default for GrpcBodyCodec<D, U, T> {
@@ -37,15 +184,95 @@ where
 
     type EncodeType = T;
 
-    fn decode(&self, body: &D) -> Result<Self::DecodeType, crate::Error> {
-        let data = body.chunk();
-        let wrapper = Self::DecodeType::decode(&data[5..])?;
-        Ok(wrapper)
+    fn decode(
+        &self,
+        body: &D,
+        compression: Option<CompressionEncoding>,
+    ) -> Result<Self::DecodeType, crate::Error> {
+        let mut decoder = self.frame_decoder();
+        let frames = decoder.decode(body.chunk())?;
+        decoder.finish()?;
+        Ok(self.decode_frames(frames, compression)?)
     }
 
-    fn encode(&self, res: &Self::EncodeType) -> Result<bytes::Bytes, crate::Error> {
+    fn encode(
+        &self,
+        res: &Self::EncodeType,
+        compression: Option<CompressionEncoding>,
+    ) -> Result<bytes::Bytes, crate::Error> {
         let buf = res.encode_to_vec();
-        let buf = get_buf(buf);
-        Ok(bytes::Bytes::from(buf))
+        if buf.len() > self.max_message_size {
+            return Err(FusenError::status(
+                StatusCode::ResourceExhausted,
+                format!(
+                    "grpc message larger than max ({} vs. {})",
+                    buf.len(),
+                    self.max_message_size
+                ),
+            )
+            .into());
+        }
+        match compression {
+            Some(compression) if buf.len() >= MIN_COMPRESS_SIZE => {
+                Ok(encode_frame(&compression.compress(&buf)?, true)?)
+            }
+            _ => Ok(encode_frame(&buf, false)?),
+        }
     }
 }
+
+#[test]
+fn test() {
+    let mut body = BytesMut::new();
+    body.extend_from_slice(&encode_frame(b"hello", false).unwrap());
+    body.extend_from_slice(&encode_frame(b"fusen", true).unwrap());
+    let mut decoder = GrpcFrameDecoder::new(16);
+    //按照任意位置拆分DATA帧
+    let mut frames = decoder.decode(&body[..3]).unwrap();
+    frames.append(&mut decoder.decode(&body[3..12]).unwrap());
+    frames.append(&mut decoder.decode(&body[12..]).unwrap());
+    decoder.finish().unwrap();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(&frames[0].message[..], b"hello");
+    assert!(frames[1].compressed);
+    assert_eq!(&frames[1].message[..], b"fusen");
+    let mut decoder = GrpcFrameDecoder::new(4);
+    assert_eq!(
+        decoder.decode(&body).unwrap_err().code(),
+        StatusCode::ResourceExhausted
+    );
+    let mut decoder = GrpcFrameDecoder::new(16);
+    decoder.decode(&body[..7]).unwrap();
+    assert!(decoder.finish().is_err());
+    //压缩后很小但解压后超出最大长度的消息
+    let codec = GrpcBodyCodec::<Bytes, prost_types::Any, prost_types::Any>::with_max_message_size(
+        1024 * 1024,
+    );
+    let bomb = CompressionEncoding::Gzip
+        .compress(&vec![0; 64 * 1024 * 1024])
+        .unwrap();
+    let frames = vec![GrpcFrame {
+        compressed: true,
+        message: bomb,
+    }];
+    assert_eq!(
+        codec
+            .decode_frames(frames, Some(CompressionEncoding::Gzip))
+            .unwrap_err()
+            .code(),
+        StatusCode::ResourceExhausted
+    );
+    let any = prost_types::Any {
+        type_url: "fusen".to_owned(),
+        value: vec![1; 2048],
+    };
+    let body = codec.encode(&any, Some(CompressionEncoding::Gzip)).unwrap();
+    let frames = codec.frame_decoder().decode(&body).unwrap();
+    assert!(frames[0].compressed);
+    assert_eq!(
+        codec
+            .decode_frames(frames, Some(CompressionEncoding::Gzip))
+            .unwrap(),
+        any
+    );
+}
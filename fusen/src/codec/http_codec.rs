@@ -13,15 +13,21 @@ use http_body_util::BodyExt;
 pub struct FusenHttpCodec {
     request_handle: RequestHandler,
     response_handle: ResponseHandler,
+    path_cache: Arc<PathCache>,
 }
 
 impl FusenHttpCodec {
     pub fn new(path_cahce: Arc<PathCache>) -> Self {
         FusenHttpCodec {
-            request_handle: RequestHandler::new(path_cahce),
+            request_handle: RequestHandler::new(path_cahce.clone()),
             response_handle: ResponseHandler::new(),
+            path_cache: path_cahce,
         }
     }
+
+    pub fn get_path_cache(&self) -> &PathCache {
+        &self.path_cache
+    }
 }
 
 impl HttpCodec<Bytes, hyper::Error> for FusenHttpCodec {
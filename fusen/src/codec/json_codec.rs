@@ -3,6 +3,7 @@ use serde::{de::DeserializeOwned, Serialize};
 use std::marker::PhantomData;
 
 use super::BodyCodec;
+use crate::support::compression::{CompressionEncoding, MAX_DECOMPRESS_SIZE};
 
 pub struct JsonBodyCodec<D, U, T> {
     _d: PhantomData<D>,
@@ -36,14 +37,30 @@ where
 
     type EncodeType = T;
 
-    fn decode(&self, body: &D) -> Result<Self::DecodeType, crate::Error> {
-        Ok(serde_json::from_slice(body.chunk())?)
+    fn decode(
+        &self,
+        body: &D,
+        compression: Option<CompressionEncoding>,
+    ) -> Result<Self::DecodeType, crate::Error> {
+        match compression {
+            Some(compression) => Ok(serde_json::from_slice(
+                &compression.decompress(body.chunk(), MAX_DECOMPRESS_SIZE)?,
+            )?),
+            None => Ok(serde_json::from_slice(body.chunk())?),
+        }
     }
 
-    fn encode(&self, res: &Self::EncodeType) -> Result<bytes::Bytes, crate::Error> {
+    fn encode(
+        &self,
+        res: &Self::EncodeType,
+        compression: Option<CompressionEncoding>,
+    ) -> Result<bytes::Bytes, crate::Error> {
         let mut byte = bytes::BytesMut::new();
         let mut_bytes = &mut byte;
         serde_json::to_writer(mut_bytes.writer(), &res).map_err(Box::new)?;
-        Ok(byte.into())
+        match compression {
+            Some(compression) => Ok(compression.compress(&byte)?),
+            None => Ok(byte.into()),
+        }
     }
 }
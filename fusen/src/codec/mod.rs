@@ -1,9 +1,11 @@
 use std::convert::Infallible;
 
+use crate::support::compression::CompressionEncoding;
 use crate::BoxBody;
 use fusen_common::FusenContext;
 use http::Request;
 use http::Response;
+pub mod dubbo_codec;
 pub mod grpc_codec;
 pub mod http_codec;
 pub mod json_codec;
@@ -31,7 +33,15 @@ where
 
     type EncodeType;
 
-    fn decode(&self, body: &D) -> Result<Self::DecodeType, crate::Error>;
+    fn decode(
+        &self,
+        body: &D,
+        compression: Option<CompressionEncoding>,
+    ) -> Result<Self::DecodeType, crate::Error>;
 
-    fn encode(&self, res: &Self::EncodeType) -> Result<bytes::Bytes, crate::Error>;
+    fn encode(
+        &self,
+        res: &Self::EncodeType,
+        compression: Option<CompressionEncoding>,
+    ) -> Result<bytes::Bytes, crate::Error>;
 }
@@ -1,18 +1,38 @@
 use super::{grpc_codec::GrpcBodyCodec, BodyCodec};
 use crate::{
     filter::server::{PathCache, PathCacheResult},
-    support::triple::TripleRequestWrapper,
+    support::{
+        compression::{
+            CompressionEncoding, ACCEPT_ENCODING, CONTENT_ENCODING, GRPC_ACCEPT_ENCODING,
+            GRPC_ENCODING, MAX_DECOMPRESS_SIZE, MIN_COMPRESS_SIZE, SUPPORT_ENCODINGS,
+        },
+        deadline::get_deadline,
+        triple::{TripleRequestWrapper, TRI_EXCEPTION_CODE},
+    },
     BoxBody,
 };
 use bytes::{Bytes, BytesMut};
 use fusen_common::{
-    error::FusenError, logs::get_trade_id, register::Type, ContextInfo, FusenContext, FusenRequest,
-    MetaData, Path,
+    codec::{SerializationFormat, SerializationType},
+    error::FusenError,
+    logs::get_trade_id,
+    register::Type,
+    ConnectionInfo, ContextInfo, Direction, FieldBinding, FusenContext, FusenRequest, MetaData,
+    Path,
 };
 use http::Request;
 use http_body_util::{BodyExt, Full};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde_json::Value;
 use std::{convert::Infallible, sync::Arc};
 
+//path与query中除unreserved字符外均需编码
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
 pub(crate) trait RequestCodec<T, E> {
     fn encode(&self, msg: &FusenContext) -> Result<Request<BoxBody<T, Infallible>>, crate::Error>;
 
@@ -20,14 +40,7 @@ pub(crate) trait RequestCodec<T, E> {
 }
 
 pub struct RequestHandler {
-    grpc_codec: Box<
-        (dyn BodyCodec<
-            bytes::Bytes,
-            DecodeType = TripleRequestWrapper,
-            EncodeType = TripleRequestWrapper,
-        > + Sync
-             + Send),
-    >,
+    grpc_codec: GrpcBodyCodec<bytes::Bytes, TripleRequestWrapper, TripleRequestWrapper>,
     path_cache: Arc<PathCache>,
 }
 
@@ -36,7 +49,7 @@ impl RequestHandler {
         let grpc_codec =
             GrpcBodyCodec::<bytes::Bytes, TripleRequestWrapper, TripleRequestWrapper>::new();
         RequestHandler {
-            grpc_codec: Box::new(grpc_codec),
+            grpc_codec,
             path_cache,
         }
     }
@@ -45,64 +58,84 @@ impl RequestHandler {
 impl RequestCodec<Bytes, hyper::Error> for RequestHandler {
     fn encode(
         &self,
-        context: &FusenContext,        
+        context: &FusenContext,
     ) -> Result<Request<BoxBody<Bytes, Infallible>>, crate::Error> {
         let content_type = match context.get_server_type() {
             &Type::Dubbo => ("application/grpc", "tri-service-version"),
-            _ => ("application/json", "version"),
+            _ => (
+                context.get_request().get_serialization().content_type(),
+                "version",
+            ),
         };
+        let headers = context.get_request().get_headers();
+        let (encoding_key, accept_encoding_key) = match context.get_server_type() {
+            &Type::Dubbo => (GRPC_ENCODING, GRPC_ACCEPT_ENCODING),
+            _ => (CONTENT_ENCODING, ACCEPT_ENCODING),
+        };
+        let compression = CompressionEncoding::from_encoding(headers.get(encoding_key))?;
         let mut builder = Request::builder()
             .header("content-type", content_type.0)
             .header("connection", "keep-alive");
-        for (key, value) in context.get_request().get_headers() {
-            builder = builder.header(key, value);
+        if !headers.contains_key(accept_encoding_key) {
+            builder = builder.header(accept_encoding_key, SUPPORT_ENCODINGS);
+        }
+        //fusen服务端按grpc-status返回错误,保留错误码与详情
+        if matches!(context.get_server_type(), Type::Dubbo)
+            && !headers.contains_key(TRI_EXCEPTION_CODE)
+        {
+            builder = builder.header(TRI_EXCEPTION_CODE, "0");
+        }
+        for (key, value) in headers {
+            //HTTP请求体是否压缩由消息长度决定
+            if key != CONTENT_ENCODING {
+                builder = builder.header(key, value);
+            }
         }
         if let Some(version) = context.get_context_info().get_version() {
             builder = builder.header(content_type.1, version);
         }
-        let request = match context.get_context_info().get_path().clone() {
-            fusen_common::Path::GET(path) => builder
-                .method("GET")
-                .uri(get_path(
-                    path,
-                    context.get_request().get_query_fields().as_ref(),
-                ))
-                .body(Full::new(Bytes::new()).boxed()),
-            fusen_common::Path::PUT(path) => builder
-                .method("PUT")
-                .uri(get_path(
-                    path,
-                    context.get_request().get_query_fields().as_ref(),
-                ))
-                .body(Full::new(Bytes::new()).boxed()),
-            fusen_common::Path::DELETE(path) => builder
-                .method("DELETE")
-                .uri(get_path(
-                    path,
-                    context.get_request().get_query_fields().as_ref(),
-                ))
-                .body(Full::new(Bytes::new()).boxed()),
-            fusen_common::Path::POST(mut path) => {
-                let body: Bytes = match context.get_server_type() {
-                    &Type::Dubbo => {
-                        path = format!(
-                            "/{}/{}",
-                            context.get_context_info().get_class_name(),
-                            context.get_context_info().get_method_name()
-                        );
-                        let fields: Vec<String> =
-                            serde_json::from_slice(context.get_request().get_body())?;
-                        let triple_request_wrapper = TripleRequestWrapper::from(fields);
-                        self.grpc_codec.encode(&triple_request_wrapper)?
-                    }
-                    _ => Bytes::copy_from_slice(context.get_request().get_body()),
+        let request = match context.get_server_type() {
+            &Type::Dubbo => {
+                let path = format!(
+                    "/{}/{}",
+                    context.get_context_info().get_class_name(),
+                    context.get_context_info().get_method_name()
+                );
+                let fields: Vec<String> = match context.get_request().get_query_fields() {
+                    Some(query_fields) => query_fields.iter().map(|e| e.1.clone()).collect(),
+                    None => serde_json::from_slice(context.get_request().get_body())?,
                 };
-                let builder = builder.header("content-length", body.len());
+                let triple_request_wrapper = TripleRequestWrapper::from(fields);
+                let body = self
+                    .grpc_codec
+                    .encode(&triple_request_wrapper, compression)?;
                 builder
+                    .header("content-length", body.len())
                     .method("POST")
                     .uri(path)
                     .body(Full::new(body).boxed())
             }
+            _ => {
+                let path = context.get_context_info().get_path();
+                let HttpParts { uri, headers, body } = get_http_parts(context)?;
+                for (name, value) in headers {
+                    builder = builder.header(name, value);
+                }
+                let body = match compression {
+                    Some(compression) if body.len() >= MIN_COMPRESS_SIZE => {
+                        builder = builder.header(CONTENT_ENCODING, compression.as_str());
+                        compression.compress(&body)?
+                    }
+                    _ => body,
+                };
+                if !body.is_empty() {
+                    builder = builder.header("content-length", body.len());
+                }
+                builder
+                    .method(path.get_method())
+                    .uri(uri)
+                    .body(Full::new(body).boxed())
+            }
         }?;
         Ok(request)
     }
@@ -112,42 +145,61 @@ impl RequestCodec<Bytes, hyper::Error> for RequestHandler {
         mut request: Request<BoxBody<Bytes, hyper::Error>>,
     ) -> Result<FusenContext, crate::Error> {
         let meta_data = MetaData::from(request.headers());
+        //从收到请求开始计算截止时间
+        let deadline = get_deadline(&meta_data);
+        let connection = request
+            .extensions_mut()
+            .remove::<ConnectionInfo>()
+            .unwrap_or_default();
+        let uri = request
+            .uri()
+            .path_and_query()
+            .map(|e| e.as_str().to_owned());
         let path = request.uri().path().to_string();
         let method = request.method().to_string().to_lowercase();
-        let mut temp_query_fields_ty: Vec<(String, String)> = vec![];
+        let mut query_fields: Vec<(String, String)> = vec![];
+        if let Some(query) = request.uri().query() {
+            for item in query.split('&').filter(|e| !e.is_empty()) {
+                let item = item.replace('+', " ");
+                let (key, value) = item.split_once('=').unwrap_or((&item, ""));
+                query_fields.push((decode_component(key), decode_component(value)));
+            }
+        }
+        let codec = meta_data.get_codec();
         let mut body = BytesMut::new();
-        if method.contains("get") {
-            let url = request.uri().to_string();
-            let url: Vec<&str> = url.split('?').collect();
-            if url.len() > 1 {
-                let params: Vec<&str> = url[1].split('&').collect();
-                for item in params {
-                    let item: Vec<&str> = item.split('=').collect();
-                    temp_query_fields_ty.push((item[0].to_owned(), item[1].to_owned()));
+        let mut bytes = BytesMut::new();
+        let mut frame_decoder = self.grpc_codec.frame_decoder();
+        let mut grpc_frames = vec![];
+        while let Some(Ok(frame)) = request.body_mut().frame().await {
+            if let Ok(data) = frame.into_data() {
+                match codec {
+                    fusen_common::codec::CodecType::JSON => bytes.extend(data),
+                    //边读取边解析gRPC消息,超出最大长度时立即返回
+                    fusen_common::codec::CodecType::GRPC => {
+                        grpc_frames.append(&mut frame_decoder.decode(&data)?)
+                    }
                 }
             }
-        } else {
-            let mut bytes = BytesMut::new();
-            while let Some(Ok(frame)) = request.body_mut().frame().await {
-                if frame.is_data() {
-                    bytes.extend(frame.into_data().unwrap());
+        }
+        match codec {
+            fusen_common::codec::CodecType::JSON => {
+                match CompressionEncoding::from_encoding(meta_data.get_value(CONTENT_ENCODING))? {
+                    Some(compression) => body
+                        .extend_from_slice(&compression.decompress(&bytes, MAX_DECOMPRESS_SIZE)?),
+                    None => body.extend_from_slice(&bytes),
                 }
             }
-            let bytes: Bytes = bytes.into();
-            match meta_data.get_codec() {
-                fusen_common::codec::CodecType::JSON => {
-                    body.extend_from_slice(&bytes);
-                }
-                fusen_common::codec::CodecType::GRPC => {
-                    let bytes = self
-                        .grpc_codec
-                        .decode(&bytes)
-                        .map_err(FusenError::from)?
-                        .get_body();
-                    body.extend_from_slice(&bytes);
-                }
+            fusen_common::codec::CodecType::GRPC => {
+                frame_decoder.finish()?;
+                let compression =
+                    CompressionEncoding::from_encoding(meta_data.get_value(GRPC_ENCODING))?;
+                let bytes = self
+                    .grpc_codec
+                    .decode_frames(grpc_frames, compression)?
+                    .get_body();
+                body.extend_from_slice(&bytes);
             }
-        };
+        }
         let unique_identifier = meta_data
             .get_value("unique_identifier")
             .map_or(get_trade_id(), |e| e.clone());
@@ -160,13 +212,25 @@ impl RequestCodec<Bytes, hyper::Error> for RequestHandler {
             class,
             method,
             fields,
+            alias,
         } = self
             .path_cache
             .seach(&mut path)
             .ok_or(FusenError::NotFind)?;
-        if let Some(mut fields) = fields {
-            temp_query_fields_ty.append(&mut fields);
-        }
+        let fields = fields.map(|fields| {
+            fields
+                .into_iter()
+                .map(|(key, value)| (key, decode_component(&value)))
+                .collect::<Vec<_>>()
+        });
+        let query_fields = (!query_fields.is_empty()).then_some(query_fields);
+        //没有请求体时参数全部来自path与query
+        let serialization = if body.is_empty() && (fields.is_some() || query_fields.is_some()) {
+            SerializationType::JSON
+        } else {
+            meta_data.get_serialization()
+        };
+        let http_binding = matches!(codec, fusen_common::codec::CodecType::JSON) && !alias;
         let context = FusenContext::new(
             unique_identifier,
             ContextInfo::default()
@@ -174,43 +238,243 @@ impl RequestCodec<Bytes, hyper::Error> for RequestHandler {
                 .method_name(method)
                 .path(path)
                 .version(version),
-            FusenRequest::new(
-                if temp_query_fields_ty.is_empty() {
-                    None
-                } else {
-                    Some(temp_query_fields_ty)
-                },
-                body.into(),
-            ),
+            FusenRequest::new(query_fields, body.into())
+                .path_fields(fields)
+                .serialization(serialization)
+                .http_binding(http_binding)
+                .uri(uri),
             meta_data,
-        );
+        )
+        .deadline(deadline)
+        .connection(connection)
+        .direction(Direction::Server);
         Ok(context)
     }
 }
 
-fn get_path(mut path: String, query_fields: Option<&Vec<(String, String)>>) -> String {
-    if path.contains('{') {
-        return get_rest_path(path, query_fields);
+//HTTP请求的路径、header中的参数与未压缩的请求体
+pub(crate) struct HttpParts {
+    pub uri: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Bytes,
+}
+
+//按照参数绑定拆分到path/query/header/body中
+pub(crate) fn get_http_parts(context: &FusenContext) -> Result<HttpParts, crate::Error> {
+    let request = context.get_request();
+    let mut path_fields = request.get_path_fields().clone();
+    let mut query_fields = request.get_query_fields().clone();
+    let mut header_fields = vec![];
+    let mut body = request.get_body().clone();
+    if let Some(bindings) = request.get_bindings() {
+        let (mut path_vec, mut query_vec) = (vec![], vec![]);
+        body = Bytes::new();
+        for ((binding, name), field) in bindings.iter().zip(request.get_body_fields()?) {
+            match binding {
+                FieldBinding::Path => {
+                    path_vec.push((name.clone(), String::from_utf8_lossy(&field).into()))
+                }
+                FieldBinding::Query => {
+                    query_vec.push((name.clone(), String::from_utf8_lossy(&field).into()))
+                }
+                FieldBinding::Header => header_fields.push((name.clone(), get_field_text(&field))),
+                FieldBinding::Body => body = field,
+            }
+        }
+        let _ = path_fields.insert(path_vec);
+        let _ = query_fields.insert(query_vec);
     }
-    if let Some(query_fields) = query_fields {
-        path.push('?');
-        for item in query_fields {
-            path.push_str(&item.0);
-            path.push('=');
-            path.push_str(&item.1);
-            path.push('&');
+    let uri = get_path(
+        context.get_context_info().get_path().get_path(),
+        path_fields.as_ref(),
+        query_fields.as_ref(),
+    );
+    Ok(HttpParts {
+        uri,
+        headers: header_fields,
+        body,
+    })
+}
+
+//参数值为JSON文本,字符串去掉引号,数组展开为重复的key,结构体展开为各个字段
+fn get_path(
+    mut path: String,
+    path_fields: Option<&Vec<(String, String)>>,
+    query_fields: Option<&Vec<(String, String)>>,
+) -> String {
+    let mut query = vec![];
+    //未声明参数绑定时,路径中的参数同样从query_fields中获取
+    for item in path_fields.into_iter().chain(query_fields).flatten() {
+        let value = serde_json::from_str(&item.1).unwrap_or(Value::String(item.1.clone()));
+        let text = get_value_text(&value).unwrap_or_default();
+        if let Some(new_path) = replace_path_field(&path, &item.0, &text) {
+            path = new_path;
+            continue;
+        }
+        match value {
+            Value::Array(items) => push_query(&mut query, &item.0, &items),
+            Value::Object(fields) => {
+                for (key, value) in fields {
+                    match value {
+                        Value::Array(items) => push_query(&mut query, &key, &items),
+                        value => push_query(&mut query, &key, &[value]),
+                    }
+                }
+            }
+            value => push_query(&mut query, &item.0, &[value]),
         }
-        path.remove(path.len() - 1);
+    }
+    if !query.is_empty() {
+        path.push('?');
+        path.push_str(&query.join("&"));
     }
     path
 }
 
-fn get_rest_path(mut path: String, query_fields: Option<&Vec<(String, String)>>) -> String {
-    if let Some(query_fields) = query_fields {
-        for item in query_fields {
-            let temp = format!("{{{}}}", item.0);
-            path = path.replace(&temp, &item.1.replace('\"', ""));
+//替换路径中的 {name},{name:regex} 与 {*name} 参数段
+fn replace_path_field(path: &str, name: &str, value: &str) -> Option<String> {
+    let mut found = false;
+    let segments: Vec<String> = path
+        .split('/')
+        .map(|segment| {
+            let inner = segment.strip_prefix('{').and_then(|e| e.strip_suffix('}'));
+            match inner {
+                Some(inner) if !found && inner.split(':').next() == Some(name) => {
+                    found = true;
+                    utf8_percent_encode(value, COMPONENT).to_string()
+                }
+                Some(inner) if !found && inner.strip_prefix('*') == Some(name) => {
+                    found = true;
+                    value
+                        .split('/')
+                        .map(|e| utf8_percent_encode(e, COMPONENT).to_string())
+                        .collect::<Vec<_>>()
+                        .join("/")
+                }
+                _ => segment.to_owned(),
+            }
+        })
+        .collect();
+    found.then(|| segments.join("/"))
+}
+
+fn push_query(query: &mut Vec<String>, key: &str, values: &[Value]) {
+    for value in values {
+        if let Some(value) = get_value_text(value) {
+            query.push(format!(
+                "{}={}",
+                utf8_percent_encode(key, COMPONENT),
+                utf8_percent_encode(&value, COMPONENT)
+            ));
         }
     }
-    path
+}
+
+//值为null时不传递该参数
+fn get_value_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(value) => Some(value.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+fn decode_component(value: &str) -> String {
+    percent_decode_str(value).decode_utf8_lossy().to_string()
+}
+
+//JSON字符串去掉引号,其余类型保持JSON文本
+fn get_field_text(field: &Bytes) -> String {
+    serde_json::from_slice::<String>(field)
+        .unwrap_or_else(|_| String::from_utf8_lossy(field).to_string())
+}
+
+#[test]
+fn test() {
+    let request = FusenRequest::new_for_client_binding(
+        vec![
+            (FieldBinding::Path, "id".to_owned()),
+            (FieldBinding::Query, "name".to_owned()),
+            (FieldBinding::Header, "x-tenant-id".to_owned()),
+            (FieldBinding::Body, "user".to_owned()),
+        ],
+        vec![
+            Bytes::from("7"),
+            Bytes::from("\"bob\""),
+            Bytes::from("\"t1\""),
+            Bytes::from("{\"str\":\"fusen\"}"),
+        ],
+        SerializationType::JSON,
+    );
+    let context = FusenContext::new(
+        String::new(),
+        ContextInfo::default().path(Path::PATCH("/user/{id}".to_owned())),
+        request,
+        MetaData::new(),
+    );
+    let request = RequestHandler::new(Default::default())
+        .encode(&context)
+        .unwrap();
+    assert_eq!(request.method(), "PATCH");
+    assert_eq!(request.uri(), "/user/7?name=bob");
+    assert_eq!(request.headers()["x-tenant-id"], "t1");
+    assert_eq!(request.headers()["content-length"], "15");
+    let query_fields = [
+        ("tag", "\"a/b\""),
+        ("keyword", "\"a b&中\""),
+        ("ids", "[1,2]"),
+        ("query", "{\"name\":\"x\",\"tags\":[\"y\"]}"),
+        ("none", "null"),
+    ]
+    .map(|(key, value)| (key.to_owned(), value.to_owned()))
+    .to_vec();
+    assert_eq!(
+        get_path("/search/{tag}".to_owned(), None, Some(&query_fields)),
+        "/search/a%2Fb?keyword=a%20b%26%E4%B8%AD&ids=1&ids=2&name=x&tags=y"
+    );
+    let path_fields = [("id", "12"), ("rest", "\"a b/c.txt\"")]
+        .map(|(key, value)| (key.to_owned(), value.to_owned()))
+        .to_vec();
+    assert_eq!(
+        get_path(
+            "/user/{id:[0-9]+}/{*rest}".to_owned(),
+            Some(&path_fields),
+            None
+        ),
+        "/user/12/a%20b/c.txt"
+    );
+    let mut meta_data = MetaData::new();
+    meta_data.insert("x-tenant-id".to_owned(), "t1".to_owned());
+    let request = FusenRequest::new(
+        Some(vec![
+            ("name".to_owned(), "bob".to_owned()),
+            ("ids".to_owned(), "1".to_owned()),
+            ("ids".to_owned(), "2".to_owned()),
+        ]),
+        Bytes::from("{\"str\":\"fusen\"}"),
+    )
+    .path_fields(Some(vec![("id".to_owned(), "7".to_owned())]))
+    .http_binding(true);
+    let context = FusenContext::new(String::new(), Default::default(), request, meta_data);
+    let fields = context
+        .get_binding_fields(vec![
+            (FieldBinding::Path, "id"),
+            (FieldBinding::Query, "name"),
+            (FieldBinding::Header, "x-tenant-id"),
+            (FieldBinding::Body, "user"),
+            (FieldBinding::Query, "ids"),
+            (FieldBinding::Query, "age"),
+        ])
+        .unwrap();
+    assert_eq!(fields[0].deserialize::<i64>().unwrap(), 7);
+    assert_eq!(fields[1].deserialize::<String>().unwrap(), "bob");
+    assert_eq!(fields[2].deserialize::<String>().unwrap(), "t1");
+    assert_eq!(
+        fields[3].deserialize::<serde_json::Value>().unwrap()["str"],
+        "fusen"
+    );
+    assert_eq!(fields[4].deserialize::<Vec<i64>>().unwrap(), vec![1, 2]);
+    assert_eq!(fields[5].deserialize::<Option<u8>>().unwrap(), None);
+    let err = fields[1].deserialize::<i64>().unwrap_err();
+    assert_eq!(err.code().http_status(), 400);
 }
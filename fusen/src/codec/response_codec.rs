@@ -1,10 +1,23 @@
 use super::{grpc_codec::GrpcBodyCodec, BodyCodec};
-use crate::support::triple::TripleResponseWrapper;
+use crate::context::CONSUMER_APPLICATION;
+use crate::support::{
+    compression::{
+        CompressionEncoding, ACCEPT_ENCODING, CONTENT_ENCODING, GRPC_ACCEPT_ENCODING,
+        GRPC_ENCODING, MAX_DECOMPRESS_SIZE, MIN_COMPRESS_SIZE, SUPPORT_ENCODINGS,
+    },
+    grpc::{decode_status_details, encode_status_details},
+    triple::{TripleExceptionWrapper, TripleResponseWrapper, TRI_EXCEPTION_CODE},
+};
 use bytes::{Bytes, BytesMut};
-use fusen_common::{codec::CodecType, error::FusenError, FusenContext};
+use fusen_common::{
+    codec::{CodecType, SerializationFormat, SerializationType},
+    error::{FusenError, FusenStatus, StatusCode},
+    FusenContext,
+};
 use http::{HeaderMap, HeaderValue, Response};
 use http_body::Frame;
 use http_body_util::{combinators::BoxBody, BodyExt};
+use percent_encoding::{percent_decode, utf8_percent_encode, AsciiSet, CONTROLS};
 use std::convert::Infallible;
 
 pub(crate) trait ResponseCodec<T, E> {
@@ -14,22 +27,19 @@ pub(crate) trait ResponseCodec<T, E> {
 }
 
 pub struct ResponseHandler {
-    grpc_codec: Box<
-        (dyn BodyCodec<
-            bytes::Bytes,
-            DecodeType = TripleResponseWrapper,
-            EncodeType = TripleResponseWrapper,
-        > + Sync
-             + Send),
-    >,
+    grpc_codec: GrpcBodyCodec<bytes::Bytes, TripleResponseWrapper, TripleResponseWrapper>,
+    exception_codec: GrpcBodyCodec<bytes::Bytes, TripleExceptionWrapper, TripleExceptionWrapper>,
 }
 
 impl ResponseHandler {
     pub fn new() -> Self {
         let grpc_codec =
             GrpcBodyCodec::<bytes::Bytes, TripleResponseWrapper, TripleResponseWrapper>::new();
+        let exception_codec =
+            GrpcBodyCodec::<bytes::Bytes, TripleExceptionWrapper, TripleExceptionWrapper>::new();
         ResponseHandler {
-            grpc_codec: Box::new(grpc_codec),
+            grpc_codec,
+            exception_codec,
         }
     }
 }
@@ -46,65 +56,88 @@ impl ResponseCodec<Bytes, hyper::Error> for ResponseHandler {
         context: FusenContext,
     ) -> Result<Response<BoxBody<Bytes, Infallible>>, crate::Error> {
         let meta_data = context.get_meta_data();
+        let serialization = meta_data.get_accept_serialization();
         let content_type = match meta_data.get_codec() {
-            fusen_common::codec::CodecType::JSON => "application/json",
+            fusen_common::codec::CodecType::JSON => serialization.content_type(),
             fusen_common::codec::CodecType::GRPC => "application/grpc",
         };
+        //根据客户端声明的accept-encoding协商响应压缩方式
+        let compression = match meta_data.get_codec() {
+            fusen_common::codec::CodecType::JSON => meta_data.get_value(ACCEPT_ENCODING),
+            fusen_common::codec::CodecType::GRPC => meta_data.get_value(GRPC_ACCEPT_ENCODING),
+        }
+        .and_then(|e| CompressionEncoding::from_accept_encoding(e));
+        //Dubbo3 Triple消费端(携带tri-consumer-appname)的异常通过TripleExceptionWrapper返回,
+        //消费端也可以通过tri-exception-code显式声明,原生gRPC客户端使用grpc-status
+        let exception_wrapper = match meta_data.get_value(TRI_EXCEPTION_CODE) {
+            Some(code) => code != "0",
+            None => meta_data.get_value(CONSUMER_APPLICATION).is_some(),
+        };
         let mut builder = Response::builder().header("content-type", content_type);
         for (key, value) in context.get_response().get_headers() {
             builder = builder.header(key, value);
         }
         let body = match meta_data.get_codec() {
             fusen_common::codec::CodecType::JSON => {
-                vec![match context.into_response().into_response() {
-                    Ok(res) => Frame::data(res),
+                let data = match context.into_response().into_response() {
+                    Ok(res) => res,
                     Err(err) => {
                         if let FusenError::Null = err {
-                            Frame::data(bytes::Bytes::from("null"))
+                            match serialization {
+                                SerializationType::JSON => bytes::Bytes::from("null"),
+                                _ => bytes::Bytes::new(),
+                            }
                         } else {
-                            return Err(crate::Error::from(err.to_string()));
+                            return Err(err.into());
                         }
                     }
-                }]
+                };
+                vec![Frame::data(match compression {
+                    Some(compression) if data.len() >= MIN_COMPRESS_SIZE => {
+                        builder = builder.header(CONTENT_ENCODING, compression.as_str());
+                        compression.compress(&data)?
+                    }
+                    _ => data,
+                })]
             }
             fusen_common::codec::CodecType::GRPC => {
-                let mut status = "0";
-                let mut message = String::from("success");
                 let mut trailers = HeaderMap::new();
                 let mut vec = vec![];
+                builder = builder.header(GRPC_ACCEPT_ENCODING, SUPPORT_ENCODINGS);
+                if let Some(compression) = compression {
+                    builder = builder.header(GRPC_ENCODING, compression.as_str());
+                }
                 match context.into_response().into_response() {
                     Ok(data) => {
                         let res_wrapper = TripleResponseWrapper::form(data.into());
                         let buf = self
                             .grpc_codec
-                            .encode(&res_wrapper)
+                            .encode(&res_wrapper, compression)
                             .map_err(FusenError::from)?;
                         vec.push(Frame::data(buf));
+                        trailers.insert("grpc-status", HeaderValue::from_static("0"));
                     }
-                    Err(err) => {
-                        message = match err {
-                            FusenError::Null => {
-                                let res_wrapper = TripleResponseWrapper::form(b"null".to_vec());
-                                let buf = self
-                                    .grpc_codec
-                                    .encode(&res_wrapper)
-                                    .map_err(FusenError::from)?;
-                                vec.push(Frame::data(buf));
-                                "null value".to_owned()
-                            }
-                            FusenError::NotFind => {
-                                status = "91";
-                                "not find".to_owned()
-                            }
-                            FusenError::Info(msg) => {
-                                status = "92";
-                                msg.clone()
-                            }
-                        }
+                    //空返回值按照null正常返回
+                    Err(FusenError::Null) => {
+                        let res_wrapper = TripleResponseWrapper::form(b"null".to_vec());
+                        let buf = self
+                            .grpc_codec
+                            .encode(&res_wrapper, compression)
+                            .map_err(FusenError::from)?;
+                        vec.push(Frame::data(buf));
+                        trailers.insert("grpc-status", HeaderValue::from_static("0"));
                     }
+                    Err(err) if exception_wrapper => {
+                        let buf = self
+                            .exception_codec
+                            .encode(&TripleExceptionWrapper::from_error(&err), compression)
+                            .map_err(FusenError::from)?;
+                        vec.push(Frame::data(buf));
+                        trailers.insert("grpc-status", HeaderValue::from_static("0"));
+                        trailers.insert(TRI_EXCEPTION_CODE, HeaderValue::from_static("1"));
+                    }
+                    Err(err) => trailers = get_grpc_status(err),
                 }
-                trailers.insert("grpc-status", HeaderValue::from_str(status).unwrap());
-                trailers.insert("grpc-message", HeaderValue::from_str(&message).unwrap());
                 vec.push(Frame::trailers(trailers));
                 vec
             }
@@ -126,63 +159,192 @@ impl ResponseCodec<Bytes, hyper::Error> for ResponseHandler {
         &self,
         mut response: Response<BoxBody<Bytes, hyper::Error>>,
     ) -> Result<Bytes, FusenError> {
+        let codec_type = response
+            .headers()
+            .iter()
+            .find(|e| e.0.as_str().to_lowercase() == "content-type")
+            .map(|e| e.1)
+            .map_or(CodecType::JSON, |e| match e.to_str() {
+                Ok(coder) => CodecType::from(coder),
+                Err(_) => CodecType::JSON,
+            });
+        //Trailers-Only响应的grpc-status在header中
+        if let CodecType::GRPC = codec_type {
+            check_grpc_status(response.headers())?;
+        }
         let mut bytes = BytesMut::new();
+        let mut frame_decoder = self.grpc_codec.frame_decoder();
+        let mut grpc_frames = vec![];
+        let mut is_exception = is_exception_wrapper(response.headers());
         while let Some(Ok(frame)) = response.frame().await {
             if frame.is_trailers() {
                 let trailers = frame
                     .trailers_ref()
                     .ok_or(FusenError::from("error trailers N1"))?;
-                match trailers.get("grpc-status") {
-                    Some(status) => match status.as_bytes() {
-                        b"0" => {
-                            break;
-                        }
-                        else_status => {
-                            let msg = match trailers.get("grpc-message") {
-                                Some(value) => {
-                                    String::from_utf8(value.as_bytes().to_vec()).unwrap()
-                                }
-                                None => {
-                                    "grpc-status=".to_owned()
-                                        + &String::from_utf8(else_status.to_vec()).unwrap()
-                                }
-                            };
-                            match else_status {
-                                b"90" => return Err(FusenError::Null),
-                                b"91" => return Err(FusenError::NotFind),
-                                _ => return Err(FusenError::from(msg)),
-                            };
-                        }
-                    },
-                    None => return Err(FusenError::from("error trailers N2")),
+                if !check_grpc_status(trailers)? {
+                    return Err(FusenError::from("error trailers N2"));
                 }
+                is_exception |= is_exception_wrapper(trailers);
+                break;
+            }
+            let data = frame.into_data().unwrap();
+            match codec_type {
+                CodecType::JSON => bytes.extend(data),
+                CodecType::GRPC => grpc_frames.append(&mut frame_decoder.decode(&data)?),
             }
-            bytes.extend(frame.into_data().unwrap());
         }
         if !response.status().is_success() {
+            //服务端返回的错误体为JSON格式的FusenStatus
+            if let Ok(status) = serde_json::from_slice::<FusenStatus>(&bytes) {
+                return Err(FusenError::Status(status));
+            }
             let mut err_info = format!("errcode : {}", response.status().as_str());
             if !bytes.is_empty() {
                 err_info.push_str(&format!(" ,message : {:?}", bytes));
             }
-            return Err(FusenError::from(err_info));
+            return Err(FusenError::status(
+                StatusCode::from_http_status(response.status().as_u16()),
+                err_info,
+            ));
         }
-        let codec_type = response
-            .headers()
-            .iter()
-            .find(|e| e.0.as_str().to_lowercase() == "content-type")
-            .map(|e| e.1)
-            .map_or(CodecType::JSON, |e| match e.to_str() {
-                Ok(coder) => CodecType::from(coder),
-                Err(_) => CodecType::JSON,
-            });
-        let bytes: Bytes = bytes.into();
         let res = match codec_type {
-            CodecType::JSON => bytes,
+            CodecType::JSON => match CompressionEncoding::from_encoding(
+                get_header(response.headers(), CONTENT_ENCODING).as_ref(),
+            )? {
+                Some(compression) => compression
+                    .decompress(&bytes, MAX_DECOMPRESS_SIZE)
+                    .map_err(|e| FusenError::from(e.to_string()))?,
+                None => bytes.into(),
+            },
             CodecType::GRPC => {
-                let response = self.grpc_codec.decode(&bytes)?;
+                frame_decoder.finish()?;
+                let compression = CompressionEncoding::from_encoding(
+                    get_header(response.headers(), GRPC_ENCODING).as_ref(),
+                )?;
+                //Java服务端抛出的异常
+                if is_exception {
+                    let wrapper = self
+                        .exception_codec
+                        .decode_frames(grpc_frames, compression)?;
+                    return Err(wrapper.to_java_exception().into());
+                }
+                let response = self.grpc_codec.decode_frames(grpc_frames, compression)?;
                 Bytes::copy_from_slice(&response.data)
             }
         };
         Ok(res)
     }
 }
+
+//grpc-message需要对非可见字符进行百分号编码
+const GRPC_MESSAGE_ENCODE_SET: &AsciiSet = &CONTROLS.add(b'%');
+
+pub(crate) fn get_grpc_status(err: FusenError) -> HeaderMap {
+    let status = err.into_status();
+    let mut headers = HeaderMap::new();
+    headers.insert("grpc-status", HeaderValue::from(status.get_code().as_i32()));
+    let message = utf8_percent_encode(status.get_message(), GRPC_MESSAGE_ENCODE_SET).to_string();
+    if let Ok(message) = HeaderValue::from_str(&message) {
+        headers.insert("grpc-message", message);
+    }
+    if let Ok(details) = HeaderValue::from_str(&encode_status_details(status)) {
+        headers.insert("grpc-status-details-bin", details);
+    }
+    headers
+}
+
+//返回是否包含grpc-status,非0时转换为对应的错误
+fn check_grpc_status(headers: &HeaderMap) -> Result<bool, FusenError> {
+    let status = match headers.get("grpc-status") {
+        Some(status) => status.as_bytes(),
+        None => return Ok(false),
+    };
+    if status == b"0" {
+        return Ok(true);
+    }
+    //兼容旧版本fusen服务端的自定义状态码
+    match status {
+        b"90" => return Err(FusenError::Null),
+        b"91" => return Err(FusenError::NotFind),
+        _ => (),
+    }
+    if let Some(status) = headers
+        .get("grpc-status-details-bin")
+        .and_then(|e| decode_status_details(e.as_bytes()))
+    {
+        return Err(FusenError::Status(status));
+    }
+    let code = std::str::from_utf8(status)
+        .ok()
+        .and_then(|e| e.parse::<i32>().ok())
+        .map_or(StatusCode::Unknown, StatusCode::from_i32);
+    let msg = match headers.get("grpc-message") {
+        Some(value) => percent_decode(value.as_bytes())
+            .decode_utf8_lossy()
+            .to_string(),
+        None => "grpc-status=".to_owned() + &String::from_utf8_lossy(status),
+    };
+    match status {
+        b"92" => Err(FusenError::Info(msg)),
+        _ => Err(FusenError::status(code, msg)),
+    }
+}
+
+fn is_exception_wrapper(headers: &HeaderMap) -> bool {
+    headers
+        .get(TRI_EXCEPTION_CODE)
+        .is_some_and(|e| e.as_bytes() != b"0")
+}
+
+fn get_header(headers: &HeaderMap, key: &str) -> Option<String> {
+    headers
+        .get(key)
+        .and_then(|e| e.to_str().ok())
+        .map(|e| e.to_owned())
+}
+
+#[test]
+fn test() {
+    use fusen_common::{ContextInfo, FusenRequest, MetaData};
+
+    let encode = |headers: &[(&str, &str)]| {
+        let mut meta_data = MetaData::new();
+        meta_data.insert("content-type".to_owned(), "application/grpc".to_owned());
+        for (key, value) in headers {
+            meta_data.insert(key.to_string(), value.to_string());
+        }
+        let mut context = FusenContext::new(
+            String::new(),
+            ContextInfo::default(),
+            FusenRequest::new(None, Bytes::new()),
+            meta_data,
+        );
+        context
+            .get_mut_response()
+            .set_response(Err(FusenError::from("divide by zero")));
+        let response = ResponseHandler::new().encode(context).unwrap();
+        let trailers = futures::executor::block_on(response.into_body().collect())
+            .unwrap()
+            .trailers()
+            .cloned()
+            .unwrap();
+        (
+            trailers["grpc-status"].clone(),
+            trailers.get(TRI_EXCEPTION_CODE).cloned(),
+        )
+    };
+    //Java消费端不会发送tri-exception-code
+    let (status, code) = encode(&[(CONSUMER_APPLICATION, "dubbo-consumer")]);
+    assert_eq!(status, "0");
+    assert_eq!(code.unwrap(), "1");
+    let (status, code) = encode(&[
+        (CONSUMER_APPLICATION, "dubbo-consumer"),
+        (TRI_EXCEPTION_CODE, "0"),
+    ]);
+    assert_ne!(status, "0");
+    assert!(code.is_none());
+    //原生gRPC客户端
+    let (status, code) = encode(&[]);
+    assert_ne!(status, "0");
+    assert!(code.is_none());
+}
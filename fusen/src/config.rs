@@ -1,12 +1,24 @@
+use fusen_common::openapi::OpenApiConfig;
 use fusen_procedural_macro::Data;
 use serde::{Deserialize, Serialize};
 
-use crate::handler::HandlerInfo;
+use crate::{
+    client::ClientInfo,
+    handler::{auth::AuthConfig, HandlerInfo},
+    support::{cors::CorsConfig, tls::TlsConfig},
+};
 
 #[derive(Serialize, Deserialize, Default, Data)]
 pub struct FusenApplicationConfig {
     application_name: String,
     port: Option<u16>,
+    http3: Option<bool>,
+    tls: Option<TlsConfig>,
     register: Option<String>,
     handler_infos: Option<Vec<HandlerInfo>>,
+    client_infos: Option<Vec<ClientInfo>>,
+    pass_through_headers: Option<Vec<String>>,
+    auth: Option<AuthConfig>,
+    cors: Option<Vec<CorsConfig>>,
+    openapi: Option<OpenApiConfig>,
 }
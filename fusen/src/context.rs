@@ -0,0 +1,144 @@
+use fusen_common::{ConnectionInfo, FusenContext};
+use fusen_procedural_macro::Data;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+use tracing::debug;
+
+pub const UNIQUE_IDENTIFIER: &str = "unique_identifier";
+//与Dubbo3保持一致,标识调用方的应用名
+pub const CONSUMER_APPLICATION: &str = "tri-consumer-appname";
+const DUBBO_APPLICATION: &str = "remote.application";
+const TRACEPARENT: &str = "traceparent";
+//链路追踪信息总是传递给下游服务
+const TRACE_HEADERS: [&str; 3] = [TRACEPARENT, "tracestate", "baggage"];
+
+tokio::task_local! {
+    static REQUEST_CONTEXT: RefCell<RequestContext>;
+}
+
+//服务端处理请求期间的上下文,在处理过程中发起的调用会自动携带attachments
+#[derive(Debug, Clone, Default, Data)]
+pub struct RequestContext {
+    unique_identifier: String,
+    caller: Option<String>,
+    trace_id: Option<String>,
+    principal: Option<String>,
+    attachments: HashMap<String, String>,
+    headers: HashMap<String, String>,
+    connection: ConnectionInfo,
+    //clone后共享同一份响应头,处理完成后写入响应
+    response_headers: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl RequestContext {
+    //只有在白名单中的请求头会作为attachments向下游传递,支持以*结尾的前缀匹配
+    pub fn from_context(context: &FusenContext, pass_through_headers: &[String]) -> Self {
+        let meta_data = context.get_meta_data();
+        let attachments = meta_data
+            .get_iter()
+            .filter(|(key, _)| {
+                TRACE_HEADERS.contains(&key.as_str()) || is_pass_through(pass_through_headers, key)
+            })
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        RequestContext {
+            unique_identifier: context.get_unique_identifier().clone(),
+            caller: meta_data
+                .get_value(CONSUMER_APPLICATION)
+                .or(meta_data.get_value(DUBBO_APPLICATION))
+                .cloned(),
+            trace_id: meta_data
+                .get_value(TRACEPARENT)
+                .and_then(|e| e.split('-').nth(1))
+                .map(|e| e.to_owned()),
+            principal: context.get_principal().clone(),
+            attachments,
+            headers: meta_data
+                .get_iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            connection: context.get_connection().clone(),
+            response_headers: Default::default(),
+        }
+    }
+
+    pub fn current() -> Option<RequestContext> {
+        REQUEST_CONTEXT.try_with(|e| e.borrow().clone()).ok()
+    }
+
+    //不在请求处理过程中时(如直接调用服务实现)返回新的上下文,其中设置的响应头不会写回响应
+    pub fn current_or_default() -> RequestContext {
+        Self::current().unwrap_or_else(|| {
+            debug!("request context is not set, response headers will be dropped");
+            RequestContext::default()
+        })
+    }
+
+    //修改当前请求的attachments,不在请求处理过程中时返回false
+    pub fn put_attachment(key: &str, value: String) -> bool {
+        REQUEST_CONTEXT
+            .try_with(|e| {
+                e.borrow_mut().attachments.insert(key.to_lowercase(), value);
+            })
+            .is_ok()
+    }
+
+    pub fn get_attachment(&self, key: &str) -> Option<&String> {
+        self.attachments.get(&key.to_lowercase())
+    }
+
+    pub fn get_header(&self, key: &str) -> Option<&String> {
+        self.headers.get(&key.to_lowercase())
+    }
+
+    pub fn set_response_header(&self, key: &str, value: String) {
+        if let Ok(mut headers) = self.response_headers.lock() {
+            headers.insert(key.to_lowercase(), value);
+        }
+    }
+
+    pub fn take_response_headers(&self) -> HashMap<String, String> {
+        self.response_headers
+            .lock()
+            .map(|mut e| std::mem::take(&mut *e))
+            .unwrap_or_default()
+    }
+
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        REQUEST_CONTEXT.scope(RefCell::new(self), future).await
+    }
+}
+
+fn is_pass_through(pass_through_headers: &[String], key: &str) -> bool {
+    pass_through_headers
+        .iter()
+        .any(|e| match e.strip_suffix('*') {
+            Some(prefix) => key.starts_with(&prefix.to_lowercase()),
+            None => e.eq_ignore_ascii_case(key),
+        })
+}
+
+#[test]
+fn test() {
+    let pass_through_headers = vec!["X-Tenant-Id".to_owned(), "x-biz-*".to_owned()];
+    assert!(is_pass_through(&pass_through_headers, "x-tenant-id"));
+    assert!(is_pass_through(&pass_through_headers, "x-biz-user"));
+    assert!(!is_pass_through(&pass_through_headers, "authorization"));
+    let context = RequestContext::default();
+    context
+        .clone()
+        .set_response_header("X-Request-Id", "1".to_owned());
+    assert_eq!(
+        context.take_response_headers().get("x-request-id"),
+        Some(&"1".to_owned())
+    );
+    //没有当前请求时的上下文不共享响应头
+    RequestContext::current_or_default().set_response_header("x-request-id", "2".to_owned());
+    assert!(RequestContext::current_or_default()
+        .take_response_headers()
+        .is_empty());
+}
@@ -1,48 +1,72 @@
 use super::FusenFilter;
+use crate::{
+    context::RequestContext,
+    support::{cors::CorsConfig, deadline},
+};
 use fusen_common::{
     error::FusenError,
     server::RpcServer,
     trie::{QueryResult, Trie},
     FusenContext, FusenFuture, Path,
 };
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 #[derive(Clone, Default)]
 pub struct RpcServerFilter {
     cache: HashMap<String, &'static dyn RpcServer>,
     path_cache: Arc<PathCache>,
+    pass_through_headers: Arc<Vec<String>>,
 }
 
 impl RpcServerFilter {
-    pub fn new(cache: HashMap<String, &'static dyn RpcServer>) -> Self {
+    pub fn new(
+        cache: HashMap<String, &'static dyn RpcServer>,
+        pass_through_headers: Vec<String>,
+        cors: Vec<CorsConfig>,
+    ) -> Result<Self, crate::Error> {
         let mut hash_cache = HashMap::new();
+        let mut alias_cache = HashMap::new();
         let mut rest_trie = Trie::default();
+        let mut generic_ids = HashSet::new();
         for item in &cache {
             let info = item.1.get_info();
+            if item.1.is_generic() {
+                generic_ids.insert(info.get_id().to_string());
+            }
             for method in info.get_methods() {
-                let path = method.get_path().clone();
-                let name = method.get_name().clone();
-                let method = method.get_method().clone();
-                if path.contains('{') {
-                    rest_trie.insert(path.clone());
+                let name = method.get_name();
+                let target = (info.get_id().to_string(), name.clone());
+                let path = Path::new(&method.get_method(), method.get_path());
+                match hash_cache.get(&path.get_key()) {
+                    //同一服务的不同版本
+                    Some(old) if old == &target => continue,
+                    _ => rest_trie.insert(path.get_method(), path.get_path())?,
                 }
-                hash_cache.insert(
-                    Path::new(&method, path).get_key(),
-                    (info.get_id().to_string(), name.clone()),
-                );
-                hash_cache.insert(
-                    Path::new(&method, format!("/{}/{}", info.get_id(), name.clone())).get_key(),
-                    (info.get_id().to_string(), name),
+                hash_cache.insert(path.get_key(), target.clone());
+                //泛化调用与Dubbo客户端使用 POST /{id}/{method},与声明的请求方法无关
+                alias_cache.insert(
+                    Path::POST(format!("/{}/{}", info.get_id(), name)).get_key(),
+                    target,
                 );
             }
         }
-        RpcServerFilter {
+        for cors in &cors {
+            cors.check()?;
+        }
+        Ok(RpcServerFilter {
             cache,
             path_cache: Arc::new(PathCache {
                 path_cache: hash_cache,
+                alias_cache,
                 rest_trie,
+                generic_ids,
+                cors: cors.into_iter().map(|e| (e.get_id().clone(), e)).collect(),
             }),
-        }
+            pass_through_headers: Arc::new(pass_through_headers),
+        })
     }
     pub fn get_path_cache(&self) -> Arc<PathCache> {
         self.path_cache.clone()
@@ -63,7 +87,21 @@ impl FusenFilter for RpcServerFilter {
     fn call(&self, mut context: FusenContext) -> FusenFuture<Result<FusenContext, crate::Error>> {
         let server = self.get_server(&mut context);
         match server {
-            Some(server) => Box::pin(async move { Ok(server.invoke(context).await) }),
+            Some(server) => {
+                let request_context =
+                    RequestContext::from_context(&context, &self.pass_through_headers);
+                Box::pin(async move {
+                    //切面中可能切换了任务,重新设置截止时间
+                    let deadline = *context.get_deadline();
+                    let future = deadline::scope(deadline, server.invoke(context));
+                    let mut context = request_context.clone().scope(future).await?;
+                    context
+                        .get_mut_response()
+                        .get_mut_headers()
+                        .extend(request_context.take_response_headers());
+                    Ok(context)
+                })
+            }
             None => Box::pin(async move {
                 context
                     .get_mut_response()
@@ -77,13 +115,21 @@ impl FusenFilter for RpcServerFilter {
 #[derive(Debug, Default)]
 pub struct PathCache {
     path_cache: HashMap<String, (String, String)>,
+    //POST /{id}/{method} 形式的默认路径
+    alias_cache: HashMap<String, (String, String)>,
     rest_trie: Trie,
+    generic_ids: HashSet<String>,
+    cors: HashMap<String, CorsConfig>,
 }
 
+const METHODS: [&str; 7] = ["GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS"];
+
 pub struct PathCacheResult {
     pub class: String,
     pub method: String,
     pub fields: Option<Vec<(String, String)>>,
+    //通过默认路径访问,参数按顺序放在body中
+    pub alias: bool,
 }
 
 impl PathCache {
@@ -93,8 +139,12 @@ impl PathCache {
                 class: data.0.clone(),
                 method: data.1.clone(),
                 fields: None,
+                alias: false,
             })
-        } else if let Some(rest_data) = self.rest_trie.search(&mut_path.get_path()) {
+        } else if let Some(rest_data) = self
+            .rest_trie
+            .search(mut_path.get_method(), &mut_path.get_path())
+        {
             let QueryResult { path, query_fields } = rest_data;
             mut_path.update_path(path);
             self.path_cache
@@ -103,9 +153,54 @@ impl PathCache {
                     class: data.0.clone(),
                     method: data.1.clone(),
                     fields: query_fields,
+                    alias: false,
                 })
+        } else if let Some(data) = self.alias_cache.get(&mut_path.get_key()) {
+            //声明的路径优先于默认路径
+            Some(PathCacheResult {
+                class: data.0.clone(),
+                method: data.1.clone(),
+                fields: None,
+                alias: true,
+            })
         } else {
-            None
+            //泛化服务接收 /{id}/{method} 形式的任意方法,POST请求与默认路径一致
+            let alias = matches!(mut_path, Path::POST(_));
+            let path = mut_path.get_path();
+            let (class, method) = path.strip_prefix('/')?.rsplit_once('/')?;
+            self.generic_ids.contains(class).then(|| PathCacheResult {
+                class: class.to_owned(),
+                method: method.to_owned(),
+                fields: None,
+                alias,
+            })
+        }
+    }
+
+    //路径对应的服务id及支持的请求方法,路径不存在时返回None
+    pub fn get_allow(&self, path: &str) -> Option<(String, Vec<&'static str>)> {
+        let mut class = None;
+        let mut methods = vec![];
+        for method in METHODS {
+            if let Some(result) = self.seach(&mut Path::new(method, path.to_owned())) {
+                class.get_or_insert(result.class);
+                methods.push(method);
+            }
         }
+        class.map(|class| (class, methods))
+    }
+
+    //请求方法与路径对应的服务id
+    pub fn get_class(&self, method: &str, path: &str) -> Option<String> {
+        self.seach(&mut Path::new(method, path.to_owned()))
+            .map(|e| e.class)
+    }
+
+    pub fn has_cors(&self) -> bool {
+        !self.cors.is_empty()
+    }
+
+    pub fn get_cors(&self, class: &str) -> Option<&CorsConfig> {
+        self.cors.get(class)
     }
 }
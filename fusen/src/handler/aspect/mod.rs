@@ -1,8 +1,11 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
 
 use super::HandlerContext;
+use crate::codec::dubbo_codec::DubboCodec;
 use crate::codec::request_codec::RequestCodec;
 use crate::codec::response_codec::ResponseCodec;
+use crate::protocol::socket::Socket;
 use crate::register::ResourceInfo;
 use crate::route::client::Route;
 use crate::{
@@ -12,7 +15,6 @@ use crate::{
 };
 use fusen_common::error::FusenError;
 use fusen_common::FusenContext;
-use http_body_util::BodyExt;
 use opentelemetry::propagation::TextMapPropagator;
 use opentelemetry::trace::TraceContextExt;
 use opentelemetry_sdk::propagation::TraceContextPropagator;
@@ -48,11 +50,67 @@ impl Aspect_ for DefaultAspect {
     }
 }
 
+//配置了多个Aspect时按配置顺序嵌套执行,前面的Aspect包裹后面的Aspect
+pub struct AspectChain {
+    aspect: &'static dyn Aspect_,
+    next: &'static dyn Aspect_,
+    //每个FusenFilter对应的下一层Filter,服务端与客户端的Filter都是全局唯一的
+    filters: RwLock<HashMap<usize, &'static dyn FusenFilter>>,
+}
+
+impl AspectChain {
+    pub fn new(aspect: &'static dyn Aspect_, next: &'static dyn Aspect_) -> Self {
+        AspectChain {
+            aspect,
+            next,
+            filters: Default::default(),
+        }
+    }
+
+    fn get_filter(&self, filter: &'static dyn FusenFilter) -> &'static dyn FusenFilter {
+        let key = filter as *const dyn FusenFilter as *const () as usize;
+        if let Some(next_filter) = self.filters.read().unwrap().get(&key) {
+            return *next_filter;
+        }
+        *self.filters.write().unwrap().entry(key).or_insert_with(|| {
+            Box::leak(Box::new(AspectFilter {
+                aspect: self.next,
+                filter,
+            }))
+        })
+    }
+}
+
+impl Aspect_ for AspectChain {
+    fn aroud_(
+        &'static self,
+        filter: &'static dyn FusenFilter,
+        context: FusenContext,
+    ) -> FusenFuture<Result<FusenContext, crate::Error>> {
+        self.aspect.aroud_(self.get_filter(filter), context)
+    }
+}
+
+struct AspectFilter {
+    aspect: &'static dyn Aspect_,
+    filter: &'static dyn FusenFilter,
+}
+
+impl FusenFilter for AspectFilter {
+    fn call(
+        &'static self,
+        context: FusenContext,
+    ) -> FusenFuture<Result<FusenContext, crate::Error>> {
+        self.aspect.aroud_(self.filter, context)
+    }
+}
+
 pub struct AspectClientFilter {
     request_handle: RequestHandler,
     response_handle: ResponseHandler,
     handle_context: Arc<HandlerContext>,
     route: Route,
+    dubbo_codec: DubboCodec,
     trace_context_propagator: TraceContextPropagator,
 }
 
@@ -68,6 +126,7 @@ impl AspectClientFilter {
             response_handle,
             handle_context,
             route,
+            dubbo_codec: DubboCodec,
             trace_context_propagator: TraceContextPropagator::new(),
         }
     }
@@ -97,13 +156,26 @@ impl FusenFilter for AspectClientFilter {
                 self.trace_context_propagator
                     .inject_context(&span_context, context.get_mut_request().get_mut_headers());
             }
+            if let Socket::Dubbo(_) = socket.get_socket() {
+                let frame = self.dubbo_codec.encode_request(&context)?;
+                let frame = socket.send_dubbo_request(frame).await?;
+                let res = self.dubbo_codec.decode_response(frame);
+                context.get_mut_response().set_response(res);
+                return Ok(context);
+            }
             let request = self.request_handle.encode(&context)?;
-            let response: http::Response<hyper::body::Incoming> =
-                socket.send_request(request).await?;
-            let res = self
-                .response_handle
-                .decode(response.map(|e| e.boxed()))
-                .await;
+            let response = socket.send_request(request).await?;
+            if let Some(content_type) = response
+                .headers()
+                .get("content-type")
+                .and_then(|e| e.to_str().ok())
+            {
+                context
+                    .get_mut_response()
+                    .get_mut_headers()
+                    .insert("content-type".to_owned(), content_type.to_owned());
+            }
+            let res = self.response_handle.decode(response).await;
             context.get_mut_response().set_response(res);
             Ok(context)
         })
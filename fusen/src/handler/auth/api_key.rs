@@ -0,0 +1,118 @@
+use super::{unauthenticated, Authenticator, Signer};
+use fusen_common::{error::FusenError, FusenContext};
+use fusen_procedural_macro::Data;
+use ring::{hmac, rand::SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub const API_KEY: &str = "x-api-key";
+
+#[derive(Serialize, Deserialize, Default, Clone, Data)]
+pub struct ApiKeyConfig {
+    //携带key的请求头,默认x-api-key
+    header: Option<String>,
+    //服务端允许的key及对应的调用方身份
+    keys: Option<HashMap<String, String>>,
+    //客户端请求携带的key
+    key: Option<String>,
+}
+
+impl ApiKeyConfig {
+    pub fn get_header_name(&self) -> String {
+        self.header
+            .as_ref()
+            .map_or(API_KEY.to_owned(), |e| e.to_lowercase())
+    }
+}
+
+//服务端只保存key的HMAC,校验时用ring::hmac::verify做常量时间比较,并遍历所有key避免提前返回
+pub(crate) struct ApiKeyAuthenticator {
+    header: String,
+    secret: hmac::Key,
+    keys: Vec<(hmac::Tag, String)>,
+}
+
+impl ApiKeyAuthenticator {
+    pub fn new(header: String, keys: &HashMap<String, String>) -> Result<Self, FusenError> {
+        let secret = hmac::Key::generate(hmac::HMAC_SHA256, &SystemRandom::new())
+            .map_err(|_| FusenError::from("api key : generate secret error"))?;
+        let keys = keys
+            .iter()
+            .map(|(key, principal)| (hmac::sign(&secret, key.as_bytes()), principal.clone()))
+            .collect();
+        Ok(ApiKeyAuthenticator {
+            header,
+            secret,
+            keys,
+        })
+    }
+}
+
+impl Authenticator for ApiKeyAuthenticator {
+    fn authenticate(&self, context: &FusenContext) -> Result<String, FusenError> {
+        let key = context
+            .get_meta_data()
+            .get_value(&self.header)
+            .ok_or_else(|| unauthenticated("missing api key"))?;
+        let mut principal = None;
+        for (tag, name) in &self.keys {
+            if hmac::verify(&self.secret, key.as_bytes(), tag.as_ref()).is_ok() {
+                principal = Some(name);
+            }
+        }
+        principal
+            .cloned()
+            .ok_or_else(|| unauthenticated("invalid api key"))
+    }
+}
+
+pub(crate) struct ApiKeySigner {
+    header: String,
+    key: String,
+}
+
+impl ApiKeySigner {
+    pub fn new(header: String, key: &str) -> Self {
+        ApiKeySigner {
+            header,
+            key: key.to_owned(),
+        }
+    }
+}
+
+impl Signer for ApiKeySigner {
+    fn sign(&self, context: &mut FusenContext) -> Result<(), FusenError> {
+        context
+            .get_mut_request()
+            .get_mut_headers()
+            .insert(self.header.clone(), self.key.clone());
+        Ok(())
+    }
+}
+
+#[test]
+fn test() {
+    use fusen_common::{ContextInfo, FusenRequest, MetaData};
+    let keys = HashMap::from([
+        ("key-a".to_owned(), "app-a".to_owned()),
+        ("key-b".to_owned(), "app-b".to_owned()),
+    ]);
+    let authenticator = ApiKeyAuthenticator::new(API_KEY.to_owned(), &keys).unwrap();
+    let authenticate = |key: Option<&str>| {
+        let mut meta_data = MetaData::new();
+        if let Some(key) = key {
+            meta_data.insert(API_KEY.to_owned(), key.to_owned());
+        }
+        let context = FusenContext::new(
+            String::new(),
+            ContextInfo::default(),
+            FusenRequest::new(None, Default::default()),
+            meta_data,
+        );
+        authenticator.authenticate(&context)
+    };
+    assert_eq!(authenticate(Some("key-b")).unwrap(), "app-b");
+    assert!(authenticate(Some("key-")).is_err());
+    assert!(authenticate(Some("key-c")).is_err());
+    assert!(authenticate(None).is_err());
+}
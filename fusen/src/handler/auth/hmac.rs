@@ -0,0 +1,339 @@
+use super::{unauthenticated, Authenticator, Signer};
+use crate::codec::request_codec::{get_http_parts, HttpParts};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use fusen_common::{
+    codec::CodecType, date_util::get_now_date_time_as_millis, error::FusenError, logs::get_uuid,
+    register::Type, ContextInfo, FusenContext,
+};
+use fusen_procedural_macro::Data;
+use ring::{digest, hmac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Mutex,
+};
+
+//与Dubbo的auth filter保持一致
+pub const ACCESS_KEY: &str = "ak";
+pub const TIMESTAMP: &str = "timestamp";
+pub const SIGNATURE: &str = "signature";
+//每个请求唯一的随机值,服务端拒绝重复使用的nonce
+pub const NONCE: &str = "nonce";
+//参与签名的header参数名,以逗号分隔
+pub const SIGNED_HEADERS: &str = "signed-headers";
+
+const DEFAULT_MAX_SKEW: u64 = 300;
+const MAX_NONCE_LENGTH: usize = 64;
+
+#[derive(Serialize, Deserialize, Default, Clone, Data)]
+pub struct HmacConfig {
+    //客户端签名使用的accessKey/secretKey
+    access_key: Option<String>,
+    secret_key: Option<String>,
+    //服务端允许的accessKey及对应的secretKey
+    credentials: Option<HashMap<String, String>>,
+    //允许的时间偏差,单位秒,默认300
+    max_skew: Option<u64>,
+}
+
+//签名内容为 服务名#方法名#secretKey#时间戳#nonce#请求内容摘要
+fn get_sign_content(
+    context_info: &ContextInfo,
+    secret_key: &str,
+    timestamp: &str,
+    nonce: &str,
+    digest: &str,
+) -> String {
+    format!(
+        "{}#{}#{}#{}#{}#{}",
+        context_info.get_handler_key(),
+        context_info.get_method_name(),
+        secret_key,
+        timestamp,
+        nonce,
+        digest
+    )
+}
+
+//HTTP请求的摘要包含方法、路径与query、header中的参数以及未压缩的请求体
+fn get_http_digest(method: &str, uri: &str, headers: &[(String, String)], body: &[u8]) -> String {
+    let mut context = digest::Context::new(&digest::SHA256);
+    context.update(format!("{}\n{}\n", method, uri).as_bytes());
+    for (name, value) in headers {
+        context.update(format!("{}:{}\n", name, value).as_bytes());
+    }
+    context.update(body);
+    BASE64_STANDARD.encode(context.finish())
+}
+
+//Dubbo请求按顺序传递参数,经过Dubbo2协议转换后JSON格式可能不同,统一为serde_json的输出
+fn get_fields_digest(fields: Vec<String>) -> String {
+    let fields: Vec<String> = fields
+        .into_iter()
+        .map(|e| serde_json::from_str::<Value>(&e).map_or(e, |e| e.to_string()))
+        .collect();
+    let bytes = serde_json::to_vec(&fields).unwrap_or_default();
+    BASE64_STANDARD.encode(digest::digest(&digest::SHA256, &bytes))
+}
+
+//客户端按照编码后的请求计算摘要,同时返回header中参与签名的参数名
+fn get_client_digest(context: &FusenContext) -> Result<(String, Vec<String>), FusenError> {
+    let request = context.get_request();
+    let dubbo = match context.get_server_type() {
+        Type::Dubbo => true,
+        Type::Host(host) => host.starts_with("dubbo://"),
+        _ => false,
+    };
+    if dubbo {
+        let fields = match request.get_query_fields() {
+            Some(query_fields) => query_fields.iter().map(|e| e.1.clone()).collect(),
+            None => serde_json::from_slice(request.get_body())
+                .map_err(|e| FusenError::from(e.to_string()))?,
+        };
+        return Ok((get_fields_digest(fields), vec![]));
+    }
+    let HttpParts { uri, headers, body } =
+        get_http_parts(context).map_err(|e| FusenError::from(e.to_string()))?;
+    let headers: Vec<(String, String)> = headers
+        .into_iter()
+        .map(|(name, value)| (name.to_lowercase(), value))
+        .collect();
+    let method = context.get_context_info().get_path().get_method();
+    let digest = get_http_digest(method, &uri, &headers, &body);
+    Ok((digest, headers.into_iter().map(|e| e.0).collect()))
+}
+
+//服务端按照收到的请求计算摘要,不信任客户端传递的摘要
+fn get_server_digest(context: &FusenContext) -> Result<String, FusenError> {
+    let request = context.get_request();
+    let meta_data = context.get_meta_data();
+    let uri = match request.get_uri() {
+        Some(uri) if matches!(meta_data.get_codec(), CodecType::JSON) => uri,
+        //triple与Dubbo2协议的请求按顺序传递参数
+        _ => {
+            let fields = serde_json::from_slice(request.get_body())
+                .map_err(|_| unauthenticated("invalid request content"))?;
+            return Ok(get_fields_digest(fields));
+        }
+    };
+    let headers: Vec<(String, String)> = meta_data
+        .get_value(SIGNED_HEADERS)
+        .map(|e| e.split(',').filter(|e| !e.is_empty()).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|name| {
+            let value = meta_data.get_value(name).cloned().unwrap_or_default();
+            (name.to_owned(), value)
+        })
+        .collect();
+    let method = context.get_context_info().get_path().get_method();
+    Ok(get_http_digest(method, uri, &headers, request.get_body()))
+}
+
+//时间偏差范围内使用过的nonce,过期时间按照记录的顺序递增
+#[derive(Default)]
+struct NonceCache {
+    nonces: HashSet<String>,
+    expires: VecDeque<(i128, String)>,
+}
+
+impl NonceCache {
+    //nonce未使用过时记录并返回true
+    fn insert(&mut self, nonce: &str, now: i128, ttl: i128) -> bool {
+        while self.expires.front().is_some_and(|e| e.0 <= now) {
+            if let Some((_, nonce)) = self.expires.pop_front() {
+                self.nonces.remove(&nonce);
+            }
+        }
+        if !self.nonces.insert(nonce.to_owned()) {
+            return false;
+        }
+        self.expires.push_back((now + ttl, nonce.to_owned()));
+        true
+    }
+}
+
+pub(crate) struct HmacAuthenticator {
+    credentials: HashMap<String, String>,
+    max_skew: i128,
+    nonces: Mutex<NonceCache>,
+}
+
+impl HmacAuthenticator {
+    pub fn new(credentials: &HashMap<String, String>, max_skew: &Option<u64>) -> Self {
+        HmacAuthenticator {
+            credentials: credentials.clone(),
+            max_skew: max_skew.unwrap_or(DEFAULT_MAX_SKEW) as i128 * 1000,
+            nonces: Default::default(),
+        }
+    }
+}
+
+impl Authenticator for HmacAuthenticator {
+    fn authenticate(&self, context: &FusenContext) -> Result<String, FusenError> {
+        let meta_data = context.get_meta_data();
+        let (Some(access_key), Some(timestamp), Some(nonce), Some(signature)) = (
+            meta_data.get_value(ACCESS_KEY),
+            meta_data.get_value(TIMESTAMP),
+            meta_data.get_value(NONCE),
+            meta_data.get_value(SIGNATURE),
+        ) else {
+            return Err(unauthenticated("missing request signature"));
+        };
+        let secret_key = self
+            .credentials
+            .get(access_key)
+            .ok_or_else(|| unauthenticated("invalid access key"))?;
+        let request_time: i128 = timestamp
+            .parse()
+            .map_err(|_| unauthenticated("invalid timestamp"))?;
+        let now = get_now_date_time_as_millis();
+        if (now - request_time).abs() > self.max_skew {
+            return Err(unauthenticated("request timestamp expired"));
+        }
+        if nonce.is_empty() || nonce.len() > MAX_NONCE_LENGTH {
+            return Err(unauthenticated("invalid nonce"));
+        }
+        let signature = BASE64_STANDARD
+            .decode(signature)
+            .map_err(|_| unauthenticated("invalid signature"))?;
+        let digest = get_server_digest(context)?;
+        let content = get_sign_content(
+            context.get_context_info(),
+            secret_key,
+            timestamp,
+            nonce,
+            &digest,
+        );
+        let key = hmac::Key::new(hmac::HMAC_SHA256, secret_key.as_bytes());
+        hmac::verify(&key, content.as_bytes(), &signature)
+            .map_err(|_| unauthenticated("invalid signature"))?;
+        //签名校验通过后再记录nonce,时间戳在now±max_skew内有效,记录的nonce保留2倍max_skew
+        let mut nonces = self
+            .nonces
+            .lock()
+            .map_err(|e| FusenError::from(e.to_string()))?;
+        if !nonces.insert(&format!("{}#{}", access_key, nonce), now, self.max_skew * 2) {
+            return Err(unauthenticated("request nonce reused"));
+        }
+        Ok(access_key.clone())
+    }
+}
+
+pub(crate) struct HmacSigner {
+    access_key: String,
+    secret_key: String,
+    key: hmac::Key,
+}
+
+impl HmacSigner {
+    pub fn new(access_key: &str, secret_key: &str) -> Self {
+        HmacSigner {
+            access_key: access_key.to_owned(),
+            secret_key: secret_key.to_owned(),
+            key: hmac::Key::new(hmac::HMAC_SHA256, secret_key.as_bytes()),
+        }
+    }
+}
+
+impl Signer for HmacSigner {
+    fn sign(&self, context: &mut FusenContext) -> Result<(), FusenError> {
+        let timestamp = get_now_date_time_as_millis().to_string();
+        let nonce = get_uuid();
+        let (digest, signed_headers) = get_client_digest(context)?;
+        let content = get_sign_content(
+            context.get_context_info(),
+            &self.secret_key,
+            &timestamp,
+            &nonce,
+            &digest,
+        );
+        let signature = hmac::sign(&self.key, content.as_bytes());
+        let headers = context.get_mut_request().get_mut_headers();
+        headers.insert(ACCESS_KEY.to_owned(), self.access_key.clone());
+        headers.insert(SIGNATURE.to_owned(), BASE64_STANDARD.encode(signature));
+        headers.insert(TIMESTAMP.to_owned(), timestamp);
+        headers.insert(NONCE.to_owned(), nonce);
+        if !signed_headers.is_empty() {
+            headers.insert(SIGNED_HEADERS.to_owned(), signed_headers.join(","));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test() {
+    use bytes::Bytes;
+    use fusen_common::{codec::SerializationType, FusenRequest, MetaData, Path};
+    let credentials = HashMap::from([("app-order".to_owned(), "secret".to_owned())]);
+    let authenticator = HmacAuthenticator::new(&credentials, &None);
+    let signer = HmacSigner::new("app-order", "secret");
+    let context_info = || {
+        ContextInfo::default()
+            .path(Path::new("POST", "/DemoService/sayHello".to_owned()))
+            .class_name("DemoService".to_owned())
+            .method_name("sayHello".to_owned())
+    };
+    let mut context = FusenContext::new(
+        String::new(),
+        context_info(),
+        FusenRequest::new_for_client(
+            "POST",
+            vec!["name".to_owned()],
+            vec![Bytes::from("\"world\"")],
+            SerializationType::JSON,
+        ),
+        MetaData::new(),
+    );
+    signer.sign(&mut context).unwrap();
+    let body = context.get_request().get_body().clone();
+    let server_context = |headers: &[(&str, &str)], body: Bytes| {
+        let mut meta_data = MetaData::new();
+        for (key, value) in context.get_request().get_headers() {
+            meta_data.insert(key.clone(), value.clone());
+        }
+        for (key, value) in headers {
+            meta_data.insert(key.to_string(), value.to_string());
+        }
+        FusenContext::new(
+            String::new(),
+            context_info(),
+            FusenRequest::new(None, body).uri(Some("/DemoService/sayHello".to_owned())),
+            meta_data,
+        )
+    };
+    assert_eq!(
+        authenticator
+            .authenticate(&server_context(&[], body.clone()))
+            .unwrap(),
+        "app-order"
+    );
+    //重放的请求被拒绝
+    assert!(authenticator
+        .authenticate(&server_context(&[], body.clone()))
+        .is_err());
+    //请求体被篡改
+    assert!(authenticator
+        .authenticate(&server_context(
+            &[(NONCE, "other")],
+            Bytes::from("[\"\\\"hacker\\\"\"]")
+        ))
+        .is_err());
+    assert!(authenticator
+        .authenticate(&server_context(&[(NONCE, "other"), (TIMESTAMP, "0")], body))
+        .is_err());
+}
+
+#[test]
+fn test_dubbo_fields() {
+    //经过Dubbo2协议转换后字段顺序与空白不同,摘要保持一致
+    assert_eq!(
+        get_fields_digest(vec!["{\"b\": 1, \"a\": \"x\"}".to_owned()]),
+        get_fields_digest(vec!["{\"a\":\"x\",\"b\":1}".to_owned()])
+    );
+    assert_ne!(
+        get_fields_digest(vec!["1".to_owned()]),
+        get_fields_digest(vec!["2".to_owned()])
+    );
+}
@@ -0,0 +1,171 @@
+use super::{unauthenticated, Authenticator, Signer};
+use fusen_common::{error::FusenError, FusenContext};
+use fusen_procedural_macro::Data;
+use jsonwebtoken::{
+    decode, decode_header,
+    jwk::{AlgorithmParameters, EllipticCurve, Jwk, JwkSet},
+    Algorithm, DecodingKey, Validation,
+};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+pub const AUTHORIZATION: &str = "authorization";
+const BEARER: &str = "Bearer ";
+
+#[derive(Serialize, Deserialize, Default, Clone, Data)]
+pub struct JwtConfig {
+    //服务端校验token使用的本地JWKS文件
+    jwks_path: Option<String>,
+    issuer: Option<String>,
+    audience: Option<String>,
+    //客户端请求携带的token
+    token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Claims {
+    sub: String,
+}
+
+struct JwtKey {
+    kid: Option<String>,
+    algorithm: Algorithm,
+    key: DecodingKey,
+}
+
+pub(crate) struct JwtAuthenticator {
+    keys: Vec<JwtKey>,
+    issuer: Option<String>,
+    audience: Option<String>,
+}
+
+impl JwtAuthenticator {
+    pub fn new(config: &JwtConfig) -> Result<Self, crate::Error> {
+        let jwks_path = config.jwks_path.as_ref().ok_or("jwt auth need jwks_path")?;
+        let jwks: JwkSet = serde_json::from_str(&std::fs::read_to_string(jwks_path)?)?;
+        Self::from_jwks(jwks, config.issuer.clone(), config.audience.clone())
+    }
+
+    fn from_jwks(
+        jwks: JwkSet,
+        issuer: Option<String>,
+        audience: Option<String>,
+    ) -> Result<Self, crate::Error> {
+        let mut keys = vec![];
+        for jwk in &jwks.keys {
+            keys.push(JwtKey {
+                kid: jwk.common.key_id.clone(),
+                algorithm: get_algorithm(jwk)?,
+                key: DecodingKey::from_jwk(jwk)?,
+            });
+        }
+        if keys.is_empty() {
+            return Err("jwks not find any key".into());
+        }
+        Ok(JwtAuthenticator {
+            keys,
+            issuer,
+            audience,
+        })
+    }
+}
+
+//JWK未声明alg时根据密钥类型推断,校验时只接受该算法
+fn get_algorithm(jwk: &Jwk) -> Result<Algorithm, crate::Error> {
+    if let Some(algorithm) = jwk.common.key_algorithm {
+        return Ok(Algorithm::from_str(&algorithm.to_string())?);
+    }
+    match &jwk.algorithm {
+        AlgorithmParameters::RSA(_) => Ok(Algorithm::RS256),
+        AlgorithmParameters::EllipticCurve(params) => match params.curve {
+            EllipticCurve::P256 => Ok(Algorithm::ES256),
+            EllipticCurve::P384 => Ok(Algorithm::ES384),
+            _ => Err("unsupported jwk curve".into()),
+        },
+        AlgorithmParameters::OctetKeyPair(_) => Ok(Algorithm::EdDSA),
+        AlgorithmParameters::OctetKey(_) => Ok(Algorithm::HS256),
+    }
+}
+
+impl Authenticator for JwtAuthenticator {
+    fn authenticate(&self, context: &FusenContext) -> Result<String, FusenError> {
+        let token = context
+            .get_meta_data()
+            .get_value(AUTHORIZATION)
+            .and_then(|e| e.strip_prefix(BEARER))
+            .ok_or_else(|| unauthenticated("missing bearer token"))?;
+        let header = decode_header(token).map_err(|_| unauthenticated("invalid token"))?;
+        let key = self
+            .keys
+            .iter()
+            .find(|e| e.algorithm == header.alg && (header.kid.is_none() || e.kid == header.kid))
+            .ok_or_else(|| unauthenticated("not find jwt key"))?;
+        let mut validation = Validation::new(key.algorithm);
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        match &self.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        let claims = decode::<Claims>(token, &key.key, &validation)
+            .map_err(|err| unauthenticated(&format!("invalid token : {}", err)))?;
+        Ok(claims.claims.sub)
+    }
+}
+
+pub(crate) struct JwtSigner {
+    authorization: String,
+}
+
+impl JwtSigner {
+    pub fn new(token: &str) -> Self {
+        JwtSigner {
+            authorization: format!("{}{}", BEARER, token),
+        }
+    }
+}
+
+impl Signer for JwtSigner {
+    fn sign(&self, context: &mut FusenContext) -> Result<(), FusenError> {
+        context
+            .get_mut_request()
+            .get_mut_headers()
+            .entry(AUTHORIZATION.to_owned())
+            .or_insert_with(|| self.authorization.clone());
+        Ok(())
+    }
+}
+
+#[test]
+fn test() {
+    use fusen_common::{ContextInfo, FusenRequest, MetaData};
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    let jwks: JwkSet = serde_json::from_str(
+        r#"{"keys":[{"kty":"oct","kid":"k1","alg":"HS256","k":"c2VjcmV0LWtleS1mb3ItdGVzdA"}]}"#,
+    )
+    .unwrap();
+    let authenticator = JwtAuthenticator::from_jwks(jwks, Some("fusen".to_owned()), None).unwrap();
+    let exp = fusen_common::date_util::get_now_date_time_as_millis() / 1000 + 60;
+    let mut header = Header::new(Algorithm::HS256);
+    header.kid = Some("k1".to_owned());
+    let authenticate = |iss: &str| {
+        let token = encode(
+            &header,
+            &serde_json::json!({"sub": "app-order", "iss": iss, "exp": exp}),
+            &EncodingKey::from_secret(b"secret-key-for-test"),
+        )
+        .unwrap();
+        let mut meta_data = MetaData::new();
+        meta_data.insert(AUTHORIZATION.to_owned(), format!("{}{}", BEARER, token));
+        let context = FusenContext::new(
+            String::new(),
+            ContextInfo::default(),
+            FusenRequest::new(None, Default::default()),
+            meta_data,
+        );
+        authenticator.authenticate(&context)
+    };
+    assert_eq!(authenticate("fusen").unwrap(), "app-order");
+    assert!(authenticate("other").is_err());
+}
@@ -0,0 +1,281 @@
+use super::{aspect::Aspect_, Handler, HandlerInvoker};
+use crate::{filter::FusenFilter, FusenFuture};
+use api_key::{ApiKeyAuthenticator, ApiKeyConfig, ApiKeySigner};
+use fusen_common::{
+    error::{FusenError, StatusCode},
+    ContextInfo, Direction, FusenContext,
+};
+use fusen_procedural_macro::Data;
+use hmac::{HmacAuthenticator, HmacConfig, HmacSigner};
+use jwt::{JwtAuthenticator, JwtConfig, JwtSigner};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+pub mod api_key;
+pub mod hmac;
+pub mod jwt;
+
+pub const JWT_SERVER_ASPECT: &str = "JwtServerAspect";
+pub const JWT_CLIENT_ASPECT: &str = "JwtClientAspect";
+pub const HMAC_SERVER_ASPECT: &str = "HmacServerAspect";
+pub const HMAC_CLIENT_ASPECT: &str = "HmacClientAspect";
+pub const API_KEY_SERVER_ASPECT: &str = "ApiKeyServerAspect";
+pub const API_KEY_CLIENT_ASPECT: &str = "ApiKeyClientAspect";
+
+#[derive(Serialize, Deserialize, Default, Clone, Data)]
+pub struct AuthConfig {
+    jwt: Option<JwtConfig>,
+    hmac: Option<HmacConfig>,
+    api_key: Option<ApiKeyConfig>,
+    rules: Option<Vec<AuthRule>>,
+}
+
+//方法级别的访问规则,service与method支持以*结尾的前缀匹配,按顺序匹配第一条
+#[derive(Serialize, Deserialize, Default, Clone, Data)]
+pub struct AuthRule {
+    service: String,
+    method: Option<String>,
+    //允许访问的调用方身份,为空时允许所有认证通过的调用方
+    allow: Option<Vec<String>>,
+    //无需认证即可访问
+    anonymous: Option<bool>,
+}
+
+impl AuthConfig {
+    //根据配置创建鉴权组件,在handler_infos中通过id引用
+    pub fn load(&self) -> Result<Vec<Handler>, crate::Error> {
+        let rules = AuthRules(Arc::new(self.rules.clone().unwrap_or_default()));
+        let mut handlers = vec![];
+        if let Some(jwt) = &self.jwt {
+            if jwt.get_jwks_path().is_some() {
+                let authenticator = JwtAuthenticator::new(jwt)?;
+                handlers.push(server_handler(JWT_SERVER_ASPECT, authenticator, &rules));
+            }
+            if let Some(token) = jwt.get_token() {
+                handlers.push(client_handler(JWT_CLIENT_ASPECT, JwtSigner::new(token)));
+            }
+        }
+        if let Some(hmac) = &self.hmac {
+            if let Some(credentials) = hmac.get_credentials() {
+                let authenticator = HmacAuthenticator::new(credentials, hmac.get_max_skew());
+                handlers.push(server_handler(HMAC_SERVER_ASPECT, authenticator, &rules));
+            }
+            if let (Some(access_key), Some(secret_key)) =
+                (hmac.get_access_key(), hmac.get_secret_key())
+            {
+                let signer = HmacSigner::new(access_key, secret_key);
+                handlers.push(client_handler(HMAC_CLIENT_ASPECT, signer));
+            }
+        }
+        if let Some(api_key) = &self.api_key {
+            if let Some(keys) = api_key.get_keys() {
+                let authenticator = ApiKeyAuthenticator::new(api_key.get_header_name(), keys)?;
+                handlers.push(server_handler(API_KEY_SERVER_ASPECT, authenticator, &rules));
+            }
+            if let Some(key) = api_key.get_key() {
+                let signer = ApiKeySigner::new(api_key.get_header_name(), key);
+                handlers.push(client_handler(API_KEY_CLIENT_ASPECT, signer));
+            }
+        }
+        Ok(handlers)
+    }
+}
+
+fn server_handler<A: Authenticator + 'static>(
+    id: &str,
+    authenticator: A,
+    rules: &AuthRules,
+) -> Handler {
+    let aspect = ServerAuthAspect {
+        authenticator,
+        rules: rules.clone(),
+    };
+    Handler::new(
+        id.to_owned(),
+        HandlerInvoker::Aspect(Box::leak(Box::new(aspect))),
+    )
+}
+
+fn client_handler<S: Signer + 'static>(id: &str, signer: S) -> Handler {
+    Handler::new(
+        id.to_owned(),
+        HandlerInvoker::Aspect(Box::leak(Box::new(ClientAuthAspect { signer }))),
+    )
+}
+
+pub fn unauthenticated(message: &str) -> FusenError {
+    FusenError::status(StatusCode::Unauthenticated, message)
+}
+
+#[derive(Clone, Default)]
+struct AuthRules(Arc<Vec<AuthRule>>);
+
+impl AuthRules {
+    fn find(&self, context_info: &ContextInfo) -> Option<&AuthRule> {
+        self.0.iter().find(|rule| {
+            is_match(&rule.service, context_info.get_class_name())
+                && rule
+                    .method
+                    .as_ref()
+                    .is_none_or(|e| is_match(e, context_info.get_method_name()))
+        })
+    }
+
+    fn is_anonymous(&self, context_info: &ContextInfo) -> bool {
+        self.find(context_info)
+            .is_some_and(|e| e.anonymous.unwrap_or_default())
+    }
+
+    fn check(&self, context_info: &ContextInfo, principal: &str) -> Result<(), FusenError> {
+        match self.find(context_info).and_then(|e| e.allow.as_ref()) {
+            Some(allow) if !allow.iter().any(|e| is_match(e, principal)) => {
+                Err(FusenError::status(
+                    StatusCode::PermissionDenied,
+                    format!(
+                        "{} not allowed to call {}.{}",
+                        principal,
+                        context_info.get_class_name(),
+                        context_info.get_method_name()
+                    ),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn is_match(pattern: &str, value: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => value.starts_with(prefix),
+        None => pattern == value,
+    }
+}
+
+//校验请求携带的凭证,返回调用方身份
+trait Authenticator: Send + Sync {
+    fn authenticate(&self, context: &FusenContext) -> Result<String, FusenError>;
+}
+
+//为发出的请求添加凭证
+trait Signer: Send + Sync {
+    fn sign(&self, context: &mut FusenContext) -> Result<(), FusenError>;
+}
+
+struct ServerAuthAspect<A> {
+    authenticator: A,
+    rules: AuthRules,
+}
+
+impl<A: Authenticator> Aspect_ for ServerAuthAspect<A> {
+    fn aroud_(
+        &'static self,
+        filter: &'static dyn FusenFilter,
+        mut context: FusenContext,
+    ) -> FusenFuture<Result<FusenContext, crate::Error>> {
+        Box::pin(async move {
+            match context.get_direction() {
+                Direction::Server => (),
+                Direction::Client => return filter.call(context).await,
+                Direction::Unknown => {
+                    return Err(unauthenticated("unknown request direction").into())
+                }
+            }
+            if self.rules.is_anonymous(context.get_context_info()) {
+                return filter.call(context).await;
+            }
+            let principal = self.authenticator.authenticate(&context)?;
+            self.rules.check(context.get_context_info(), &principal)?;
+            context.set_principal(Some(principal));
+            filter.call(context).await
+        })
+    }
+}
+
+struct ClientAuthAspect<S> {
+    signer: S,
+}
+
+impl<S: Signer> Aspect_ for ClientAuthAspect<S> {
+    fn aroud_(
+        &'static self,
+        filter: &'static dyn FusenFilter,
+        mut context: FusenContext,
+    ) -> FusenFuture<Result<FusenContext, crate::Error>> {
+        Box::pin(async move {
+            match context.get_direction() {
+                Direction::Client => self.signer.sign(&mut context)?,
+                Direction::Server => (),
+                Direction::Unknown => {
+                    return Err(unauthenticated("unknown request direction").into())
+                }
+            }
+            filter.call(context).await
+        })
+    }
+}
+
+#[test]
+fn test() {
+    let rules = AuthRules(Arc::new(vec![
+        AuthRule::default()
+            .service("org.apache.dubbo.*".to_owned())
+            .method(Some("health".to_owned()))
+            .anonymous(Some(true)),
+        AuthRule::default()
+            .service("org.apache.dubbo.springboot.demo.DemoService".to_owned())
+            .allow(Some(vec!["admin".to_owned(), "app-*".to_owned()])),
+    ]));
+    let context_info = ContextInfo::default()
+        .class_name("org.apache.dubbo.springboot.demo.DemoService".to_owned())
+        .method_name("sayHello".to_owned());
+    assert!(!rules.is_anonymous(&context_info));
+    assert!(rules.check(&context_info, "admin").is_ok());
+    assert!(rules.check(&context_info, "app-order").is_ok());
+    assert!(rules.check(&context_info, "guest").is_err());
+    let context_info = context_info.method_name("health".to_owned());
+    assert!(rules.is_anonymous(&context_info));
+    let context_info = context_info.class_name("UserService".to_owned());
+    assert!(rules.check(&context_info, "guest").is_ok());
+}
+
+#[tokio::test]
+async fn test_direction() {
+    use fusen_common::{FusenRequest, MetaData};
+    struct EmptyFilter;
+    impl FusenFilter for EmptyFilter {
+        fn call(
+            &'static self,
+            context: FusenContext,
+        ) -> FusenFuture<Result<FusenContext, crate::Error>> {
+            Box::pin(async move { Ok(context) })
+        }
+    }
+    let keys = std::collections::HashMap::from([("key".to_owned(), "app".to_owned())]);
+    let aspect: &'static ServerAuthAspect<ApiKeyAuthenticator> =
+        Box::leak(Box::new(ServerAuthAspect {
+            authenticator: ApiKeyAuthenticator::new("x-api-key".to_owned(), &keys).unwrap(),
+            rules: Default::default(),
+        }));
+    let new_context = |direction| {
+        FusenContext::new(
+            String::new(),
+            ContextInfo::default(),
+            FusenRequest::new(None, Default::default()),
+            MetaData::new(),
+        )
+        .direction(direction)
+    };
+    //未标记方向的请求不会跳过鉴权
+    assert!(aspect
+        .aroud_(&EmptyFilter, new_context(Direction::Unknown))
+        .await
+        .is_err());
+    assert!(aspect
+        .aroud_(&EmptyFilter, new_context(Direction::Server))
+        .await
+        .is_err());
+    assert!(aspect
+        .aroud_(&EmptyFilter, new_context(Direction::Client))
+        .await
+        .is_ok());
+}
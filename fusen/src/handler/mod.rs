@@ -1,8 +1,9 @@
 use self::loadbalance::{DefaultLoadBalance, LoadBalance_};
-use aspect::{Aspect_, DefaultAspect};
+use aspect::{AspectChain, Aspect_, DefaultAspect};
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, sync::Arc};
 pub mod aspect;
+pub mod auth;
 pub mod loadbalance;
 
 #[derive(Clone)]
@@ -59,7 +60,7 @@ impl HandlerContext {
 
     pub fn load_controller(&mut self, handler_info: HandlerInfo) -> Result<(), crate::Error> {
         let mut load_balance: Option<&'static dyn LoadBalance_> = None;
-        let mut aspect: Option<&'static dyn Aspect_> = None;
+        let mut aspects: Vec<&'static dyn Aspect_> = vec![];
 
         for item in &handler_info.handlers_id {
             if let Some(handler) = self.get_handler(item) {
@@ -67,9 +68,7 @@ impl HandlerContext {
                     HandlerInvoker::LoadBalance(handler) => {
                         let _ = load_balance.insert(handler);
                     }
-                    HandlerInvoker::Aspect(handler) => {
-                        let _ = aspect.insert(handler);
-                    }
+                    HandlerInvoker::Aspect(handler) => aspects.push(handler),
                 };
             }
         }
@@ -81,6 +80,10 @@ impl HandlerContext {
                 };
             }
         }
+        let mut aspect = aspects.into_iter().rev().reduce(|next, aspect| {
+            let chain: &'static dyn Aspect_ = Box::leak(Box::new(AspectChain::new(aspect, next)));
+            chain
+        });
         if aspect.is_none() {
             if let Some(handler) = self.get_handler("DefaultAspect") {
                 match handler.handler_invoker {
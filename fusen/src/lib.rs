@@ -1,6 +1,7 @@
 pub mod client;
 pub mod codec;
 pub mod config;
+pub mod context;
 pub mod filter;
 pub mod handler;
 pub mod protocol;
@@ -9,17 +10,22 @@ pub mod route;
 pub mod server;
 pub mod support;
 use crate::{
-    handler::HandlerInfo,
+    handler::{auth::AuthConfig, HandlerInfo},
     register::{Category, RegisterBuilder, Resource},
+    support::{
+        cors::CorsConfig,
+        tls::{init_client_tls, TlsConfig},
+    },
 };
-use client::FusenClient;
+use client::{ClientInfo, FusenClient};
 use codec::{request_codec::RequestHandler, response_codec::ResponseHandler};
 use config::FusenApplicationConfig;
 use filter::FusenFilter;
 pub use fusen_common;
 use fusen_common::{
+    openapi::{OpenApiConfig, OpenApiFn, OpenApiServer},
     register::Type,
-    server::{RpcServer, ServerInfo},
+    server::{GenericRpcServer, GenericServer, RpcServer, ServerInfo},
     MetaData,
 };
 pub use fusen_procedural_macro;
@@ -48,10 +54,18 @@ pub type StreamBody<D, E> = http_body_util::StreamBody<
 #[derive(Default)]
 pub struct FusenApplicationBuilder {
     port: Option<String>,
+    http3: bool,
+    tls: Option<TlsConfig>,
     application_name: String,
     register_config: Option<String>,
     handlers: Vec<Handler>,
     handler_infos: Vec<HandlerInfo>,
+    client_infos: Vec<ClientInfo>,
+    pass_through_headers: Vec<String>,
+    auth: Option<AuthConfig>,
+    cors: Vec<CorsConfig>,
+    openapi: Option<OpenApiConfig>,
+    openapi_docs: Vec<OpenApiFn>,
     servers: HashMap<String, Box<dyn RpcServer>>,
 }
 
@@ -66,6 +80,17 @@ impl FusenApplicationBuilder {
         self
     }
 
+    pub fn http3(mut self, http3: bool) -> Self {
+        self.http3 = http3;
+        self
+    }
+
+    //HTTP3服务端与客户端的证书配置
+    pub fn tls(mut self, tls: Option<TlsConfig>) -> Self {
+        self.tls = tls;
+        self
+    }
+
     pub fn register(mut self, register_config: Option<&str>) -> Self {
         self.register_config = register_config.map(|e| e.to_owned());
         self
@@ -83,6 +108,17 @@ impl FusenApplicationBuilder {
         self
     }
 
+    //注册泛化服务,处理id对应接口的所有方法
+    pub fn add_generic_server(
+        self,
+        id: &str,
+        version: Option<&str>,
+        group: Option<&str>,
+        server: Box<dyn GenericServer>,
+    ) -> Self {
+        self.add_fusen_server(Box::new(GenericRpcServer::new(id, version, group, server)))
+    }
+
     pub fn add_handler(mut self, handler: Handler) -> Self {
         self.handlers.push(handler);
         self
@@ -92,29 +128,105 @@ impl FusenApplicationBuilder {
         self
     }
 
+    pub fn add_client_info(mut self, info: ClientInfo) -> Self {
+        self.client_infos.push(info);
+        self
+    }
+
+    //处理请求时向下游服务透传的请求头
+    pub fn pass_through_headers(mut self, pass_through_headers: Vec<String>) -> Self {
+        self.pass_through_headers = pass_through_headers;
+        self
+    }
+
+    //鉴权组件配置,创建的组件通过handler_infos按id引用
+    pub fn auth(mut self, auth: Option<AuthConfig>) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    //服务的跨域策略
+    pub fn add_cors(mut self, cors: CorsConfig) -> Self {
+        self.cors.push(cors);
+        self
+    }
+
+    //配置后在对应路径提供OpenAPI文档
+    pub fn openapi(mut self, openapi: Option<OpenApiConfig>) -> Self {
+        self.openapi = openapi;
+        self
+    }
+
+    //添加#[fusen_trait(openapi = true)]生成的接口描述,如UserServiceClient::get_openapi
+    pub fn add_openapi(mut self, openapi: OpenApiFn) -> Self {
+        self.openapi_docs.push(openapi);
+        self
+    }
+
     pub fn init(self, config: FusenApplicationConfig) -> Self {
         let mut builder = self
             .application_name(config.get_application_name())
             .port(*config.get_port())
-            .register(config.get_register().as_deref());
+            .http3(config.get_http3().unwrap_or_default())
+            .tls(config.get_tls().clone())
+            .register(config.get_register().as_deref())
+            .auth(config.get_auth().clone())
+            .openapi(config.get_openapi().clone())
+            .pass_through_headers(
+                config
+                    .get_pass_through_headers()
+                    .clone()
+                    .unwrap_or_default(),
+            );
         if let Some(handler_infos) = config.get_handler_infos() {
             for handler_info in handler_infos {
                 builder = builder.add_handler_info(handler_info.clone());
             }
         }
+        if let Some(client_infos) = config.get_client_infos() {
+            for client_info in client_infos {
+                builder = builder.add_client_info(client_info.clone());
+            }
+        }
+        if let Some(cors) = config.get_cors() {
+            for cors in cors {
+                builder = builder.add_cors(cors.clone());
+            }
+        }
         builder
     }
 
+    //路由冲突或鉴权配置错误时panic,需要处理错误时使用try_build
     pub fn build(self) -> FusenApplicationContext {
+        self.try_build()
+            .unwrap_or_else(|err| panic!("fusen application build error : {}", err))
+    }
+
+    pub fn try_build(self) -> Result<FusenApplicationContext> {
         let FusenApplicationBuilder {
             application_name,
             port,
+            http3,
+            tls,
             register_config,
             handlers,
             handler_infos,
+            client_infos,
+            pass_through_headers,
+            auth,
+            cors,
+            openapi,
+            openapi_docs,
             servers,
         } = self;
+        let tls = tls.unwrap_or_default();
+        init_client_tls(tls.clone());
         let mut handler_context = HandlerContext::default();
+        if let Some(auth) = auth {
+            for handler in auth.load()? {
+                handler_context.insert(handler);
+            }
+        }
         for handler in handlers {
             handler_context.insert(handler);
         }
@@ -130,23 +242,39 @@ impl FusenApplicationBuilder {
             ));
         }
         let handler_context = Arc::new(handler_context);
-        FusenApplicationContext {
+        let client_infos = client_infos.into_iter().fold(HashMap::new(), |mut map, e| {
+            map.insert(e.get_id().to_owned(), e);
+            map
+        });
+        let mut server = FusenServer::new(port, http3, servers, handler_context.clone())
+            .pass_through_headers(pass_through_headers)
+            .cors(cors)
+            .tls(tls)
+            .openapi(openapi.map(|config| -> &'static dyn RpcServer {
+                Box::leak(Box::new(OpenApiServer::new(&config, &openapi_docs)))
+            }));
+        server.init_route()?;
+        Ok(FusenApplicationContext {
+            application_name,
             register: register.clone(),
             handler_context: handler_context.clone(),
+            client_infos: Arc::new(client_infos),
             client_filter: Box::leak(Box::new(AspectClientFilter::new(
                 RequestHandler::new(Arc::new(Default::default())),
                 ResponseHandler::new(),
                 handler_context.clone(),
                 Route::new(register),
             ))),
-            server: FusenServer::new(port, servers, handler_context),
-        }
+            server,
+        })
     }
 }
 
 pub struct FusenApplicationContext {
+    application_name: String,
     register: Option<Arc<Box<dyn Register>>>,
     handler_context: Arc<HandlerContext>,
+    client_infos: Arc<HashMap<String, ClientInfo>>,
     client_filter: &'static dyn FusenFilter,
     server: FusenServer,
 }
@@ -157,9 +285,11 @@ impl FusenApplicationContext {
 
     pub fn client(&self, server_type: Type) -> FusenClient {
         FusenClient::build(
+            self.application_name.clone(),
             server_type,
             self.client_filter,
             self.handler_context.clone(),
+            self.client_infos.clone(),
         )
     }
 
@@ -169,13 +299,17 @@ impl FusenApplicationContext {
         let shutdown = Shutdown::new(receiver);
         let mut shutdown_complete_rx = self.server.run(shutdown).await;
         let mut resources = vec![];
+        let mut params = MetaData::default().into_inner();
+        if *self.server.get_http3() {
+            params.insert("http3".to_owned(), "true".to_owned());
+        }
         if let Some(register) = self.register.clone() {
             //首先注册server
             let resource = Resource::default()
                 .category(Category::Server)
                 .host(fusen_common::net::get_ip())
                 .port(port.clone())
-                .params(MetaData::default().into_inner());
+                .params(params.clone());
             resources.push(resource.clone());
             let _ = register.register(resource).await;
             //再注册service
@@ -195,7 +329,7 @@ impl FusenApplicationContext {
                     .methods(methods)
                     .host(fusen_common::net::get_ip())
                     .port(port.clone())
-                    .params(MetaData::default().into_inner());
+                    .params(params.clone());
                 resources.push(resource.clone());
                 let _ = register.register(resource).await;
             }
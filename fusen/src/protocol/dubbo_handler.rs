@@ -0,0 +1,123 @@
+use super::{StreamHandler, DUBBO_WRITE_BUFFER};
+use crate::{
+    codec::dubbo_codec::{DubboCodec, DubboFrame, STATUS_BAD_REQUEST},
+    filter::server::RpcServerFilter,
+    handler::HandlerContext,
+    support::deadline,
+};
+use bytes::{Bytes, BytesMut};
+use fusen_common::{error::FusenError, ConnectionInfo};
+use std::sync::Arc;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    sync::{broadcast, mpsc},
+};
+use tracing::debug;
+
+impl StreamHandler {
+    pub async fn run_dubbo(mut self) {
+        let connection = ConnectionInfo::default()
+            .remote_addr(self.tcp_stream.peer_addr().ok())
+            .local_addr(self.tcp_stream.local_addr().ok())
+            .protocol("dubbo".to_owned());
+        let (mut reader, mut writer) = self.tcp_stream.into_split();
+        let (sender, mut receiver) = mpsc::channel::<Bytes>(DUBBO_WRITE_BUFFER);
+        let writer_task = tokio::spawn(async move {
+            while let Some(bytes) = receiver.recv().await {
+                if writer.write_all(&bytes).await.is_err() {
+                    break;
+                }
+            }
+        });
+        //连接断开时通知正在处理的请求取消执行
+        let (cancel, _) = broadcast::channel::<()>(1);
+        let mut buf = BytesMut::with_capacity(1024);
+        let err_info = 'read: loop {
+            let res = tokio::select! {
+                res = reader.read_buf(&mut buf) => res,
+                res2 = self.shutdown.recv() => match res2 {
+                    Ok(_) => break "dubbo shutdown error".to_string(),
+                    Err(_) => break "dubbo server shutdown".to_string(),
+                }
+            };
+            match res {
+                Ok(0) => break "client close".to_string(),
+                Ok(_) => (),
+                Err(err) => break err.to_string(),
+            }
+            loop {
+                match DubboFrame::decode(&mut buf) {
+                    Ok(Some(frame)) => {
+                        if !frame.is_request() {
+                            continue;
+                        }
+                        if frame.is_event() {
+                            if frame.is_two_way() {
+                                let _ = sender.send(frame.heartbeat_response().encode()).await;
+                            }
+                            continue;
+                        }
+                        //先占用写出队列的位置,队列已满时不再读取新的请求
+                        let permit = match sender.clone().reserve_owned().await {
+                            Ok(permit) => permit,
+                            Err(_) => break 'read "dubbo writer closed".to_string(),
+                        };
+                        let mut cancelled = cancel.subscribe();
+                        let call = Self::call_dubbo(
+                            frame,
+                            self.route,
+                            self.handler_context.clone(),
+                            permit,
+                            connection.clone(),
+                        );
+                        tokio::spawn(async move {
+                            tokio::select! {
+                                _ = call => (),
+                                _ = cancelled.recv() => debug!("dubbo request cancelled"),
+                            }
+                        });
+                    }
+                    Ok(None) => break,
+                    Err(err) => break 'read err.to_string(),
+                }
+            }
+        };
+        drop(cancel);
+        drop(sender);
+        let _ = writer_task.await;
+        debug!("dubbo connect close by {}", err_info);
+    }
+
+    async fn call_dubbo(
+        frame: DubboFrame,
+        route: &'static RpcServerFilter,
+        handler_context: Arc<HandlerContext>,
+        permit: mpsc::OwnedPermit<Bytes>,
+        connection: ConnectionInfo,
+    ) {
+        let codec = DubboCodec;
+        let response = match codec.decode_request(&frame) {
+            Ok((mut context, with_attachments)) => {
+                context.set_connection(connection);
+                let handler = handler_context
+                    .get_controller(&context.get_context_info().get_handler_key())
+                    .get_aspect();
+                let deadline = *context.get_deadline();
+                let context = match deadline::scope(deadline, handler.aroud_(route, context)).await
+                {
+                    Ok(context) => context.map_err(FusenError::from),
+                    Err(err) => Err(err),
+                };
+                codec.encode_response(*frame.get_id(), with_attachments, context)
+            }
+            Err(err) => {
+                let mut encoder = crate::support::hessian::HessianEncoder::new();
+                encoder.write_string(&err.to_string());
+                DubboFrame::new_response(*frame.get_id(), STATUS_BAD_REQUEST, encoder.into_bytes())
+            }
+        };
+        if frame.is_two_way() {
+            permit.send(response.encode());
+        }
+    }
+}
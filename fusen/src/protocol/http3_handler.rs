@@ -0,0 +1,275 @@
+use super::MAX_BODY_LENGTH;
+use crate::{filter::server::RpcServerFilter, route::server::FusenRouter};
+use bytes::{Buf, Bytes, BytesMut};
+use fusen_common::{ConnectionInfo, TlsInfo};
+use h3::{error::Code, server::RequestStream};
+use http::{Request, Response, StatusCode};
+use http_body_util::{BodyExt, Full};
+use std::{net::SocketAddr, sync::Arc};
+use tokio::sync::{broadcast, mpsc};
+use tracing::debug;
+
+pub struct Http3Handler {
+    pub(crate) incoming: quinn::Incoming,
+    pub(crate) route: Arc<FusenRouter<RpcServerFilter>>,
+    pub(crate) local_port: u16,
+    pub(crate) shutdown: broadcast::Receiver<()>,
+    pub(crate) _shutdown_complete: mpsc::Sender<()>,
+}
+
+impl Http3Handler {
+    pub async fn run(mut self) {
+        let err_info = tokio::select! {
+            res = Self::serve_connection(self.incoming, self.route, self.local_port) => match res {
+                Ok(_) => "client close".to_string(),
+                Err(err) => err.to_string(),
+            },
+            res2 = self.shutdown.recv() => match res2 {
+                Ok(_) => "http3 shutdown error".to_string(),
+                Err(_) => "http3 server shutdown".to_string(),
+            }
+        };
+        debug!("http3 connect close by {}", err_info);
+    }
+
+    async fn serve_connection(
+        incoming: quinn::Incoming,
+        route: Arc<FusenRouter<RpcServerFilter>>,
+        local_port: u16,
+    ) -> crate::Result<()> {
+        let connection = incoming.await?;
+        let closed = connection.clone();
+        debug!(
+            "http3 connection connect, addr: {:?}",
+            connection.remote_address()
+        );
+        let tls = connection
+            .handshake_data()
+            .and_then(|e| e.downcast::<quinn::crypto::rustls::HandshakeData>().ok())
+            .map(|e| {
+                TlsInfo::default()
+                    .alpn(e.protocol.map(|e| String::from_utf8_lossy(&e).into_owned()))
+                    .server_name(e.server_name)
+            });
+        let connection_info = ConnectionInfo::default()
+            .remote_addr(Some(connection.remote_address()))
+            .local_addr(
+                connection
+                    .local_ip()
+                    .map(|ip| SocketAddr::new(ip, local_port)),
+            )
+            .protocol("HTTP/3.0".to_owned())
+            .tls(tls);
+        let mut h3_conn: h3::server::Connection<h3_quinn::Connection, Bytes> =
+            h3::server::Connection::new(h3_quinn::Connection::new(connection)).await?;
+        loop {
+            match h3_conn.accept().await {
+                Ok(Some(resolver)) => {
+                    let route = route.clone();
+                    let closed = closed.clone();
+                    let connection_info = connection_info.clone();
+                    tokio::spawn(async move {
+                        match resolver.resolve_request().await {
+                            Ok((mut request, stream)) => {
+                                request.extensions_mut().insert(connection_info);
+                                //连接断开时取消正在处理的请求
+                                tokio::select! {
+                                    res = Self::call(request, stream, route) => if let Err(err) = res {
+                                        debug!("http3 request error : {:?}", err);
+                                    },
+                                    err = closed.closed() => debug!("http3 request cancelled : {:?}", err),
+                                }
+                            }
+                            Err(err) => debug!("http3 resolve request error : {:?}", err),
+                        }
+                    });
+                }
+                Ok(None) => return Ok(()),
+                Err(err) if err.is_h3_no_error() => return Ok(()),
+                Err(err) => return Err(err.into()),
+            }
+        }
+    }
+
+    async fn call(
+        request: Request<()>,
+        mut stream: RequestStream<h3_quinn::BidiStream<Bytes>, Bytes>,
+        route: Arc<FusenRouter<RpcServerFilter>>,
+    ) -> crate::Result<()> {
+        let mut body = BytesMut::new();
+        loop {
+            match stream.recv_data().await {
+                Ok(Some(mut chunk)) => {
+                    if body.len() + chunk.remaining() > MAX_BODY_LENGTH {
+                        let response = Response::builder()
+                            .status(StatusCode::PAYLOAD_TOO_LARGE)
+                            .body(())?;
+                        stream.send_response(response).await?;
+                        stream.stop_sending(Code::H3_NO_ERROR);
+                        return Ok(stream.finish().await?);
+                    }
+                    body.extend_from_slice(&chunk.copy_to_bytes(chunk.remaining()));
+                }
+                Ok(None) => break,
+                Err(err) => {
+                    stream.stop_stream(Code::H3_REQUEST_INCOMPLETE);
+                    return Err(err.into());
+                }
+            }
+        }
+        let request = request.map(|_| {
+            Full::new(body.freeze())
+                .map_err(|never| match never {})
+                .boxed()
+        });
+        let (parts, mut body) = route.handle(request).await.into_parts();
+        stream
+            .send_response(Response::from_parts(parts, ()))
+            .await?;
+        while let Some(frame) = body.frame().await {
+            let frame = match frame {
+                Ok(frame) => frame,
+                Err(never) => match never {},
+            };
+            match frame.into_data() {
+                Ok(data) => stream.send_data(data).await?,
+                Err(frame) => {
+                    if let Ok(trailers) = frame.into_trailers() {
+                        //发送trailers时会同时结束流
+                        return Ok(stream.send_trailers(trailers).await?);
+                    }
+                }
+            }
+        }
+        Ok(stream.finish().await?)
+    }
+}
+
+#[tokio::test]
+async fn test() {
+    use crate::{
+        codec::http_codec::FusenHttpCodec,
+        protocol::socket::Http3Socket,
+        support::tls::{http3_server_config, TlsConfig},
+    };
+    use fusen_common::{
+        openapi::{OpenApiConfig, OpenApiServer},
+        server::RpcServer,
+    };
+    use std::collections::HashMap;
+
+    let dir = std::env::temp_dir().join(format!("fusen-http3-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let certified_key = rcgen::generate_simple_self_signed(vec!["127.0.0.1".to_owned()]).unwrap();
+    let cert = dir.join("cert.pem").to_string_lossy().into_owned();
+    let key = dir.join("key.pem").to_string_lossy().into_owned();
+    std::fs::write(&cert, certified_key.cert.pem()).unwrap();
+    std::fs::write(&key, certified_key.key_pair.serialize_pem()).unwrap();
+    //未配置证书时只有显式开启insecure才会生成自签名证书
+    assert!(http3_server_config(&TlsConfig::default()).is_err());
+    let tls = TlsConfig::default().cert(Some(cert.clone())).key(Some(key));
+    let endpoint = quinn::Endpoint::server(
+        http3_server_config(&tls).unwrap(),
+        "127.0.0.1:0".parse().unwrap(),
+    )
+    .unwrap();
+    let local_port = endpoint.local_addr().unwrap().port();
+    let openapi: &'static dyn RpcServer =
+        Box::leak(Box::new(OpenApiServer::new(&OpenApiConfig::default(), &[])));
+    let filter = RpcServerFilter::new(
+        HashMap::from([("OpenApi".to_owned(), openapi)]),
+        vec![],
+        vec![],
+    )
+    .unwrap();
+    let filter: &'static RpcServerFilter = Box::leak(Box::new(filter));
+    let route = Arc::new(FusenRouter::new(
+        filter,
+        Arc::new(FusenHttpCodec::new(filter.get_path_cache())),
+        Default::default(),
+    ));
+    let (notify_shutdown, _) = broadcast::channel(1);
+    let (shutdown_complete_tx, _shutdown_complete_rx) = mpsc::channel(1);
+    tokio::spawn(async move {
+        while let Some(incoming) = endpoint.accept().await {
+            let handler = Http3Handler {
+                incoming,
+                route: route.clone(),
+                local_port,
+                shutdown: notify_shutdown.subscribe(),
+                _shutdown_complete: shutdown_complete_tx.clone(),
+            };
+            tokio::spawn(handler.run());
+        }
+    });
+    let addr = format!("127.0.0.1:{}", local_port);
+    let request = |method: &str, body: Bytes| {
+        Request::builder()
+            .method(method)
+            .uri("/openapi.json")
+            .body(Full::new(body).boxed())
+            .unwrap()
+    };
+    //使用系统根证书无法校验自签名证书
+    let socket = Http3Socket::new(Default::default());
+    assert!(socket
+        .send_request(&addr, request("GET", Bytes::new()))
+        .await
+        .is_err());
+    let socket = Http3Socket::new(Arc::new(TlsConfig::default().ca(Some(cert))));
+    let response = socket
+        .send_request(&addr, request("GET", Bytes::new()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let document: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(document["openapi"], "3.0.3");
+    let response = socket
+        .send_request(
+            &addr,
+            request("POST", Bytes::from(vec![0; MAX_BODY_LENGTH + 1])),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    //insecure模式不校验服务端证书
+    let socket = Http3Socket::new(Arc::new(TlsConfig::default().insecure(Some(true))));
+    let response = socket
+        .send_request(&addr, request("GET", Bytes::new()))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    //客户端读取响应体时同样限制最大长度
+    let endpoint = quinn::Endpoint::server(
+        http3_server_config(&tls).unwrap(),
+        "127.0.0.1:0".parse().unwrap(),
+    )
+    .unwrap();
+    let large_addr = format!("127.0.0.1:{}", endpoint.local_addr().unwrap().port());
+    tokio::spawn(async move {
+        let connection = endpoint.accept().await.unwrap().await.unwrap();
+        let mut h3_conn: h3::server::Connection<h3_quinn::Connection, Bytes> =
+            h3::server::Connection::new(h3_quinn::Connection::new(connection))
+                .await
+                .unwrap();
+        let resolver = h3_conn.accept().await.unwrap().unwrap();
+        let (_, mut stream) = resolver.resolve_request().await.unwrap();
+        stream
+            .send_response(Response::builder().body(()).unwrap())
+            .await
+            .unwrap();
+        let _ = stream
+            .send_data(Bytes::from(vec![0; MAX_BODY_LENGTH + 1]))
+            .await;
+        let _ = stream.finish().await;
+        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+    });
+    let socket = Http3Socket::new(Arc::new(TlsConfig::default().insecure(Some(true))));
+    let err = socket
+        .send_request(&large_addr, request("GET", Bytes::new()))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("exceeded max length"));
+    let _ = std::fs::remove_dir_all(dir);
+}
@@ -1,11 +1,16 @@
 use super::StreamHandler;
 use crate::route::server::FusenRouter;
+use fusen_common::ConnectionInfo;
 use hyper_util::rt::TokioIo;
 use tracing::debug;
 impl StreamHandler {
     pub async fn run_http(mut self) {
+        let connection = ConnectionInfo::default()
+            .remote_addr(self.tcp_stream.peer_addr().ok())
+            .local_addr(self.tcp_stream.local_addr().ok());
         let hyper_io = TokioIo::new(self.tcp_stream);
-        let route = FusenRouter::new(self.route, self.http_codec, self.handler_context);
+        let route = FusenRouter::new(self.route, self.http_codec, self.handler_context)
+            .connection(connection);
         let conn = self.builder.serve_connection(hyper_io, route);
         let err_info = tokio::select! {
                 res = conn =>
@@ -10,10 +10,18 @@ use crate::{
     codec::http_codec::FusenHttpCodec, filter::server::RpcServerFilter, handler::HandlerContext,
 };
 
+mod dubbo_handler;
+mod http3_handler;
 mod http_handler;
 pub mod server;
 pub mod socket;
 
+//HTTP3请求体与响应体的最大长度
+const MAX_BODY_LENGTH: usize = 8 * 1024 * 1024;
+
+//Dubbo连接的待写出报文数量上限,对端读取过慢时暂停读取新的请求
+const DUBBO_WRITE_BUFFER: usize = 1024;
+
 pub struct StreamHandler {
     builder: Arc<Builder<TokioExecutor>>,
     tcp_stream: TcpStream,
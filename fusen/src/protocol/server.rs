@@ -1,11 +1,13 @@
+use crate::codec::dubbo_codec::MAGIC;
 use crate::codec::http_codec::FusenHttpCodec;
 use crate::filter::server::RpcServerFilter;
 use crate::handler::HandlerContext;
+use crate::protocol::http3_handler::Http3Handler;
 use crate::protocol::StreamHandler;
+use crate::route::server::FusenRouter;
 use crate::support::shutdown::Shutdown;
-use fusen_common::server::RpcServer;
+use crate::support::tls::{http3_server_config, TlsConfig};
 use hyper_util::rt::TokioExecutor;
-use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::net::TcpListener;
 use tokio::sync::mpsc::Receiver;
@@ -15,14 +17,23 @@ use tracing::{debug, error};
 #[derive(Clone)]
 pub struct TcpServer {
     port: String,
-    fusen_servers: HashMap<String, &'static dyn RpcServer>,
+    http3: bool,
+    route: &'static RpcServerFilter,
+    tls: TlsConfig,
 }
 
 impl TcpServer {
-    pub fn init(port: String, fusen_servers: HashMap<String, &'static dyn RpcServer>) -> Self {
+    pub fn init(
+        port: String,
+        http3: bool,
+        route: &'static RpcServerFilter,
+        tls: TlsConfig,
+    ) -> Self {
         TcpServer {
             port,
-            fusen_servers,
+            http3,
+            route,
+            tls,
         }
     }
     pub async fn run(
@@ -31,9 +42,23 @@ impl TcpServer {
         handler_context: Arc<HandlerContext>,
     ) -> Receiver<()> {
         let (shutdown_complete_tx, shutdown_complete_rx) = mpsc::channel(1);
-        let route = Box::leak(Box::new(RpcServerFilter::new(self.fusen_servers)));
+        let route = self.route;
         let http_codec = Arc::new(FusenHttpCodec::new(route.get_path_cache()));
         let port = self.port;
+        if self.http3 {
+            //HTTP3与TCP监听同一端口号的UDP
+            tokio::spawn(Self::monitor_http3(
+                port.clone(),
+                self.tls,
+                Arc::new(FusenRouter::new(
+                    route,
+                    http_codec.clone(),
+                    handler_context.clone(),
+                )),
+                shutdown.resubscribe(),
+                shutdown_complete_tx.clone(),
+            ));
+        }
         tokio::spawn(Self::monitor(
             port,
             route,
@@ -80,10 +105,61 @@ impl TcpServer {
                         _shutdown_complete: shutdown_complete_tx.clone(),
                     };
                     debug!("socket stream connect, addr: {:?}", stream.1);
-                    tokio::spawn(stream_handler.run_http());
+                    tokio::spawn(async move {
+                        //根据首字节区分Dubbo协议与HTTP协议
+                        let mut magic = [0_u8; 1];
+                        match stream_handler.tcp_stream.peek(&mut magic).await {
+                            Ok(1) if magic[0] == MAGIC[0] => stream_handler.run_dubbo().await,
+                            _ => stream_handler.run_http().await,
+                        }
+                    });
                 }
                 Err(err) => error!("tcp connect, err: {:?}", err),
             }
         }
     }
+
+    async fn monitor_http3(
+        port: String,
+        tls: TlsConfig,
+        route: Arc<FusenRouter<RpcServerFilter>>,
+        mut shutdown: Shutdown,
+        shutdown_complete_tx: mpsc::Sender<()>,
+    ) -> crate::Result<()> {
+        let notify_shutdown = broadcast::channel(1).0;
+        let server_config = http3_server_config(&tls).map_err(|err| {
+            error!("http3 server tls, err: {:?}", err);
+            err
+        })?;
+        let endpoint = quinn::Endpoint::server(server_config, format!("0.0.0.0:{}", port).parse()?)
+            .map_err(|err| {
+                error!("http3 server bind, err: {:?}", err);
+                err
+            })?;
+        let local_port = endpoint.local_addr()?.port();
+        loop {
+            let incoming = tokio::select! {
+                _ = shutdown.recv() => {
+                    endpoint.close(0_u32.into(), b"shutdown");
+                    drop(notify_shutdown);
+                    drop(shutdown_complete_tx);
+                    return Ok(());
+                },
+                res = endpoint.accept() => res
+            };
+            match incoming {
+                Some(incoming) => {
+                    let handler = Http3Handler {
+                        incoming,
+                        route: route.clone(),
+                        local_port,
+                        shutdown: notify_shutdown.subscribe(),
+                        _shutdown_complete: shutdown_complete_tx.clone(),
+                    };
+                    tokio::spawn(handler.run());
+                }
+                None => return Ok(()),
+            }
+        }
+    }
 }
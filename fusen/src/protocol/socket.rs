@@ -1,12 +1,22 @@
+use super::{DUBBO_WRITE_BUFFER, MAX_BODY_LENGTH};
+use crate::codec::dubbo_codec::{next_request_id, DubboFrame};
+use crate::support::tls::{get_client_tls, http3_client_config, TlsConfig};
+use crate::StreamBody;
+use bytes::{Buf, Bytes, BytesMut};
 use fusen_common::error::FusenError;
 use fusen_procedural_macro::Data;
-use http::{Request, Response, Uri, Version};
-use http_body_util::combinators::BoxBody;
-use hyper::body::Incoming;
+use http::{uri::InvalidUri, Request, Response, Uri, Version};
+use http_body::Frame;
+use http_body_util::{combinators::BoxBody, BodyExt};
 use hyper_tls::HttpsConnector;
 use hyper_util::client::legacy::{connect::HttpConnector, Client};
-use std::{convert::Infallible, time::Duration};
-use tracing::error;
+use std::{collections::HashMap, convert::Infallible, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    sync::{mpsc, oneshot},
+};
+use tracing::{debug, error};
 pub type HttpSocket = Client<HttpsConnector<HttpConnector>, BoxBody<bytes::Bytes, Infallible>>;
 use crate::register::Resource;
 
@@ -20,13 +30,19 @@ pub struct InvokerAssets {
 pub enum Socket {
     HTTP1(HttpSocket),
     HTTP2(HttpSocket),
+    HTTP3(Http3Socket),
+    Dubbo(DubboSocket),
 }
 
 impl Socket {
     pub fn new(protocol: Option<&str>) -> Self {
         let mut connector = HttpConnector::new();
         connector.set_keepalive(Some(Duration::from_secs(90)));
-        if protocol.is_some_and(|e| e.to_lowercase().contains("http2")) {
+        if protocol.is_some_and(|e| e.to_lowercase().contains("dubbo")) {
+            Socket::Dubbo(DubboSocket::default())
+        } else if protocol.is_some_and(|e| e.to_lowercase().contains("http3")) {
+            Socket::HTTP3(Http3Socket::new(get_client_tls()))
+        } else if protocol.is_some_and(|e| e.to_lowercase().contains("http2")) {
             Socket::HTTP2(
                 Client::builder(hyper_util::rt::TokioExecutor::new())
                     .http2_only(true)
@@ -48,7 +64,7 @@ impl InvokerAssets {
     pub async fn send_request(
         &self,
         mut request: Request<BoxBody<bytes::Bytes, Infallible>>,
-    ) -> Result<Response<Incoming>, FusenError> {
+    ) -> Result<Response<BoxBody<Bytes, hyper::Error>>, FusenError> {
         match &self.socket {
             Socket::HTTP1(client) => {
                 *request.version_mut() = Version::HTTP_11;
@@ -58,7 +74,262 @@ impl InvokerAssets {
                 *request.version_mut() = Version::HTTP_2;
                 send_http_request(client, &self.resource, request).await
             }
+            Socket::HTTP3(socket) => {
+                *request.version_mut() = Version::HTTP_3;
+                socket
+                    .send_request(&self.resource.get_addr(), request)
+                    .await
+            }
+            Socket::Dubbo(_) => Err(FusenError::from("dubbo socket not support http request")),
+        }
+    }
+
+    pub async fn send_dubbo_request(&self, frame: DubboFrame) -> Result<DubboFrame, FusenError> {
+        match &self.socket {
+            Socket::Dubbo(socket) => socket.send_request(&self.resource.get_addr(), frame).await,
+            _ => Err(FusenError::from("http socket not support dubbo request")),
+        }
+    }
+}
+
+type Http3Sender = h3::client::SendRequest<h3_quinn::OpenStreams, Bytes>;
+
+pub struct Http3Socket {
+    tls: Arc<TlsConfig>,
+    connection: tokio::sync::Mutex<Option<Http3Connection>>,
+}
+
+impl std::fmt::Debug for Http3Socket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Http3Socket").finish_non_exhaustive()
+    }
+}
+
+struct Http3Connection {
+    _endpoint: quinn::Endpoint,
+    connection: quinn::Connection,
+    sender: Http3Sender,
+}
+
+impl Http3Socket {
+    pub fn new(tls: Arc<TlsConfig>) -> Self {
+        Http3Socket {
+            tls,
+            connection: Default::default(),
+        }
+    }
+
+    async fn get_sender(&self, uri: &Uri) -> Result<Http3Sender, FusenError> {
+        let mut connection = self.connection.lock().await;
+        if let Some(connection) = connection.as_ref() {
+            if connection.connection.close_reason().is_none() {
+                return Ok(connection.sender.clone());
+            }
+        }
+        let new_connection = Http3Connection::connect(uri, &self.tls)
+            .await
+            .map_err(|e| {
+                error!("http3 connect error : {:?}", e);
+                FusenError::from(e.to_string())
+            })?;
+        let sender = new_connection.sender.clone();
+        let _ = connection.insert(new_connection);
+        Ok(sender)
+    }
+
+    pub async fn send_request(
+        &self,
+        addr: &str,
+        request: Request<BoxBody<Bytes, Infallible>>,
+    ) -> Result<Response<BoxBody<Bytes, hyper::Error>>, FusenError> {
+        let temp_url: Uri = addr.parse().map_err(|e: InvalidUri| e.to_string())?;
+        let mut sender = self.get_sender(&temp_url).await?;
+        let (mut parts, mut body) = request.into_parts();
+        parts.uri = Uri::builder()
+            .scheme("https")
+            .authority(temp_url.authority().map_or(addr, |e| e.as_str()))
+            .path_and_query(parts.uri.path_and_query().map_or("/", |e| e.as_str()))
+            .build()?;
+        let send = async move {
+            let mut stream = sender.send_request(Request::from_parts(parts, ())).await?;
+            while let Some(frame) = body.frame().await {
+                let frame = match frame {
+                    Ok(frame) => frame,
+                    Err(never) => match never {},
+                };
+                if let Ok(data) = frame.into_data() {
+                    stream.send_data(data).await?;
+                }
+            }
+            stream.finish().await?;
+            let response = stream.recv_response().await?;
+            //HTTP3的响应体读取完毕后再交给解码器,保持与hyper的Body一致,长度限制与服务端相同
+            let mut frames = vec![];
+            let mut length = 0;
+            while let Some(mut chunk) = stream.recv_data().await? {
+                length += chunk.remaining();
+                if length > MAX_BODY_LENGTH {
+                    stream.stop_sending(h3::error::Code::H3_NO_ERROR);
+                    return Err("http3 response body exceeded max length".into());
+                }
+                frames.push(Ok(Frame::data(chunk.copy_to_bytes(chunk.remaining()))));
+            }
+            if let Some(trailers) = stream.recv_trailers().await? {
+                frames.push(Ok(Frame::trailers(trailers)));
+            }
+            Ok::<_, crate::Error>(
+                response.map(|_| StreamBody::new(futures_util::stream::iter(frames)).boxed()),
+            )
+        };
+        send.await.map_err(|e| {
+            error!("http3 request error : {:?}", e);
+            FusenError::from(e.to_string())
+        })
+    }
+}
+
+impl Http3Connection {
+    async fn connect(uri: &Uri, tls: &TlsConfig) -> Result<Self, crate::Error> {
+        let host = uri.host().ok_or("http3 addr must have host")?;
+        let port = uri.port_u16().unwrap_or(443);
+        let addr = tokio::net::lookup_host((host, port))
+            .await?
+            .next()
+            .ok_or("http3 addr can not resolve")?;
+        let mut endpoint = quinn::Endpoint::client(if addr.is_ipv4() {
+            "0.0.0.0:0".parse()?
+        } else {
+            "[::]:0".parse()?
+        })?;
+        endpoint.set_default_client_config(http3_client_config(tls)?);
+        let connection = endpoint.connect(addr, host)?.await?;
+        let (mut driver, sender) =
+            h3::client::new(h3_quinn::Connection::new(connection.clone())).await?;
+        tokio::spawn(async move {
+            let err = driver.wait_idle().await;
+            debug!("http3 connection close : {:?}", err);
+        });
+        Ok(Http3Connection {
+            _endpoint: endpoint,
+            connection,
+            sender,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct DubboSocket {
+    connection: tokio::sync::Mutex<Option<Arc<DubboConnection>>>,
+}
+
+type DubboPending = Arc<std::sync::Mutex<HashMap<i64, oneshot::Sender<DubboFrame>>>>;
+
+#[derive(Debug)]
+struct DubboConnection {
+    sender: mpsc::Sender<Bytes>,
+    pending: DubboPending,
+}
+
+impl DubboSocket {
+    async fn get_connection(&self, addr: &str) -> Result<Arc<DubboConnection>, FusenError> {
+        let mut connection = self.connection.lock().await;
+        if let Some(connection) = connection.as_ref() {
+            if !connection.sender.is_closed() {
+                return Ok(connection.clone());
+            }
         }
+        let new_connection = Arc::new(DubboConnection::connect(addr).await?);
+        let _ = connection.insert(new_connection.clone());
+        Ok(new_connection)
+    }
+
+    pub async fn send_request(
+        &self,
+        addr: &str,
+        frame: DubboFrame,
+    ) -> Result<DubboFrame, FusenError> {
+        let connection = self.get_connection(addr).await?;
+        let (sender, receiver) = oneshot::channel();
+        connection
+            .pending
+            .lock()
+            .unwrap()
+            .insert(*frame.get_id(), sender);
+        if connection.sender.send(frame.encode()).await.is_err() {
+            connection.pending.lock().unwrap().remove(frame.get_id());
+            return Err(FusenError::from("dubbo connection closed"));
+        }
+        receiver
+            .await
+            .map_err(|_| FusenError::from("dubbo connection closed"))
+    }
+}
+
+impl DubboConnection {
+    async fn connect(addr: &str) -> Result<Self, FusenError> {
+        let tcp_stream = TcpStream::connect(addr).await.map_err(|e| {
+            error!("dubbo connect error : {:?}", e);
+            FusenError::from(e.to_string())
+        })?;
+        let (mut reader, mut writer) = tcp_stream.into_split();
+        let (sender, mut receiver) = mpsc::channel::<Bytes>(DUBBO_WRITE_BUFFER);
+        let pending: DubboPending = Default::default();
+        let writer_task = tokio::spawn(async move {
+            //空闲时发送心跳保持连接
+            let mut heartbeat = tokio::time::interval(Duration::from_secs(60));
+            heartbeat.tick().await;
+            loop {
+                let bytes = tokio::select! {
+                    bytes = receiver.recv() => match bytes {
+                        Some(bytes) => bytes,
+                        None => break,
+                    },
+                    _ = heartbeat.tick() => DubboFrame::new_heartbeat(next_request_id()).encode(),
+                };
+                if let Err(err) = writer.write_all(&bytes).await {
+                    debug!("dubbo connection write error : {:?}", err);
+                    break;
+                }
+            }
+        });
+        let reader_pending = pending.clone();
+        let reader_sender = sender.clone();
+        tokio::spawn(async move {
+            let mut buf = BytesMut::with_capacity(1024);
+            'read: loop {
+                match reader.read_buf(&mut buf).await {
+                    Ok(0) => break,
+                    Ok(_) => (),
+                    Err(err) => {
+                        debug!("dubbo connection read error : {:?}", err);
+                        break;
+                    }
+                }
+                loop {
+                    match DubboFrame::decode(&mut buf) {
+                        Ok(Some(frame)) => {
+                            if frame.is_request() {
+                                if frame.is_event() && frame.is_two_way() {
+                                    let _ = reader_sender.send(frame.heartbeat_response().encode()).await;
+                                }
+                            } else if let Some(sender) =
+                                reader_pending.lock().unwrap().remove(frame.get_id())
+                            {
+                                let _ = sender.send(frame);
+                            }
+                        }
+                        Ok(None) => break,
+                        Err(err) => {
+                            error!("dubbo connection decode error : {:?}", err);
+                            break 'read;
+                        }
+                    }
+                }
+            }
+            writer_task.abort();
+            reader_pending.lock().unwrap().clear();
+        });
+        Ok(DubboConnection { sender, pending })
     }
 }
 
@@ -66,7 +337,7 @@ async fn send_http_request(
     client: &Client<HttpsConnector<HttpConnector>, BoxBody<bytes::Bytes, Infallible>>,
     resource: &Resource,
     mut request: Request<BoxBody<bytes::Bytes, Infallible>>,
-) -> Result<Response<Incoming>, FusenError> {
+) -> Result<Response<BoxBody<Bytes, hyper::Error>>, FusenError> {
     let org: &Uri = request.uri();
     let temp_url: Uri = resource.get_addr().parse().unwrap();
     let mut host = temp_url.host().unwrap().to_string();
@@ -83,5 +354,5 @@ async fn send_http_request(
         error!("error : {:?}", e);
         FusenError::from(e.to_string())
     })?;
-    Ok(response)
+    Ok(response.map(|e| e.boxed()))
 }
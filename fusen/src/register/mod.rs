@@ -161,14 +161,23 @@ impl Directory {
                             let key = get_path(item.host.clone(), item.port.as_deref());
                             res.push(match map.get(&format!("{}-{:?}", key, item.weight)) {
                                 Some(info) => info.clone(),
-                                None => Arc::new(InvokerAssets::new(
-                                    item,
-                                    Socket::new(if let Category::Service = category {
-                                        Some("http2")
-                                    } else {
-                                        None
-                                    }),
-                                )),
+                                None => {
+                                    let protocol = match item.params.get("protocol") {
+                                        Some(protocol) if protocol == "dubbo" => Some("dubbo"),
+                                        _ if item
+                                            .params
+                                            .get("http3")
+                                            .is_some_and(|e| e == "true") =>
+                                        {
+                                            Some("http3")
+                                        }
+                                        _ => match category {
+                                            Category::Service => Some("http2"),
+                                            _ => None,
+                                        },
+                                    };
+                                    Arc::new(InvokerAssets::new(item, Socket::new(protocol)))
+                                }
                             });
                         }
                         cache = Arc::new(ResourceInfo::new(res));
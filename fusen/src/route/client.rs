@@ -78,21 +78,35 @@ impl Route {
                         .version(version.map(|e| e.to_owned()))
                         .host(fusen_common::net::get_ip())
                         .params(context.get_meta_data().clone_map());
-                    let directory = if let fusen_common::register::Type::Host(host) =
-                        context.get_server_type()
-                    {
-                        let directory = Directory::new(Category::Server).await;
-                        let resource_server = Resource::default()
-                            .server_name(name.to_owned())
-                            .category(Category::Server)
-                            .host(host.clone());
-                        let _ = directory.change(vec![resource_server]).await;
-                        directory
-                    } else if let Some(register) = &self.register {
-                        register.subscribe(resource_server).await?
-                    } else {
-                        return Err("must set register".into());
-                    };
+                    let directory =
+                        if let fusen_common::register::Type::Host(host) = context.get_server_type()
+                        {
+                            let directory = Directory::new(Category::Server).await;
+                            let mut resource_server = Resource::default()
+                                .server_name(name.to_owned())
+                                .category(Category::Server)
+                                .host(host.clone());
+                            //直连Dubbo服务 dubbo://127.0.0.1:20880
+                            if let Some(host) = host.strip_prefix("dubbo://") {
+                                resource_server =
+                                    resource_server.host(host.to_owned()).params(HashMap::from([
+                                        ("protocol".to_owned(), "dubbo".to_owned()),
+                                    ]));
+                            }
+                            //直连HTTP3服务 h3://127.0.0.1:8081
+                            if let Some(host) = host.strip_prefix("h3://") {
+                                resource_server =
+                                    resource_server.host(host.to_owned()).params(HashMap::from([
+                                        ("http3".to_owned(), "true".to_owned()),
+                                    ]));
+                            }
+                            let _ = directory.change(vec![resource_server]).await;
+                            directory
+                        } else if let Some(register) = &self.register {
+                            register.subscribe(resource_server).await?
+                        } else {
+                            return Err("must set register".into());
+                        };
                     let oneshot = oneshot::channel();
                     self.sender
                         .send((RouteSender::CHANGE((key, directory.clone())), oneshot.0))?;
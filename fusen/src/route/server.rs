@@ -1,16 +1,22 @@
 use bytes::Bytes;
 use fusen_common::{
-    error::{BoxFusenError, FusenError},
-    FusenFuture,
+    codec::CodecType,
+    error::{BoxFusenError, FusenError, StatusCode},
+    ConnectionInfo, FusenFuture, MetaData,
 };
 use http_body_util::{combinators::BoxBody, BodyExt, Full};
-use hyper::{service::Service, Request, Response};
+use hyper::{
+    header::{HeaderValue, ACCESS_CONTROL_REQUEST_METHOD, ALLOW, ORIGIN},
+    service::Service,
+    Method, Request, Response,
+};
 use std::{convert::Infallible, sync::Arc};
 
 use crate::{
-    codec::{http_codec::FusenHttpCodec, HttpCodec},
+    codec::{http_codec::FusenHttpCodec, response_codec::get_grpc_status, HttpCodec},
     filter::FusenFilter,
     handler::HandlerContext,
+    support::deadline,
 };
 
 #[derive(Clone)]
@@ -18,6 +24,7 @@ pub struct FusenRouter<KF: 'static> {
     fusen_filter: &'static KF,
     http_codec: Arc<FusenHttpCodec>,
     handler_context: Arc<HandlerContext>,
+    connection: ConnectionInfo,
 }
 
 impl<KF> FusenRouter<KF>
@@ -33,21 +40,140 @@ where
             fusen_filter,
             http_codec,
             handler_context,
+            connection: Default::default(),
+        }
+    }
+
+    pub fn connection(mut self, connection: ConnectionInfo) -> Self {
+        self.connection = connection;
+        self
+    }
+
+    pub async fn handle(
+        &self,
+        request: Request<BoxBody<Bytes, hyper::Error>>,
+    ) -> Response<BoxBody<Bytes, Infallible>> {
+        let codec = MetaData::from(request.headers()).get_codec();
+        let method = request.method().clone();
+        let path = request.uri().path().to_owned();
+        let origin = request
+            .headers()
+            .get(ORIGIN)
+            .and_then(|e| e.to_str().ok())
+            .map(|e| e.to_owned());
+        let path_cache = self.http_codec.get_path_cache();
+        //配置了跨域策略的服务直接响应预检请求
+        let preflight = method == Method::OPTIONS
+            && request
+                .headers()
+                .contains_key(ACCESS_CONTROL_REQUEST_METHOD);
+        if let (true, Some(origin)) = (preflight, &origin) {
+            if let Some((class, methods)) = path_cache.get_allow(&path) {
+                if let Some(cors) = path_cache.get_cors(&class) {
+                    let mut response = Self::allow_response(&method, &methods);
+                    cors.preflight(origin, request.headers(), &methods, response.headers_mut());
+                    return response;
+                }
+            }
+        }
+        //NotFind时查询过的服务id,避免重复查询路由
+        let mut class = None;
+        let mut response = match Self::call(
+            request,
+            self.http_codec.clone(),
+            self.fusen_filter,
+            self.handler_context.clone(),
+        )
+        .await
+        {
+            Ok(response) => response,
+            //gRPC请求返回Trailers-Only响应
+            Err(fusen_error) if matches!(codec, CodecType::GRPC) => {
+                let mut response = Response::builder()
+                    .header("content-type", "application/grpc")
+                    .body(Full::new(Bytes::new()).boxed())
+                    .unwrap();
+                response.headers_mut().extend(get_grpc_status(fusen_error));
+                response
+            }
+            Err(FusenError::NotFind) => {
+                let allow = path_cache.get_allow(&path);
+                let response = match &allow {
+                    //路径存在但不支持该请求方法
+                    Some((_, methods)) if !methods.contains(&method.as_str()) => {
+                        Self::allow_response(&method, methods)
+                    }
+                    _ => Self::error_response(FusenError::NotFind),
+                };
+                class = Some(allow.map(|e| e.0));
+                response
+            }
+            Err(fusen_error) => Self::error_response(fusen_error),
+        };
+        if let (Some(origin), true) = (origin, path_cache.has_cors()) {
+            let class = class.unwrap_or_else(|| path_cache.get_class(method.as_str(), &path));
+            if let Some(cors) = class.and_then(|class| path_cache.get_cors(&class)) {
+                cors.apply(&origin, response.headers_mut());
+            }
+        }
+        response
+    }
+
+    fn error_response(fusen_error: FusenError) -> Response<BoxBody<Bytes, Infallible>> {
+        let status = match fusen_error {
+            FusenError::NotFind => 404,
+            ref err => err.code().http_status(),
+        };
+        Response::builder()
+            .status(status)
+            .header("content-type", "application/json")
+            .body(
+                Full::new(Bytes::from(
+                    serde_json::to_vec(&fusen_error.into_status()).unwrap_or_default(),
+                ))
+                .boxed(),
+            )
+            .unwrap()
+    }
+
+    //OPTIONS请求返回204,其余请求方法返回405,Allow为路径支持的请求方法
+    fn allow_response(method: &Method, methods: &[&str]) -> Response<BoxBody<Bytes, Infallible>> {
+        let mut response = if method == Method::OPTIONS {
+            Response::builder()
+                .status(204)
+                .body(Full::new(Bytes::new()).boxed())
+                .unwrap()
+        } else {
+            let mut response = Self::error_response(FusenError::status(
+                StatusCode::Unimplemented,
+                format!("method {} not allowed", method),
+            ));
+            *response.status_mut() = hyper::StatusCode::METHOD_NOT_ALLOWED;
+            response
+        };
+        let mut allow = methods.to_vec();
+        if !allow.contains(&"OPTIONS") {
+            allow.push("OPTIONS");
         }
+        if let Ok(allow) = HeaderValue::from_str(&allow.join(", ")) {
+            response.headers_mut().insert(ALLOW, allow);
+        }
+        response
     }
 
     async fn call(
-        request: Request<hyper::body::Incoming>,
+        request: Request<BoxBody<Bytes, hyper::Error>>,
         http_codec: Arc<FusenHttpCodec>,
         fusen_filter: &'static KF,
         handler_context: Arc<HandlerContext>,
     ) -> Result<Response<BoxBody<Bytes, Infallible>>, FusenError> {
-        let request = request.map(|e| e.boxed());
         let context = http_codec.decode(request).await?;
         let handler = handler_context
             .get_controller(&context.get_context_info().get_handler_key())
             .get_aspect();
-        let context = handler.aroud_(fusen_filter, context).await?;
+        //超过截止时间后取消处理,子调用继承剩余的超时时间
+        let deadline = *context.get_deadline();
+        let context = deadline::scope(deadline, handler.aroud_(fusen_filter, context)).await??;
         let response = http_codec.encode(context).await?;
         Ok(response)
     }
@@ -61,32 +187,17 @@ where
     type Error = BoxFusenError;
     type Future = FusenFuture<Result<Self::Response, Self::Error>>;
 
-    fn call(&self, req: Request<hyper::body::Incoming>) -> Self::Future {
-        let fusen_filter = self.fusen_filter;
-        let http_codec = self.http_codec.clone();
-        let handler_context = self.handler_context.clone();
-
-        Box::pin(async move {
-            Ok(
-                match Self::call(req, http_codec, fusen_filter, handler_context).await {
-                    Ok(response) => response,
-                    Err(fusen_error) => {
-                        let mut status = 500;
-                        if let FusenError::NotFind = fusen_error {
-                            status = 404;
-                        }
-                        Response::builder()
-                            .status(status)
-                            .body(
-                                Full::new(Bytes::copy_from_slice(
-                                    format!("{:?}", fusen_error).as_bytes(),
-                                ))
-                                .boxed(),
-                            )
-                            .unwrap()
-                    }
-                },
-            )
-        })
+    fn call(&self, mut req: Request<hyper::body::Incoming>) -> Self::Future {
+        let router = Self::new(
+            self.fusen_filter,
+            self.http_codec.clone(),
+            self.handler_context.clone(),
+        );
+        let connection = self
+            .connection
+            .clone()
+            .protocol(format!("{:?}", req.version()));
+        req.extensions_mut().insert(connection);
+        Box::pin(async move { Ok(router.handle(req.map(|e| e.boxed())).await) })
     }
 }
@@ -1,4 +1,9 @@
-use crate::{handler::HandlerContext, protocol::server::TcpServer, support::shutdown::Shutdown};
+use crate::{
+    filter::server::RpcServerFilter,
+    handler::HandlerContext,
+    protocol::server::TcpServer,
+    support::{cors::CorsConfig, shutdown::Shutdown, tls::TlsConfig},
+};
 use fusen_common::server::RpcServer;
 use fusen_procedural_macro::Data;
 use std::{collections::HashMap, sync::Arc};
@@ -6,13 +11,21 @@ use std::{collections::HashMap, sync::Arc};
 #[derive(Default, Data)]
 pub struct FusenServer {
     port: Option<String>,
+    http3: bool,
     fusen_servers: HashMap<String, &'static dyn RpcServer>,
     handler_context: Arc<HandlerContext>,
+    pass_through_headers: Vec<String>,
+    cors: Vec<CorsConfig>,
+    tls: TlsConfig,
+    //OpenAPI文档不注册到注册中心
+    openapi: Option<&'static dyn RpcServer>,
+    route: Option<&'static RpcServerFilter>,
 }
 
 impl FusenServer {
     pub fn new(
         port: Option<String>,
+        http3: bool,
         servers: HashMap<String, Box<dyn RpcServer>>,
         handler_context: Arc<HandlerContext>,
     ) -> FusenServer {
@@ -22,15 +35,38 @@ impl FusenServer {
         }
         FusenServer {
             port,
+            http3,
             fusen_servers,
             handler_context,
+            pass_through_headers: vec![],
+            cors: vec![],
+            tls: TlsConfig::default(),
+            openapi: None,
+            route: None,
         }
     }
 
+    //根据注册的服务构建路由,路由冲突时返回错误
+    pub fn init_route(&mut self) -> crate::Result<()> {
+        let mut fusen_servers = self.fusen_servers.clone();
+        if let Some(openapi) = self.openapi {
+            fusen_servers.insert(openapi.get_info().get_id().clone(), openapi);
+        }
+        let route = RpcServerFilter::new(
+            fusen_servers,
+            self.pass_through_headers.clone(),
+            self.cors.clone(),
+        )?;
+        self.route = Some(Box::leak(Box::new(route)));
+        Ok(())
+    }
+
     pub async fn run(&mut self, shutdown: Shutdown) -> tokio::sync::mpsc::Receiver<()> {
         let tcp_server = TcpServer::init(
             self.port.as_ref().expect("not set server port").clone(),
-            self.fusen_servers.clone(),
+            self.http3,
+            self.route.expect("route not initialized"),
+            self.tls.clone(),
         );
         tcp_server.run(shutdown, self.handler_context.clone()).await
     }
@@ -15,110 +15,148 @@
  * limitations under the License.
  */
 
-use std::collections::HashMap;
+use std::io::{Read, Write};
 
-use bytes::{Buf, BufMut, BytesMut};
+use bytes::Bytes;
 use flate2::{
-    read::{GzDecoder, GzEncoder},
+    read::{GzDecoder, ZlibDecoder},
+    write::{GzEncoder, ZlibEncoder},
     Compression,
 };
-use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
 
 pub const GRPC_ACCEPT_ENCODING: &str = "grpc-accept-encoding";
 pub const GRPC_ENCODING: &str = "grpc-encoding";
-
-#[derive(Debug, Clone, Copy)]
+pub const ACCEPT_ENCODING: &str = "accept-encoding";
+pub const CONTENT_ENCODING: &str = "content-encoding";
+pub const SUPPORT_ENCODINGS: &str = "gzip,deflate,zstd";
+//小于该长度的消息不做压缩
+pub const MIN_COMPRESS_SIZE: usize = 1024;
+//HTTP消息解压后的最大长度
+pub const MAX_DECOMPRESS_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
 pub enum CompressionEncoding {
     Gzip,
-}
-
-lazy_static! {
-    pub static ref COMPRESSIONS: HashMap<String, Option<CompressionEncoding>> = {
-        let mut v = HashMap::new();
-        v.insert("gzip".to_string(), Some(CompressionEncoding::Gzip));
-        v
-    };
+    Deflate,
+    Zstd,
 }
 
 impl CompressionEncoding {
-    pub fn from_accept_encoding(header: &http::HeaderMap) -> Option<CompressionEncoding> {
-        let accept_encoding = header.get(GRPC_ACCEPT_ENCODING)?;
-        let encodings = accept_encoding.to_str().ok()?;
-
-        encodings
-            .trim()
-            .split(',')
-            .map(|s| s.trim())
-            .into_iter()
-            .find_map(|s| match s {
-                "gzip" => Some(CompressionEncoding::Gzip),
-                _ => None,
-            })
+    pub fn from_name(name: &str) -> Option<CompressionEncoding> {
+        match name.trim().to_lowercase().as_str() {
+            "gzip" | "x-gzip" => Some(CompressionEncoding::Gzip),
+            "deflate" => Some(CompressionEncoding::Deflate),
+            "zstd" => Some(CompressionEncoding::Zstd),
+            _ => None,
+        }
     }
 
-    pub fn into_header_value(self) -> http::HeaderValue {
-        match self {
-            CompressionEncoding::Gzip => http::HeaderValue::from_static("gzip"),
+    //解析content-encoding/grpc-encoding,identity表示未压缩
+    pub fn from_encoding(encoding: Option<&String>) -> Result<Option<Self>, crate::Error> {
+        match encoding {
+            None => Ok(None),
+            Some(encoding) if encoding.eq_ignore_ascii_case("identity") => Ok(None),
+            Some(encoding) => Self::from_name(encoding)
+                .map(Some)
+                .ok_or_else(|| format!("not support encoding {}", encoding).into()),
         }
     }
-}
-
-pub fn compress(
-    encoding: CompressionEncoding,
-    src: &mut BytesMut,
-    dst: &mut BytesMut,
-    len: usize,
-) -> Result<(), std::io::Error> {
-    dst.reserve(len);
 
-    match encoding {
-        CompressionEncoding::Gzip => {
-            let mut en = GzEncoder::new(src.reader(), Compression::default());
-
-            let mut dst_writer = dst.writer();
+    //按照客户端声明的顺序选择第一个支持的压缩方式,忽略q=0的项
+    pub fn from_accept_encoding(accept_encoding: &str) -> Option<CompressionEncoding> {
+        accept_encoding.split(',').find_map(|item| {
+            let mut item = item.split(';');
+            let name = item.next()?;
+            let disabled = item.any(|e| {
+                e.trim()
+                    .strip_prefix("q=")
+                    .and_then(|q| q.trim().parse::<f32>().ok())
+                    .is_some_and(|q| q <= 0.0)
+            });
+            if disabled {
+                return None;
+            }
+            Self::from_name(name)
+        })
+    }
 
-            std::io::copy(&mut en, &mut dst_writer)?;
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompressionEncoding::Gzip => "gzip",
+            CompressionEncoding::Deflate => "deflate",
+            CompressionEncoding::Zstd => "zstd",
         }
     }
 
-    Ok(())
-}
-
-pub fn decompress(
-    encoding: CompressionEncoding,
-    src: &mut BytesMut,
-    dst: &mut BytesMut,
-    len: usize,
-) -> Result<(), std::io::Error> {
-    let capacity = len * 2;
-    dst.reserve(capacity);
-
-    match encoding {
-        CompressionEncoding::Gzip => {
-            let mut de = GzDecoder::new(src.reader());
+    pub fn compress(&self, src: &[u8]) -> Result<Bytes, std::io::Error> {
+        let buf = match self {
+            CompressionEncoding::Gzip => {
+                let mut encoder = GzEncoder::new(vec![], Compression::default());
+                encoder.write_all(src)?;
+                encoder.finish()?
+            }
+            CompressionEncoding::Deflate => {
+                let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+                encoder.write_all(src)?;
+                encoder.finish()?
+            }
+            CompressionEncoding::Zstd => zstd::encode_all(src, 0)?,
+        };
+        Ok(buf.into())
+    }
 
-            let mut dst_writer = dst.writer();
+    pub fn decoder<'a>(&self, src: &'a [u8]) -> Result<Box<dyn Read + 'a>, std::io::Error> {
+        Ok(match self {
+            CompressionEncoding::Gzip => Box::new(GzDecoder::new(src)),
+            CompressionEncoding::Deflate => Box::new(ZlibDecoder::new(src)),
+            CompressionEncoding::Zstd => Box::new(zstd::Decoder::new(src)?),
+        })
+    }
 
-            std::io::copy(&mut de, &mut dst_writer)?;
+    //解压后的长度超过max_size时立即返回错误,避免压缩炸弹耗尽内存
+    pub fn decompress(&self, src: &[u8], max_size: usize) -> Result<Bytes, std::io::Error> {
+        let mut buf = vec![];
+        self.decoder(src)?
+            .take(max_size as u64 + 1)
+            .read_to_end(&mut buf)?;
+        if buf.len() > max_size {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("decompressed message larger than max {}", max_size),
+            ));
         }
+        Ok(buf.into())
     }
-    Ok(())
 }
 
 #[test]
 fn test_compress() {
-    let mut src = BytesMut::with_capacity(super::consts::BUFFER_SIZE);
-    src.put(&b"test compress"[..]);
-    let mut dst = BytesMut::new();
-    let len = src.len();
-    src.reserve(len);
-
-    compress(CompressionEncoding::Gzip, &mut src, &mut dst, len).unwrap();
-    info!("src: {:?}, dst: {:?}", src, dst);
-
-    let mut de_dst = BytesMut::with_capacity(super::consts::BUFFER_SIZE);
-    let de_len = dst.len();
-    decompress(CompressionEncoding::Gzip, &mut dst, &mut de_dst, de_len).unwrap();
-
-    info!("src: {:?}, dst: {:?}", dst, de_dst);
+    let src = "test compress".repeat(100);
+    for encoding in [
+        CompressionEncoding::Gzip,
+        CompressionEncoding::Deflate,
+        CompressionEncoding::Zstd,
+    ] {
+        let dst = encoding.compress(src.as_bytes()).unwrap();
+        assert!(dst.len() < src.len());
+        assert_eq!(
+            encoding.decompress(&dst, src.len()).unwrap(),
+            src.as_bytes()
+        );
+        assert!(encoding.decompress(&dst, src.len() - 1).is_err());
+    }
+    //解压后远大于限制的消息
+    let bomb = CompressionEncoding::Gzip
+        .compress(&vec![0; 64 * 1024 * 1024])
+        .unwrap();
+    assert!(bomb.len() < 1024 * 1024);
+    assert!(CompressionEncoding::Gzip
+        .decompress(&bomb, MAX_DECOMPRESS_SIZE)
+        .is_err());
+    assert_eq!(
+        CompressionEncoding::from_accept_encoding("br, gzip;q=0, zstd;q=0.5"),
+        Some(CompressionEncoding::Zstd)
+    );
 }
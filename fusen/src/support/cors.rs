@@ -0,0 +1,143 @@
+use fusen_procedural_macro::Data;
+use http::{HeaderMap, HeaderValue};
+use serde::{Deserialize, Serialize};
+
+//服务的跨域策略,id对应服务id,allow_origins中的"*"表示允许任意来源,不能与allow_credentials同时使用
+#[derive(Serialize, Deserialize, Default, Clone, Debug, Data)]
+pub struct CorsConfig {
+    id: String,
+    allow_origins: Vec<String>,
+    //未配置时使用路径支持的请求方法
+    allow_methods: Option<Vec<String>>,
+    //未配置时使用预检请求中的Access-Control-Request-Headers
+    allow_headers: Option<Vec<String>>,
+    expose_headers: Option<Vec<String>>,
+    allow_credentials: Option<bool>,
+    max_age: Option<u64>,
+}
+
+impl CorsConfig {
+    pub fn new(id: String) -> Self {
+        CorsConfig {
+            id,
+            ..Default::default()
+        }
+    }
+
+    //携带凭证时浏览器不接受"*",也不能把任意来源原样返回,必须配置明确的来源
+    pub fn check(&self) -> Result<(), crate::Error> {
+        if self.allow_credentials.unwrap_or_default() && self.allow_origins.iter().any(|e| e == "*")
+        {
+            return Err(format!(
+                "cors {} : allow_credentials requires explicit allow_origins instead of \"*\"",
+                self.id
+            )
+            .into());
+        }
+        Ok(())
+    }
+
+    fn get_allow_origin(&self, origin: &str) -> Option<String> {
+        if self.allow_origins.iter().any(|e| e == origin) {
+            return Some(origin.to_owned());
+        }
+        self.allow_origins
+            .iter()
+            .any(|e| e == "*")
+            .then(|| "*".to_owned())
+    }
+
+    //为普通请求的响应添加跨域响应头,返回来源是否被允许
+    pub fn apply(&self, origin: &str, headers: &mut HeaderMap) -> bool {
+        let Some(allow_origin) = self.get_allow_origin(origin) else {
+            return false;
+        };
+        if allow_origin != "*" {
+            headers.append("vary", HeaderValue::from_static("origin"));
+        }
+        insert_header(headers, "access-control-allow-origin", &allow_origin);
+        if self.allow_credentials.unwrap_or_default() {
+            insert_header(headers, "access-control-allow-credentials", "true");
+        }
+        if let Some(expose_headers) = &self.expose_headers {
+            insert_header(
+                headers,
+                "access-control-expose-headers",
+                &expose_headers.join(", "),
+            );
+        }
+        true
+    }
+
+    //预检请求的响应头,methods为路径支持的请求方法
+    pub fn preflight(
+        &self,
+        origin: &str,
+        request_headers: &HeaderMap,
+        methods: &[&str],
+        headers: &mut HeaderMap,
+    ) {
+        if !self.apply(origin, headers) {
+            return;
+        }
+        let allow_methods = match &self.allow_methods {
+            Some(allow_methods) => allow_methods.join(", "),
+            None => methods.join(", "),
+        };
+        insert_header(headers, "access-control-allow-methods", &allow_methods);
+        let allow_headers = match &self.allow_headers {
+            Some(allow_headers) => Some(allow_headers.join(", ")),
+            None => request_headers
+                .get("access-control-request-headers")
+                .and_then(|e| e.to_str().ok())
+                .map(|e| e.to_owned()),
+        };
+        if let Some(allow_headers) = allow_headers {
+            insert_header(headers, "access-control-allow-headers", &allow_headers);
+        }
+        if let Some(max_age) = self.max_age {
+            insert_header(headers, "access-control-max-age", &max_age.to_string());
+        }
+    }
+}
+
+fn insert_header(headers: &mut HeaderMap, key: &'static str, value: &str) {
+    if let Ok(value) = HeaderValue::from_str(value) {
+        headers.insert(key, value);
+    }
+}
+
+#[test]
+fn test() {
+    let cors = CorsConfig::new("UserService".to_owned())
+        .allow_origins(vec!["https://a.com".to_owned()])
+        .expose_headers(Some(vec!["x-trace".to_owned()]))
+        .allow_credentials(Some(true))
+        .max_age(Some(600));
+    let mut headers = HeaderMap::new();
+    assert!(!cors.apply("https://b.com", &mut headers));
+    assert!(headers.is_empty());
+    let mut request_headers = HeaderMap::new();
+    request_headers.insert(
+        "access-control-request-headers",
+        HeaderValue::from_static("content-type"),
+    );
+    cors.preflight(
+        "https://a.com",
+        &request_headers,
+        &["GET", "PATCH"],
+        &mut headers,
+    );
+    assert_eq!(headers["access-control-allow-origin"], "https://a.com");
+    assert_eq!(headers["access-control-allow-credentials"], "true");
+    assert_eq!(headers["access-control-allow-methods"], "GET, PATCH");
+    assert_eq!(headers["access-control-allow-headers"], "content-type");
+    assert_eq!(headers["access-control-max-age"], "600");
+    assert_eq!(headers["vary"], "origin");
+    let cors = CorsConfig::new("UserService".to_owned()).allow_origins(vec!["*".to_owned()]);
+    let mut headers = HeaderMap::new();
+    assert!(cors.apply("https://b.com", &mut headers));
+    assert_eq!(headers["access-control-allow-origin"], "*");
+    let cors = cors.allow_credentials(Some(true));
+    assert!(cors.check().is_err());
+}
@@ -0,0 +1,260 @@
+use fusen_common::{
+    error::{FusenError, StatusCode},
+    MetaData,
+};
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+pub const GRPC_TIMEOUT: &str = "grpc-timeout";
+//fusen客户端传递的剩余超时时间,单位毫秒
+pub const FUSEN_TIMEOUT: &str = "fusen-timeout";
+//Dubbo2请求附件中的超时时间,单位毫秒
+pub const DUBBO_TIMEOUT: &str = "timeout";
+
+//grpc-timeout最多8位数字
+const GRPC_TIMEOUT_MAX_VALUE: u128 = 99_999_999;
+
+tokio::task_local! {
+    static DEADLINE: Instant;
+}
+
+pub fn parse_grpc_timeout(value: &str) -> Option<Duration> {
+    if value.len() < 2 || value.len() > 9 {
+        return None;
+    }
+    let (value, unit) = value.split_at(value.len() - 1);
+    let value: u64 = value.parse().ok()?;
+    match unit {
+        "H" => Some(Duration::from_secs(value * 3600)),
+        "M" => Some(Duration::from_secs(value * 60)),
+        "S" => Some(Duration::from_secs(value)),
+        "m" => Some(Duration::from_millis(value)),
+        "u" => Some(Duration::from_micros(value)),
+        "n" => Some(Duration::from_nanos(value)),
+        _ => None,
+    }
+}
+
+//选择能够表示该时长的最小单位,向上取整避免提前超时
+pub fn encode_grpc_timeout(timeout: Duration) -> String {
+    let nanos = timeout.as_nanos();
+    for (unit, scale) in [
+        ("n", 1),
+        ("u", 1_000),
+        ("m", 1_000_000),
+        ("S", 1_000_000_000),
+        ("M", 60_000_000_000),
+    ] {
+        let value = nanos.div_ceil(scale);
+        if value <= GRPC_TIMEOUT_MAX_VALUE {
+            return format!("{}{}", value, unit);
+        }
+    }
+    format!(
+        "{}H",
+        nanos
+            .div_ceil(3_600_000_000_000)
+            .min(GRPC_TIMEOUT_MAX_VALUE)
+    )
+}
+
+pub fn parse_millis(value: &str) -> Option<Duration> {
+    value.trim().parse().ok().map(Duration::from_millis)
+}
+
+//解析请求头中的超时时间
+pub fn get_deadline(meta_data: &MetaData) -> Option<Instant> {
+    meta_data
+        .get_value(GRPC_TIMEOUT)
+        .and_then(|e| parse_grpc_timeout(e))
+        .or_else(|| {
+            meta_data
+                .get_value(FUSEN_TIMEOUT)
+                .and_then(|e| parse_millis(e))
+        })
+        .map(|e| Instant::now() + e)
+}
+
+//当前请求剩余的截止时间,在服务端处理请求时发起的调用会自动继承
+pub fn current_deadline() -> Option<Instant> {
+    DEADLINE.try_with(|e| *e).ok()
+}
+
+pub fn deadline_exceeded() -> FusenError {
+    FusenError::status(StatusCode::DeadlineExceeded, "deadline exceeded")
+}
+
+//在截止时间内执行,超时后取消执行并返回DEADLINE_EXCEEDED,嵌套时取较早的截止时间
+pub async fn scope<F: Future>(
+    deadline: Option<Instant>,
+    future: F,
+) -> Result<F::Output, FusenError> {
+    let deadline = match (deadline, current_deadline()) {
+        (Some(deadline), Some(current)) => Some(deadline.min(current)),
+        (deadline, current) => deadline.or(current),
+    };
+    match deadline {
+        Some(deadline) => DEADLINE
+            .scope(deadline, tokio::time::timeout_at(deadline.into(), future))
+            .await
+            .map_err(|_| deadline_exceeded()),
+        None => Ok(future.await),
+    }
+}
+
+#[test]
+fn test() {
+    assert_eq!(parse_grpc_timeout("100m"), Some(Duration::from_millis(100)));
+    assert_eq!(parse_grpc_timeout("2S"), Some(Duration::from_secs(2)));
+    assert_eq!(parse_grpc_timeout("123456789m"), None);
+    assert_eq!(parse_grpc_timeout("10x"), None);
+    assert_eq!(encode_grpc_timeout(Duration::from_millis(1500)), "1500000u");
+    assert_eq!(encode_grpc_timeout(Duration::from_secs(1000)), "1000000m");
+    let timeout = Duration::from_secs(3600 * 24 * 365);
+    assert!(parse_grpc_timeout(&encode_grpc_timeout(timeout)).unwrap() >= timeout);
+}
+
+#[cfg(test)]
+struct DropGuard(std::sync::mpsc::Sender<&'static str>);
+
+#[cfg(test)]
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        let _ = self.0.send("drop");
+    }
+}
+
+#[tokio::test]
+async fn test_scope() {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let handler = async move {
+        let _guard = DropGuard(sender.clone());
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        let _ = sender.send("finish");
+    };
+    let start = Instant::now();
+    let err = scope(Some(start + Duration::from_millis(50)), handler)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), StatusCode::DeadlineExceeded);
+    assert!(start.elapsed() < Duration::from_secs(5));
+    //超时后处理过程被取消,不会执行完成
+    assert_eq!(receiver.try_recv(), Ok("drop"));
+    assert!(receiver.try_recv().is_err());
+    //嵌套时继承较早的截止时间
+    let deadline = Instant::now() + Duration::from_secs(1);
+    let inner = scope(Some(deadline), async {
+        scope(Some(deadline + Duration::from_secs(10)), async {
+            current_deadline()
+        })
+        .await
+    })
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(inner, Some(deadline));
+    assert_eq!(scope(None, async { 1 }).await.unwrap(), 1);
+}
+
+//接收结果时会阻塞当前线程,服务端需要运行在其他线程
+#[tokio::test(flavor = "multi_thread")]
+async fn test_cancel() {
+    use crate::{
+        filter::server::RpcServerFilter, handler::HandlerContext, protocol::server::TcpServer,
+        support::shutdown::Shutdown,
+    };
+    use fusen_common::{
+        server::{RpcServer, ServerInfo},
+        FusenContext, FusenFuture, MethodResource,
+    };
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    struct SlowServer(Mutex<std::sync::mpsc::Sender<&'static str>>);
+
+    impl RpcServer for SlowServer {
+        fn invoke(&'static self, context: FusenContext) -> FusenFuture<FusenContext> {
+            let sender = self.0.lock().unwrap().clone();
+            Box::pin(async move {
+                let _ = sender.send("start");
+                let _guard = DropGuard(sender.clone());
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                let _ = sender.send("finish");
+                context
+            })
+        }
+
+        fn get_info(&self) -> ServerInfo {
+            let method = MethodResource::new(
+                "sleep".to_owned(),
+                "/SlowServer/sleep".to_owned(),
+                "POST".to_owned(),
+            );
+            ServerInfo::new("SlowServer", None, None, vec![method])
+        }
+    }
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let server: &'static dyn RpcServer = Box::leak(Box::new(SlowServer(Mutex::new(sender))));
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let (notify, shutdown) = tokio::sync::broadcast::channel(1);
+    let route = RpcServerFilter::new(
+        HashMap::from([("SlowServer".to_owned(), server)]),
+        vec![],
+        vec![],
+    )
+    .unwrap();
+    let _shutdown_complete = TcpServer::init(
+        port.to_string(),
+        false,
+        Box::leak(Box::new(route)),
+        Default::default(),
+    )
+    .run(Shutdown::new(shutdown), Arc::new(HandlerContext::default()))
+    .await;
+    let recv = |timeout: u64| receiver.recv_timeout(Duration::from_secs(timeout));
+    let request = |headers: &str| {
+        format!(
+            "POST /SlowServer/sleep HTTP/1.1\r\nhost: 127.0.0.1\r\ncontent-type: application/json\r\ncontent-length: 2\r\n{}\r\n[]",
+            headers
+        )
+    };
+    let connect = || async {
+        for _ in 0..50 {
+            if let Ok(stream) = tokio::net::TcpStream::connect(("127.0.0.1", port)).await {
+                return stream;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("server not started");
+    };
+    //请求携带的超时时间到期后取消处理并返回DEADLINE_EXCEEDED
+    let mut stream = connect().await;
+    stream
+        .write_all(request(&format!("{}: 100\r\n", FUSEN_TIMEOUT)).as_bytes())
+        .await
+        .unwrap();
+    assert_eq!(recv(5), Ok("start"));
+    assert_eq!(recv(5), Ok("drop"));
+    let mut response = vec![0; 1024];
+    let len = stream.read(&mut response).await.unwrap();
+    let response = String::from_utf8_lossy(&response[..len]).into_owned();
+    assert!(response.contains("deadline exceeded"), "{}", response);
+    //连接断开后取消处理
+    let mut stream = connect().await;
+    stream.write_all(request("").as_bytes()).await.unwrap();
+    assert_eq!(recv(5), Ok("start"));
+    drop(stream);
+    assert_eq!(recv(5), Ok("drop"));
+    assert!(recv(1).is_err());
+    drop(notify);
+}
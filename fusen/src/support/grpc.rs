@@ -1,3 +1,7 @@
+use base64::{engine::general_purpose::STANDARD_NO_PAD, Engine};
+use fusen_common::error::{ErrorDetail, FusenStatus, StatusCode};
+use prost::Message;
+
 /// The `Status` type defines a logical error model that is suitable for different
 /// programming environments, including REST APIs and RPC APIs. It is used by
 /// \[gRPC\](<https://github.com/grpc>). The error model is designed to be:
@@ -34,22 +38,22 @@
 /// Example uses of this error model include:
 ///
 /// - Partial errors. If a service needs to return partial errors to the client,
-///      it may embed the `Status` in the normal response to indicate the partial
-///      errors.
+///   it may embed the `Status` in the normal response to indicate the partial
+///   errors.
 ///
 /// - Workflow errors. A typical workflow has multiple steps. Each step may
-///      have a `Status` message for error reporting purpose.
+///   have a `Status` message for error reporting purpose.
 ///
 /// - Batch operations. If a client uses batch request and batch response, the
-///      `Status` message should be used directly inside batch response, one for
-///      each error sub-response.
+///   `Status` message should be used directly inside batch response, one for
+///   each error sub-response.
 ///
 /// - Asynchronous operations. If an API call embeds asynchronous operation
-///      results in its response, the status of those operations should be
-///      represented directly using the `Status` message.
+///   results in its response, the status of those operations should be
+///   represented directly using the `Status` message.
 ///
 /// - Logging. If some API errors are stored in logs, the message `Status` could
-///      be used directly after any stripping needed for security/privacy reasons.
+///   be used directly after any stripping needed for security/privacy reasons.
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Status {
@@ -66,3 +70,63 @@ pub struct Status {
     #[prost(message, repeated, tag = "3")]
     pub details: ::prost::alloc::vec::Vec<::prost_types::Any>,
 }
+
+impl From<FusenStatus> for Status {
+    fn from(value: FusenStatus) -> Self {
+        Status {
+            code: value.get_code().as_i32(),
+            message: value.get_message().to_owned(),
+            details: value
+                .get_details()
+                .iter()
+                .map(|e| ::prost_types::Any {
+                    type_url: e.get_type_url().to_owned(),
+                    value: e.get_value().to_owned(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<Status> for FusenStatus {
+    fn from(value: Status) -> Self {
+        FusenStatus::new(StatusCode::from_i32(value.code), value.message).details(
+            value
+                .details
+                .into_iter()
+                .map(|e| ErrorDetail::new(e.type_url, e.value))
+                .collect(),
+        )
+    }
+}
+
+//grpc-status-details-bin为base64编码的google.rpc.Status
+pub fn encode_status_details(status: FusenStatus) -> String {
+    STANDARD_NO_PAD.encode(Status::from(status).encode_to_vec())
+}
+
+pub fn decode_status_details(value: &[u8]) -> Option<FusenStatus> {
+    //兼容带padding的实现
+    let end = value.iter().rposition(|e| *e != b'=').map_or(0, |e| e + 1);
+    let bytes = STANDARD_NO_PAD.decode(&value[..end]).ok()?;
+    Status::decode(bytes.as_slice()).ok().map(FusenStatus::from)
+}
+
+#[test]
+fn test() {
+    let status = FusenStatus::new(StatusCode::PermissionDenied, "denied".to_owned()).details(vec![
+        ErrorDetail::new(
+            "type.googleapis.com/google.rpc.ErrorInfo".to_owned(),
+            b"reason".to_vec(),
+        ),
+    ]);
+    let value = encode_status_details(status.clone());
+    assert_eq!(
+        decode_status_details(value.as_bytes()),
+        Some(status.clone())
+    );
+    //grpc-java发送的值带有padding
+    let value = base64::engine::general_purpose::STANDARD
+        .encode(Status::from(status.clone()).encode_to_vec());
+    assert_eq!(decode_status_details(value.as_bytes()), Some(status));
+}
@@ -0,0 +1,613 @@
+use bytes::{Buf, BufMut, BytesMut};
+use serde_json::{Map, Number, Value};
+
+//Hessian2序列化,只处理与JSON可以互相转换的数据类型
+#[derive(Default)]
+pub struct HessianEncoder {
+    buf: BytesMut,
+    class_defs: Vec<(String, Vec<String>)>,
+}
+
+impl HessianEncoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn into_bytes(self) -> bytes::Bytes {
+        self.buf.freeze()
+    }
+
+    pub fn write_null(&mut self) {
+        self.buf.put_u8(b'N');
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.buf.put_u8(if value { b'T' } else { b'F' });
+    }
+
+    pub fn write_int(&mut self, value: i32) {
+        if (-16..=47).contains(&value) {
+            self.buf.put_u8((0x90 + value) as u8);
+        } else if (-2048..=2047).contains(&value) {
+            self.buf.put_u8((0xc8 + (value >> 8)) as u8);
+            self.buf.put_u8(value as u8);
+        } else if (-262144..=262143).contains(&value) {
+            self.buf.put_u8((0xd4 + (value >> 16)) as u8);
+            self.buf.put_u16(value as u16);
+        } else {
+            self.buf.put_u8(b'I');
+            self.buf.put_i32(value);
+        }
+    }
+
+    pub fn write_long(&mut self, value: i64) {
+        if (-8..=15).contains(&value) {
+            self.buf.put_u8((0xe0 + value) as u8);
+        } else if (-2048..=2047).contains(&value) {
+            self.buf.put_u8((0xf8 + (value >> 8)) as u8);
+            self.buf.put_u8(value as u8);
+        } else if (-262144..=262143).contains(&value) {
+            self.buf.put_u8((0x3c + (value >> 16)) as u8);
+            self.buf.put_u16(value as u16);
+        } else if i32::try_from(value).is_ok() {
+            self.buf.put_u8(0x59);
+            self.buf.put_i32(value as i32);
+        } else {
+            self.buf.put_u8(b'L');
+            self.buf.put_i64(value);
+        }
+    }
+
+    pub fn write_double(&mut self, value: f64) {
+        if value == 0.0 {
+            self.buf.put_u8(0x5b);
+        } else if value == 1.0 {
+            self.buf.put_u8(0x5c);
+        } else if value.fract() == 0.0 && (i8::MIN as f64..=i8::MAX as f64).contains(&value) {
+            self.buf.put_u8(0x5d);
+            self.buf.put_i8(value as i8);
+        } else if value.fract() == 0.0 && (i16::MIN as f64..=i16::MAX as f64).contains(&value) {
+            self.buf.put_u8(0x5e);
+            self.buf.put_i16(value as i16);
+        } else {
+            self.buf.put_u8(b'D');
+            self.buf.put_f64(value);
+        }
+    }
+
+    pub fn write_string(&mut self, value: &str) {
+        //Hessian以UTF-16的字符数作为长度,增补字符按照两个代理项分别编码
+        let chars: Vec<u16> = value.encode_utf16().collect();
+        let mut chunks = chars.chunks(0x8000).peekable();
+        if chars.is_empty() {
+            self.buf.put_u8(0x00);
+            return;
+        }
+        while let Some(chunk) = chunks.next() {
+            let len = chunk.len();
+            if chunks.peek().is_some() {
+                self.buf.put_u8(b'R');
+                self.buf.put_u16(len as u16);
+            } else if len <= 31 {
+                self.buf.put_u8(len as u8);
+            } else if len <= 1023 {
+                self.buf.put_u8((0x30 + (len >> 8)) as u8);
+                self.buf.put_u8(len as u8);
+            } else {
+                self.buf.put_u8(b'S');
+                self.buf.put_u16(len as u16);
+            }
+            for ch in chunk {
+                let ch = *ch as u32;
+                if ch < 0x80 {
+                    self.buf.put_u8(ch as u8);
+                } else if ch < 0x800 {
+                    self.buf.put_u8((0xc0 + ((ch >> 6) & 0x1f)) as u8);
+                    self.buf.put_u8((0x80 + (ch & 0x3f)) as u8);
+                } else {
+                    self.buf.put_u8((0xe0 + ((ch >> 12) & 0xf)) as u8);
+                    self.buf.put_u8((0x80 + ((ch >> 6) & 0x3f)) as u8);
+                    self.buf.put_u8((0x80 + (ch & 0x3f)) as u8);
+                }
+            }
+        }
+    }
+
+    pub fn write_typed_list(&mut self, ty: &str, values: &[Value]) {
+        if values.len() <= 7 {
+            self.buf.put_u8(0x70 + values.len() as u8);
+            self.write_string(ty);
+        } else {
+            self.buf.put_u8(b'V');
+            self.write_string(ty);
+            self.write_int(values.len() as i32);
+        }
+        for value in values {
+            self.write_value(value);
+        }
+    }
+
+    pub fn write_list(&mut self, values: &[Value]) {
+        if values.len() <= 7 {
+            self.buf.put_u8(0x78 + values.len() as u8);
+        } else {
+            self.buf.put_u8(b'X');
+            self.write_int(values.len() as i32);
+        }
+        for value in values {
+            self.write_value(value);
+        }
+    }
+
+    pub fn write_map(&mut self, map: &Map<String, Value>) {
+        self.buf.put_u8(b'H');
+        for (key, value) in map {
+            self.write_string(key);
+            self.write_value(value);
+        }
+        self.buf.put_u8(b'Z');
+    }
+
+    //按照Java对象写出,类定义在同一个报文中只写一次
+    pub fn write_object(&mut self, class_name: &str, fields: &Map<String, Value>) {
+        let names: Vec<String> = fields.keys().cloned().collect();
+        let idx = match self
+            .class_defs
+            .iter()
+            .position(|(name, def)| name == class_name && def == &names)
+        {
+            Some(idx) => idx,
+            None => {
+                self.buf.put_u8(b'C');
+                self.write_string(class_name);
+                self.write_int(names.len() as i32);
+                for name in &names {
+                    self.write_string(name);
+                }
+                self.class_defs.push((class_name.to_owned(), names));
+                self.class_defs.len() - 1
+            }
+        };
+        if idx <= 0xf {
+            self.buf.put_u8(0x60 + idx as u8);
+        } else {
+            self.buf.put_u8(b'O');
+            self.write_int(idx as i32);
+        }
+        for value in fields.values() {
+            self.write_value(value);
+        }
+    }
+
+    pub fn write_value(&mut self, value: &Value) {
+        match value {
+            Value::Null => self.write_null(),
+            Value::Bool(value) => self.write_bool(*value),
+            Value::Number(number) => {
+                if let Some(value) = number.as_i64() {
+                    match i32::try_from(value) {
+                        Ok(value) => self.write_int(value),
+                        Err(_) => self.write_long(value),
+                    }
+                } else {
+                    self.write_double(number.as_f64().unwrap_or_default())
+                }
+            }
+            Value::String(value) => self.write_string(value),
+            Value::Array(values) => self.write_list(values),
+            Value::Object(map) => self.write_map(map),
+        }
+    }
+}
+
+//list,map,object的最大嵌套深度,防止恶意报文导致栈溢出
+const MAX_DEPTH: usize = 64;
+
+struct ClassDef {
+    name: String,
+    fields: Vec<String>,
+}
+
+pub struct HessianDecoder<'a> {
+    buf: &'a [u8],
+    refs: Vec<Value>,
+    types: Vec<String>,
+    class_defs: Vec<ClassDef>,
+    with_type: bool,
+    depth: usize,
+}
+
+impl<'a> HessianDecoder<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        HessianDecoder {
+            buf,
+            refs: vec![],
+            types: vec![],
+            class_defs: vec![],
+            with_type: false,
+            depth: 0,
+        }
+    }
+
+    pub fn has_remaining(&self) -> bool {
+        self.buf.has_remaining()
+    }
+
+    fn read_u8(&mut self) -> Result<u8, crate::Error> {
+        if !self.buf.has_remaining() {
+            return Err("hessian decode error : unexpected end of buffer".into());
+        }
+        Ok(self.buf.get_u8())
+    }
+
+    fn peek_u8(&self) -> Result<u8, crate::Error> {
+        self.buf
+            .first()
+            .copied()
+            .ok_or_else(|| "hessian decode error : unexpected end of buffer".into())
+    }
+
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], crate::Error> {
+        if self.buf.len() < len {
+            return Err("hessian decode error : unexpected end of buffer".into());
+        }
+        let (bytes, remaining) = self.buf.split_at(len);
+        self.buf = remaining;
+        Ok(bytes)
+    }
+
+    fn read_be(&mut self, len: usize) -> Result<i64, crate::Error> {
+        let bytes = self.read_bytes(len)?;
+        let mut value = 0_i64;
+        for byte in bytes {
+            value = (value << 8) | *byte as i64;
+        }
+        Ok(value)
+    }
+
+    pub fn read_int(&mut self) -> Result<i32, crate::Error> {
+        match self.read_value()? {
+            Value::Number(number) => number
+                .as_i64()
+                .map(|e| e as i32)
+                .ok_or_else(|| "hessian decode error : expect int".into()),
+            value => Err(format!("hessian decode error : expect int but {:?}", value).into()),
+        }
+    }
+
+    //长度来自对端,每个元素至少占用一个字节,超出剩余报文长度的一定是非法报文
+    fn read_len(&mut self) -> Result<usize, crate::Error> {
+        let len = self.read_int()?;
+        if len < 0 || len as usize > self.buf.len() {
+            return Err(format!("hessian decode error : bad length {}", len).into());
+        }
+        Ok(len as usize)
+    }
+
+    pub fn read_string(&mut self) -> Result<String, crate::Error> {
+        match self.read_value()? {
+            Value::String(value) => Ok(value),
+            Value::Null => Ok(String::new()),
+            value => Err(format!("hessian decode error : expect string but {:?}", value).into()),
+        }
+    }
+
+    fn read_string_chunk(&mut self, len: usize, chars: &mut Vec<u16>) -> Result<(), crate::Error> {
+        for _ in 0..len {
+            let ch = self.read_u8()? as u32;
+            let ch = if ch < 0x80 {
+                ch
+            } else if ch & 0xe0 == 0xc0 {
+                ((ch & 0x1f) << 6) | (self.read_u8()? as u32 & 0x3f)
+            } else if ch & 0xf0 == 0xe0 {
+                ((ch & 0x0f) << 12)
+                    | ((self.read_u8()? as u32 & 0x3f) << 6)
+                    | (self.read_u8()? as u32 & 0x3f)
+            } else {
+                return Err("hessian decode error : bad utf8 string".into());
+            };
+            chars.push(ch as u16);
+        }
+        Ok(())
+    }
+
+    fn read_string_by_code(&mut self, mut code: u8) -> Result<String, crate::Error> {
+        let mut chars = vec![];
+        loop {
+            let (len, last) = match code {
+                0x00..=0x1f => (code as usize, true),
+                0x30..=0x33 => (
+                    (((code - 0x30) as usize) << 8) + self.read_u8()? as usize,
+                    true,
+                ),
+                b'S' => (self.read_be(2)? as usize, true),
+                b'R' => (self.read_be(2)? as usize, false),
+                _ => return Err(format!("hessian decode error : bad string code {}", code).into()),
+            };
+            self.read_string_chunk(len, &mut chars)?;
+            if last {
+                break;
+            }
+            code = self.read_u8()?;
+        }
+        String::from_utf16(&chars).map_err(|e| e.to_string().into())
+    }
+
+    fn read_binary_by_code(&mut self, mut code: u8) -> Result<Vec<u8>, crate::Error> {
+        let mut data = vec![];
+        loop {
+            let (len, last) = match code {
+                0x20..=0x2f => ((code - 0x20) as usize, true),
+                0x34..=0x37 => (
+                    (((code - 0x34) as usize) << 8) + self.read_u8()? as usize,
+                    true,
+                ),
+                b'B' => (self.read_be(2)? as usize, true),
+                b'A' => (self.read_be(2)? as usize, false),
+                _ => return Err(format!("hessian decode error : bad binary code {}", code).into()),
+            };
+            data.extend_from_slice(self.read_bytes(len)?);
+            if last {
+                break;
+            }
+            code = self.read_u8()?;
+        }
+        Ok(data)
+    }
+
+    fn read_type(&mut self) -> Result<String, crate::Error> {
+        let code = self.peek_u8()?;
+        if matches!(code, 0x00..=0x1f | 0x30..=0x33 | b'S' | b'R') {
+            let code = self.read_u8()?;
+            let ty = self.read_string_by_code(code)?;
+            self.types.push(ty.clone());
+            Ok(ty)
+        } else {
+            let idx = self.read_int()? as usize;
+            self.types
+                .get(idx)
+                .cloned()
+                .ok_or_else(|| "hessian decode error : bad type ref".into())
+        }
+    }
+
+    fn read_class_def(&mut self) -> Result<(), crate::Error> {
+        let name = self.read_string()?;
+        let len = self.read_len()?;
+        let mut fields = vec![];
+        for _ in 0..len {
+            fields.push(self.read_string()?);
+        }
+        self.class_defs.push(ClassDef { name, fields });
+        Ok(())
+    }
+
+    fn read_list(&mut self, len: Option<usize>) -> Result<Value, crate::Error> {
+        let idx = self.refs.len();
+        self.refs.push(Value::Null);
+        let mut values = vec![];
+        match len {
+            Some(len) => {
+                for _ in 0..len {
+                    values.push(self.read_value()?);
+                }
+            }
+            None => {
+                while self.peek_u8()? != b'Z' {
+                    values.push(self.read_value()?);
+                }
+                self.read_u8()?;
+            }
+        }
+        let value = Value::Array(values);
+        self.refs[idx] = value.clone();
+        Ok(value)
+    }
+
+    fn read_map(&mut self) -> Result<Value, crate::Error> {
+        let idx = self.refs.len();
+        self.refs.push(Value::Null);
+        let mut map = Map::new();
+        while self.peek_u8()? != b'Z' {
+            let key = match self.read_value()? {
+                Value::String(key) => key,
+                key => key.to_string(),
+            };
+            map.insert(key, self.read_value()?);
+        }
+        self.read_u8()?;
+        let value = Value::Object(map);
+        self.refs[idx] = value.clone();
+        Ok(value)
+    }
+
+    fn read_object(&mut self, def_idx: usize) -> Result<Value, crate::Error> {
+        let class_def = self
+            .class_defs
+            .get(def_idx)
+            .ok_or("hessian decode error : bad class def ref")?;
+        let fields = class_def.fields.clone();
+        let mut map = Map::new();
+        if self.with_type {
+            map.insert("@type".to_owned(), Value::String(class_def.name.clone()));
+        }
+        let idx = self.refs.len();
+        self.refs.push(Value::Null);
+        for field in fields {
+            map.insert(field, self.read_value()?);
+        }
+        let value = Value::Object(map);
+        self.refs[idx] = value.clone();
+        Ok(value)
+    }
+
+    //对象中保留Java类名,写入"@type"字段,与fastjson保持一致
+    pub fn read_value_with_type(&mut self) -> Result<Value, crate::Error> {
+        self.with_type = true;
+        let value = self.read_value();
+        self.with_type = false;
+        value
+    }
+
+    pub fn read_value(&mut self) -> Result<Value, crate::Error> {
+        if self.depth >= MAX_DEPTH {
+            return Err("hessian decode error : exceeded max depth".into());
+        }
+        self.depth += 1;
+        let value = self.read_value_by_code();
+        self.depth -= 1;
+        value
+    }
+
+    fn read_value_by_code(&mut self) -> Result<Value, crate::Error> {
+        let mut code = self.read_u8()?;
+        //类定义之后紧跟着对象
+        while code == b'C' {
+            self.read_class_def()?;
+            code = self.read_u8()?;
+        }
+        let value = match code {
+            b'N' => Value::Null,
+            b'T' => Value::Bool(true),
+            b'F' => Value::Bool(false),
+            0x80..=0xbf => Value::from(code as i32 - 0x90),
+            0xc0..=0xcf => Value::from(((code as i32 - 0xc8) << 8) + self.read_u8()? as i32),
+            0xd0..=0xd7 => Value::from(((code as i32 - 0xd4) << 16) + self.read_be(2)? as i32),
+            b'I' => Value::from(self.read_be(4)? as i32),
+            0xd8..=0xef => Value::from(code as i64 - 0xe0),
+            0xf0..=0xff => Value::from(((code as i64 - 0xf8) << 8) + self.read_u8()? as i64),
+            0x38..=0x3f => Value::from(((code as i64 - 0x3c) << 16) + self.read_be(2)?),
+            0x59 => Value::from(self.read_be(4)? as i32 as i64),
+            b'L' => Value::from(self.read_be(8)?),
+            0x5b => Value::from(0.0),
+            0x5c => Value::from(1.0),
+            0x5d => Value::from(self.read_u8()? as i8 as f64),
+            0x5e => Value::from(self.read_be(2)? as i16 as f64),
+            0x5f => Value::from(self.read_be(4)? as i32 as f64 * 0.001),
+            b'D' => Number::from_f64(f64::from_bits(self.read_be(8)? as u64))
+                .map_or(Value::Null, Value::Number),
+            //日期统一转换为毫秒时间戳
+            0x4a => Value::from(self.read_be(8)?),
+            0x4b => Value::from(self.read_be(4)? as i32 as i64 * 60000),
+            0x00..=0x1f | 0x30..=0x33 | b'S' | b'R' => {
+                Value::String(self.read_string_by_code(code)?)
+            }
+            0x20..=0x2f | 0x34..=0x37 | b'B' | b'A' => Value::from(self.read_binary_by_code(code)?),
+            b'U' => {
+                self.read_type()?;
+                self.read_list(None)?
+            }
+            b'V' => {
+                self.read_type()?;
+                let len = self.read_len()?;
+                self.read_list(Some(len))?
+            }
+            b'W' => self.read_list(None)?,
+            b'X' => {
+                let len = self.read_len()?;
+                self.read_list(Some(len))?
+            }
+            0x70..=0x77 => {
+                self.read_type()?;
+                self.read_list(Some((code - 0x70) as usize))?
+            }
+            0x78..=0x7f => self.read_list(Some((code - 0x78) as usize))?,
+            b'M' => {
+                self.read_type()?;
+                self.read_map()?
+            }
+            b'H' => self.read_map()?,
+            b'O' => {
+                let def_idx = self.read_int()? as usize;
+                self.read_object(def_idx)?
+            }
+            0x60..=0x6f => self.read_object((code - 0x60) as usize)?,
+            b'Q' => {
+                let idx = self.read_int()? as usize;
+                self.refs
+                    .get(idx)
+                    .cloned()
+                    .ok_or("hessian decode error : bad ref")?
+            }
+            code => return Err(format!("hessian decode error : unknown code {}", code).into()),
+        };
+        Ok(value)
+    }
+}
+
+#[test]
+fn test() {
+    let value = serde_json::json!({
+        "str": "hello 世界 😀",
+        "long": 9_999_999_999_i64,
+        "int": -300,
+        "double": 3.25,
+        "list": [true, false, null, 1, 2, 3, 4, 5, 6, 7, 8],
+        "empty": ""
+    });
+    let mut encoder = HessianEncoder::new();
+    encoder.write_value(&value);
+    encoder.write_typed_list("[string", &[Value::from("a")]);
+    let bytes = encoder.into_bytes();
+    let mut decoder = HessianDecoder::new(&bytes);
+    assert_eq!(decoder.read_value().unwrap(), value);
+    assert_eq!(decoder.read_value().unwrap(), serde_json::json!(["a"]));
+    assert!(!decoder.has_remaining());
+    //Java Hessian2Output写出的对象: class Dto { String name; int age; }
+    let java_bytes = [
+        b'C', 0x03, b'D', b't', b'o', 0x92, 0x04, b'n', b'a', b'm', b'e', 0x03, b'a', b'g', b'e',
+        0x60, 0x02, b'h', b'i', 0xba,
+    ];
+    let mut decoder = HessianDecoder::new(&java_bytes);
+    assert_eq!(
+        decoder.read_value().unwrap(),
+        serde_json::json!({"name": "hi", "age": 42})
+    );
+    let mut decoder = HessianDecoder::new(&java_bytes);
+    assert_eq!(
+        decoder.read_value_with_type().unwrap(),
+        serde_json::json!({"@type": "Dto", "name": "hi", "age": 42})
+    );
+    let mut encoder = HessianEncoder::new();
+    let fields = serde_json::json!({"name": "hi", "age": 42});
+    encoder.write_object("Dto", fields.as_object().unwrap());
+    encoder.write_object("Dto", fields.as_object().unwrap());
+    let bytes = encoder.into_bytes();
+    let mut decoder = HessianDecoder::new(&bytes);
+    for _ in 0..2 {
+        assert_eq!(
+            decoder.read_value_with_type().unwrap(),
+            serde_json::json!({"@type": "Dto", "name": "hi", "age": 42})
+        );
+    }
+    assert!(!decoder.has_remaining());
+}
+
+#[test]
+fn test_malformed() {
+    let decode = |bytes: &[u8]| HessianDecoder::new(bytes).read_value();
+    //长度为负数
+    assert!(decode(&[b'X', 0x8f]).is_err());
+    //长度超出报文
+    assert!(decode(&[b'X', b'I', 0x7f, 0xff, 0xff, 0xff, 0x90]).is_err());
+    assert!(decode(&[b'V', 0x00, b'I', 0x7f, 0xff, 0xff, 0xff]).is_err());
+    //类定义的字段数超出报文
+    assert!(decode(&[b'C', 0x01, b'A', b'I', 0x7f, 0xff, 0xff, 0xff, 0x60]).is_err());
+    //报文截断
+    assert!(decode(&[b'S', 0x00, 0x05, b'a']).is_err());
+    assert!(decode(&[0x7a, 0x90]).is_err());
+    assert!(decode(&[b'H', 0x90]).is_err());
+    //引用不存在
+    assert!(decode(&[b'Q', 0x95]).is_err());
+    assert!(decode(&[b'O', 0x90]).is_err());
+    //嵌套过深
+    let mut bytes = vec![0x79; MAX_DEPTH];
+    bytes.push(b'N');
+    assert!(decode(&bytes).is_err());
+    let mut bytes = vec![0x79; MAX_DEPTH - 1];
+    bytes.push(b'N');
+    assert!(decode(&bytes).is_ok());
+    let mut bytes = vec![b'H'; 100_000];
+    bytes.push(b'N');
+    assert!(decode(&bytes).is_err());
+}
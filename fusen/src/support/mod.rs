@@ -1,3 +1,9 @@
+pub mod compression;
+pub mod cors;
+pub mod deadline;
 pub mod dubbo;
+pub mod grpc;
+pub mod hessian;
 pub mod shutdown;
+pub mod tls;
 pub mod triple;
@@ -16,6 +16,13 @@ impl Shutdown {
 }
 
 impl Shutdown {
+    pub fn resubscribe(&self) -> Self {
+        Shutdown {
+            shutdown: self.shutdown,
+            notify: self.notify.resubscribe(),
+        }
+    }
+
     pub fn is_shutdown(&self) -> bool {
         self.shutdown
     }
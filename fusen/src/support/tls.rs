@@ -0,0 +1,180 @@
+use fusen_procedural_macro::Data;
+use quinn::crypto::rustls::{QuicClientConfig, QuicServerConfig};
+use rustls::{
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider},
+    pki_types::{
+        pem::PemObject, CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName, UnixTime,
+    },
+    DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+use tracing::warn;
+
+const ALPN_H3: &[u8] = b"h3";
+
+//客户端通过注册中心创建连接,无法逐层传递配置,构建应用时设置一次
+static CLIENT_TLS: OnceLock<Arc<TlsConfig>> = OnceLock::new();
+
+//HTTP3的证书配置,cert,key,ca均为PEM文件路径
+#[derive(Serialize, Deserialize, Default, Clone, Debug, Data)]
+pub struct TlsConfig {
+    //服务端证书链与私钥
+    cert: Option<String>,
+    key: Option<String>,
+    //客户端校验服务端证书使用的根证书,未配置时使用系统根证书
+    ca: Option<String>,
+    //服务端未配置证书时生成自签名证书,客户端不校验服务端证书,仅用于测试环境
+    insecure: Option<bool>,
+}
+
+impl TlsConfig {
+    fn is_insecure(&self) -> bool {
+        self.insecure.unwrap_or_default()
+    }
+}
+
+pub(crate) fn init_client_tls(tls: TlsConfig) {
+    if CLIENT_TLS.set(Arc::new(tls)).is_err() {
+        warn!("http3 client tls config has been initialized");
+    }
+}
+
+pub(crate) fn get_client_tls() -> Arc<TlsConfig> {
+    CLIENT_TLS.get().cloned().unwrap_or_default()
+}
+
+pub fn http3_server_config(tls: &TlsConfig) -> Result<quinn::ServerConfig, crate::Error> {
+    let (certs, key) = match (&tls.cert, &tls.key) {
+        (Some(cert), Some(key)) => (
+            CertificateDer::pem_file_iter(cert)?.collect::<Result<Vec<_>, _>>()?,
+            PrivateKeyDer::from_pem_file(key)?,
+        ),
+        _ if tls.is_insecure() => {
+            let certified_key = rcgen::generate_simple_self_signed(vec![
+                "localhost".to_owned(),
+                fusen_common::net::get_ip(),
+            ])?;
+            warn!("http3 server use self-signed certificate");
+            (
+                vec![certified_key.cert.der().clone()],
+                PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(
+                    certified_key.key_pair.serialize_der(),
+                )),
+            )
+        }
+        _ => return Err("http3 server must set tls cert and key".into()),
+    };
+    let mut tls_config = rustls::ServerConfig::builder_with_provider(provider())
+        .with_protocol_versions(&[&rustls::version::TLS13])?
+        .with_no_client_auth()
+        .with_single_cert(certs, key)?;
+    tls_config.alpn_protocols = vec![ALPN_H3.to_vec()];
+    let mut server_config =
+        quinn::ServerConfig::with_crypto(Arc::new(QuicServerConfig::try_from(tls_config)?));
+    let mut transport = quinn::TransportConfig::default();
+    transport.keep_alive_interval(Some(Duration::from_secs(30)));
+    server_config.transport_config(Arc::new(transport));
+    Ok(server_config)
+}
+
+pub fn http3_client_config(tls: &TlsConfig) -> Result<quinn::ClientConfig, crate::Error> {
+    let provider = provider();
+    let builder = rustls::ClientConfig::builder_with_provider(provider.clone())
+        .with_protocol_versions(&[&rustls::version::TLS13])?;
+    let mut tls_config = if tls.is_insecure() {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(SkipServerVerification(provider)))
+            .with_no_client_auth()
+    } else {
+        builder
+            .with_root_certificates(get_root_store(tls)?)
+            .with_no_client_auth()
+    };
+    tls_config.alpn_protocols = vec![ALPN_H3.to_vec()];
+    let mut client_config =
+        quinn::ClientConfig::new(Arc::new(QuicClientConfig::try_from(tls_config)?));
+    let mut transport = quinn::TransportConfig::default();
+    transport.keep_alive_interval(Some(Duration::from_secs(30)));
+    client_config.transport_config(Arc::new(transport));
+    Ok(client_config)
+}
+
+fn get_root_store(tls: &TlsConfig) -> Result<RootCertStore, crate::Error> {
+    let mut root_store = RootCertStore::empty();
+    match &tls.ca {
+        Some(ca) => {
+            for cert in CertificateDer::pem_file_iter(ca)? {
+                root_store.add(cert?)?;
+            }
+        }
+        None => {
+            let native_certs = rustls_native_certs::load_native_certs();
+            for err in native_certs.errors {
+                warn!("load native certs error : {:?}", err);
+            }
+            root_store.add_parsable_certificates(native_certs.certs);
+        }
+    }
+    if root_store.is_empty() {
+        return Err("http3 client has no root certificates".into());
+    }
+    Ok(root_store)
+}
+
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(ring::default_provider())
+}
+
+#[derive(Debug)]
+struct SkipServerVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for SkipServerVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
@@ -1,5 +1,7 @@
+use super::hessian::HessianDecoder;
 use bytes::Bytes;
-use prost::Message;
+use fusen_common::error::{FusenError, JavaException, StatusCode, BUSINESS_ERROR_TYPE_URL};
+use serde_json::Value;
 
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -73,29 +75,114 @@ impl TripleResponseWrapper {
     }
 }
 
+//Dubbo3 Triple标识响应体为TripleExceptionWrapper
+pub const TRI_EXCEPTION_CODE: &str = "tri-exception-code";
+pub const RPC_EXCEPTION: &str = "org.apache.dubbo.rpc.RpcException";
+//异常链最大解析深度
+const MAX_CAUSE_DEPTH: usize = 16;
+
+//Java消费端收到后会反序列化为RpcException重新抛出,code与RpcException中的异常码对应
+pub fn get_rpc_exception_code(err: &FusenError) -> i32 {
+    let is_business = matches!(err, FusenError::Status(status)
+        if status.get_details().iter().any(|e| e.get_type_url() == BUSINESS_ERROR_TYPE_URL));
+    match err.code() {
+        _ if is_business => 3,
+        StatusCode::DeadlineExceeded => 2,
+        StatusCode::PermissionDenied | StatusCode::Unauthenticated => 4,
+        StatusCode::ResourceExhausted => 7,
+        StatusCode::Unimplemented => 11,
+        StatusCode::InvalidArgument => 12,
+        _ => 0,
+    }
+}
+
 impl TripleExceptionWrapper {
-    pub fn get_buf(strs: String) -> Vec<u8> {
-        let mut trip = TripleExceptionWrapper {
-            language: Default::default(),
-            serialization: "fastjson".to_string(),
-            class_name: Default::default(),
-            data: Default::default(),
-        };
-        trip.data = strs.as_bytes().to_vec();
-        get_buf(trip.encode_to_vec())
+    pub fn from_error(err: &FusenError) -> Self {
+        let data = serde_json::json!({
+            "@type": RPC_EXCEPTION,
+            "code": get_rpc_exception_code(err),
+            "message": err.to_string(),
+        });
+        TripleExceptionWrapper {
+            language: "rust".to_owned(),
+            serialization: "fastjson".to_owned(),
+            class_name: RPC_EXCEPTION.to_owned(),
+            data: data.to_string().into_bytes(),
+        }
     }
+
     pub fn get_err_info(&self) -> String {
-        serde_json::from_slice(&self.data[..]).unwrap_or("rpc error".to_owned())
+        self.to_java_exception().to_string()
+    }
+
+    //按照序列化方式解析Java异常,无法解析时只保留异常类名
+    pub fn to_java_exception(&self) -> JavaException {
+        let value = match self.serialization.as_str() {
+            "fastjson" | "fastjson2" => serde_json::from_slice::<Value>(&self.data).ok(),
+            "hessian2" => HessianDecoder::new(&self.data).read_value_with_type().ok(),
+            _ => None,
+        };
+        value
+            .and_then(|value| get_java_exception(&value, 0))
+            .map(|mut exception| {
+                if exception.get_class_name().is_empty() {
+                    exception.set_class_name(self.class_name.clone());
+                }
+                exception
+            })
+            .unwrap_or_else(|| JavaException::new(self.class_name.clone(), None))
     }
 }
 
-pub fn get_buf(mut data: Vec<u8>) -> Vec<u8> {
-    let mut len = data.len();
-    let mut u8_array = vec![0_u8; 5];
-    for idx in (1..5).rev() {
-        u8_array[idx] = len as u8;
-        len >>= 8;
+//解析fastjson/hessian2反序列化后的Throwable
+pub fn get_java_exception(value: &Value, depth: usize) -> Option<JavaException> {
+    let value = value.as_object()?;
+    let class_name = value
+        .get("@type")
+        .and_then(|e| e.as_str())
+        .unwrap_or_default()
+        .to_owned();
+    let message = ["detailMessage", "message", "localizedMessage"]
+        .iter()
+        .find_map(|key| value.get(*key).and_then(|e| e.as_str()))
+        .map(|e| e.to_owned());
+    let mut exception = JavaException::new(class_name, message);
+    //cause指向自身时fastjson输出$ref,hessian2输出为引用
+    if depth < MAX_CAUSE_DEPTH {
+        if let Some(cause) = value
+            .get("cause")
+            .filter(|e| e.get("$ref").is_none())
+            .and_then(|e| get_java_exception(e, depth + 1))
+        {
+            exception.set_cause(Some(Box::new(cause)));
+        }
     }
-    u8_array.append(&mut data);
-    u8_array
+    Some(exception)
+}
+
+#[test]
+fn test() {
+    let wrapper = TripleExceptionWrapper {
+        language: "java".to_owned(),
+        serialization: "fastjson2".to_owned(),
+        class_name: "java.lang.IllegalStateException".to_owned(),
+        data: br#"{"@type":"java.lang.IllegalStateException","message":"bad state","cause":{"@type":"java.io.IOException","message":"closed","cause":{"$ref":"@"}},"stackTrace":[]}"#.to_vec(),
+    };
+    let exception = wrapper.to_java_exception();
+    assert_eq!(
+        exception.to_string(),
+        "java.lang.IllegalStateException: bad state"
+    );
+    let cause = exception.get_cause().as_ref().unwrap();
+    assert_eq!(cause.get_class_name(), "java.io.IOException");
+    assert!(cause.get_cause().is_none());
+    let err = FusenError::from(exception.clone());
+    assert_eq!(err.java_exception(), Some(exception));
+    let wrapper = TripleExceptionWrapper::from_error(&FusenError::status(
+        StatusCode::InvalidArgument,
+        "bad arg",
+    ));
+    let data: Value = serde_json::from_slice(&wrapper.data).unwrap();
+    assert_eq!(data["code"], 12);
+    assert_eq!(data["message"], "bad arg");
 }